@@ -0,0 +1,32 @@
+//! Times the AI's search from the opening and from positions part way through
+//! a game
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use tic_tac_toe::{ai, state::BoardState};
+
+fn best_move(c: &mut Criterion) {
+    let mut group = c.benchmark_group("best_move");
+
+    let positions = [
+        ("empty", Vec::new()),
+        ("center", vec![(1, 1)]),
+        ("center corner", vec![(1, 1), (0, 0)]),
+        ("opposite corners", vec![(0, 0), (1, 1), (2, 2)]),
+        ("forced block", vec![(0, 0), (1, 1), (1, 0)]),
+    ];
+    for (name, moves) in positions.iter() {
+        let board = BoardState::from_moves(moves).unwrap();
+        group.bench_function(*name, |b| b.iter(|| ai::best_move(black_box(&board))));
+    }
+
+    let mut board = BoardState::with_size(4);
+    for &coords in [(1, 1), (2, 2), (1, 2), (2, 1), (0, 0), (3, 3)].iter() {
+        board.play(coords).unwrap();
+    }
+    group.bench_function("4x4", |b| b.iter(|| ai::best_move(black_box(&board))));
+
+    group.finish();
+}
+
+criterion_group!(benches, best_move);
+criterion_main!(benches);
@@ -0,0 +1,89 @@
+//! Compares playing out games on a `BoardState` and on a `Bitboard`, checking
+//! for a result after every move as a search would
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use tic_tac_toe::{
+    bitboard::Bitboard,
+    state::{BoardState, GameResult},
+};
+
+/// A game's name, board size, and moves
+type Game = (&'static str, usize, &'static [(usize, usize)]);
+
+/// A drawn game on a 3 by 3 board, and one that X wins along the diagonal of
+/// a nearly full 4 by 4 board
+const GAMES: [Game; 2] = [
+    (
+        "3x3",
+        3,
+        &[
+            (1, 1),
+            (0, 0),
+            (2, 2),
+            (0, 2),
+            (0, 1),
+            (2, 1),
+            (1, 0),
+            (1, 2),
+            (2, 0),
+        ],
+    ),
+    (
+        "4x4",
+        4,
+        &[
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (3, 0),
+            (1, 1),
+            (0, 1),
+            (3, 1),
+            (2, 1),
+            (0, 2),
+            (1, 2),
+            (2, 2),
+            (3, 2),
+            (1, 3),
+            (0, 3),
+            (3, 3),
+            (2, 3),
+        ],
+    ),
+];
+
+fn play_out(c: &mut Criterion) {
+    let mut group = c.benchmark_group("play_out");
+
+    for &(name, size, moves) in GAMES.iter() {
+        group.bench_function(format!("BoardState {}", name), |b| {
+            b.iter(|| {
+                let mut board = BoardState::with_size(size);
+                for &coords in moves {
+                    board.play(black_box(coords)).unwrap();
+                    if board.status() != GameResult::InProgress {
+                        break;
+                    }
+                }
+                board
+            })
+        });
+        group.bench_function(format!("Bitboard {}", name), |b| {
+            b.iter(|| {
+                let mut board = Bitboard::with_win_length(size, size);
+                for &coords in moves {
+                    board.play(black_box(coords)).unwrap();
+                    if board.status() != GameResult::InProgress {
+                        break;
+                    }
+                }
+                board
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, play_out);
+criterion_main!(benches);
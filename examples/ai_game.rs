@@ -0,0 +1,32 @@
+//! Plays a hard AI as X against a medium AI as O, printing the board after
+//! every move
+
+use rand::{rngs::StdRng, SeedableRng};
+use tic_tac_toe::{
+    ai::{self, Difficulty},
+    state::{BoardState, GameResult, Player},
+};
+
+fn main() -> anyhow::Result<()> {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut board = BoardState::new();
+
+    while board.status() == GameResult::InProgress {
+        let difficulty = match board.next() {
+            Player::X => Difficulty::Hard,
+            Player::O => Difficulty::Medium,
+            Player::Z => unreachable!("standard boards have two players"),
+        };
+        let coords = ai::choose_move(&board, difficulty, &mut rng);
+        println!("{} plays {}, {}", board.next(), coords.0, coords.1);
+        board.play(coords)?;
+        println!("\n{}\n", board.render().with_highlight(true));
+    }
+
+    match board.status() {
+        GameResult::Win(player) => println!("{} wins!", player),
+        _ => println!("Draw!"),
+    }
+
+    Ok(())
+}
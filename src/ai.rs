@@ -0,0 +1,1510 @@
+use core::str::FromStr;
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+};
+
+use anyhow::{self, anyhow as anyhow_error};
+use rand::{seq::IteratorRandom, Rng};
+
+use crate::state::{BoardState, GameResult, Player, TileState, BOARD_SIZE};
+
+/// Score of a win on the very next move; deeper wins score less
+const WIN: i32 = 100;
+
+/// The most empty tiles a board may have for [`strong_move`] to search its
+/// whole game tree; the standard board is the largest that searches quickly
+const FULL_SEARCH: usize = BOARD_SIZE * BOARD_SIZE;
+
+/// How many moves ahead [`strong_move`] looks on boards too big to search
+/// fully
+const LOOKAHEAD: usize = 2;
+
+/// How strongly Monte Carlo tree search favors rarely visited moves over
+/// moves that have done well
+const EXPLORATION: f64 = core::f64::consts::SQRT_2;
+
+/// Optimal replies to every standard opening of up to two moves, keyed by the
+/// [canonical](BoardState::canonical) compact encoding of the position, with
+/// each reply in the coordinates of the canonical board
+const BOOK: [(&str, (usize, usize)); 16] = [
+    (".........", (0, 0)),
+    ("........X", (1, 1)),
+    (".......OX", (2, 0)),
+    (".......X.", (1, 0)),
+    (".......XO", (0, 0)),
+    ("......O.X", (0, 0)),
+    (".....O.X.", (1, 1)),
+    (".....OX..", (0, 0)),
+    (".....XO..", (2, 2)),
+    ("....O...X", (0, 0)),
+    ("....O..X.", (0, 0)),
+    ("....X....", (0, 0)),
+    ("....X...O", (0, 0)),
+    ("....X..O.", (0, 0)),
+    ("...O.X...", (0, 0)),
+    ("..O...X..", (0, 0)),
+];
+
+/// Maps the coordinates of a tile to where a symmetry of the board moves it,
+/// given the index of the board's last row and column
+type Symmetry = fn((usize, usize), usize) -> (usize, usize);
+
+/// The eight rotations and reflections of a board
+const SYMMETRIES: [Symmetry; 8] = [
+    |(x, y), _| (x, y),
+    |(x, y), last| (last - y, x),
+    |(x, y), last| (last - x, last - y),
+    |(x, y), last| (y, last - x),
+    |(x, y), last| (last - x, y),
+    |(x, y), last| (last - y, last - x),
+    |(x, y), last| (x, last - y),
+    |(x, y), _| (y, x),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Difficulty {
+    /// Plays uniformly random legal moves
+    Easy,
+    /// Plays optimally half of the time, and randomly otherwise
+    Medium,
+    /// Always plays optimally, or as well as looking a few moves ahead allows
+    /// on boards too big to search fully
+    Hard,
+}
+
+impl FromStr for Difficulty {
+    type Err = anyhow::Error;
+
+    /// Parses a difficulty by name, ignoring case
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "easy" => Ok(Self::Easy),
+            "medium" => Ok(Self::Medium),
+            "hard" => Ok(Self::Hard),
+            _ => Err(anyhow_error!(
+                "unknown difficulty {:?}; expected easy, medium, or hard",
+                s
+            )),
+        }
+    }
+}
+
+/// Chooses a move for `state.next()` at the given difficulty, drawing any
+/// randomness from `rng`.
+///
+/// # Panics
+///
+/// Panics if the game is already over.
+pub fn choose_move(
+    state: &BoardState,
+    difficulty: Difficulty,
+    rng: &mut impl Rng,
+) -> (usize, usize) {
+    assert_eq!(
+        state.status(),
+        GameResult::InProgress,
+        "cannot choose a move once the game is over"
+    );
+
+    let optimal = match difficulty {
+        Difficulty::Easy => false,
+        Difficulty::Medium => rng.gen_bool(0.5),
+        Difficulty::Hard => true,
+    };
+
+    let coords = if optimal {
+        book_move(state).or_else(|| strong_move(state)).unwrap()
+    } else {
+        state.available_moves().choose(rng).unwrap()
+    };
+
+    #[cfg(feature = "logging")]
+    log::debug!(
+        "{:?} AI chose {:?} for {}, {}",
+        difficulty,
+        coords,
+        state.next(),
+        if optimal { "optimally" } else { "at random" }
+    );
+
+    coords
+}
+
+/// Plays a game on a standard board between an AI at difficulty `x` and one
+/// at difficulty `o`, drawing any randomness from `rng`, and returns the
+/// final board
+pub fn play_ai_game(x: Difficulty, o: Difficulty, rng: &mut impl Rng) -> BoardState {
+    let mut state = BoardState::new();
+
+    while state.status() == GameResult::InProgress {
+        let difficulty = match state.next() {
+            Player::X => x,
+            Player::O => o,
+            Player::Z => unreachable!("standard boards have two players"),
+        };
+        let coords = choose_move(&state, difficulty, rng);
+        #[cfg(feature = "logging")]
+        log::debug!("move played: {} at {:?}", state.next(), coords);
+        state.play(coords).unwrap();
+    }
+
+    #[cfg(feature = "logging")]
+    log::debug!("AI game ended: {:?}", state.status());
+
+    state
+}
+
+/// Returns an optimal move for `state.next()`, or `None` if the game is over.
+/// With three players, the other two are assumed to play together against
+/// `state.next()`.
+///
+/// Among equally good moves, prefers the one that wins soonest or loses
+/// latest, breaking any remaining ties by row-major order.
+pub fn best_move(state: &BoardState) -> Option<(usize, usize)> {
+    Search::with_table().best_move(state, true)
+}
+
+/// Returns the best move for `state.next()` that can be found quickly, or
+/// `None` if the game is over: an optimal one as [`best_move`] finds if few
+/// enough tiles are empty, and otherwise [`lookahead_move`]'s
+pub fn strong_move(state: &BoardState) -> Option<(usize, usize)> {
+    if state.remaining() <= FULL_SEARCH {
+        best_move(state)
+    } else {
+        lookahead_move(state, LOOKAHEAD)
+    }
+}
+
+/// Returns the move for `state.next()` that scores best by [`evaluate`]
+/// after `depth` moves of minimax, or `None` if the game is over. Ties go to
+/// the first move in row-major order.
+///
+/// Only tiles next to a piece already played are considered, or the center
+/// of an empty board, which keeps the search fast on boards like gomoku's
+/// where play stays in one area.
+pub fn lookahead_move(state: &BoardState, depth: usize) -> Option<(usize, usize)> {
+    if state.status() != GameResult::InProgress {
+        return None;
+    }
+
+    let root = state.next();
+    let mut best = None;
+
+    for coords in nearby_moves(state) {
+        // Only a strictly better move can replace the best so far
+        let alpha = best.map_or(i32::MIN, |(best_score, _)| best_score);
+        let score = lookahead(
+            &after(state, coords),
+            depth.saturating_sub(1),
+            root,
+            alpha,
+            i32::MAX,
+        );
+
+        if best.is_none_or(|(best_score, _)| score > best_score) {
+            best = Some((score, coords));
+        }
+    }
+
+    best.map(|(_, coords)| coords)
+}
+
+/// Scores `state` for `root` by alpha-beta search `depth` moves deep over
+/// [`nearby_moves`], valuing positions at the limit by [`evaluate`]
+fn lookahead(state: &BoardState, depth: usize, root: Player, mut alpha: i32, mut beta: i32) -> i32 {
+    match state.status() {
+        GameResult::Draw => return 0,
+        GameResult::InProgress if depth > 0 => {}
+        _ => return evaluate(state, root),
+    }
+
+    let maximizing = state.next() == root;
+    let mut best = if maximizing { i32::MIN } else { i32::MAX };
+
+    for coords in nearby_moves(state) {
+        let score = lookahead(&after(state, coords), depth - 1, root, alpha, beta);
+
+        if maximizing {
+            best = best.max(score);
+            alpha = alpha.max(best);
+        } else {
+            best = best.min(score);
+            beta = beta.min(best);
+        }
+
+        if alpha >= beta {
+            break;
+        }
+    }
+
+    best
+}
+
+/// The empty tiles, in row-major order, next to a played tile in any of the
+/// eight directions, or the center of an empty board
+fn nearby_moves(state: &BoardState) -> Vec<(usize, usize)> {
+    if state.is_empty() {
+        return vec![(state.size() / 2, state.size() / 2)];
+    }
+
+    let last = state.size() - 1;
+    state
+        .available_moves()
+        .filter(|&(x, y)| {
+            (x.saturating_sub(1)..=(x + 1).min(last)).any(|nx| {
+                (y.saturating_sub(1)..=(y + 1).min(last))
+                    .any(|ny| state[(nx, ny)] != TileState::Empty)
+            })
+        })
+        .collect()
+}
+
+/// Returns the opening book's optimal move for `state.next()`, or `None` if
+/// `state` isn't one of the first few positions of a standard game
+pub fn book_move(state: &BoardState) -> Option<(usize, usize)> {
+    if state.size() != BOARD_SIZE
+        || state.win_length() != BOARD_SIZE
+        || state.players() != 2
+        || state.first_player() != Player::X
+        || state.infer_next().ok() != Some(state.next())
+    {
+        return None;
+    }
+
+    let size = state.size();
+    let tiles = state.to_compact().into_bytes();
+    SYMMETRIES.iter().find_map(|map| {
+        let mut image = vec![b'.'; tiles.len()];
+        for (index, &tile) in tiles.iter().enumerate() {
+            let (x, y) = map((index % size, index / size), size - 1);
+            image[x + y * size] = tile;
+        }
+
+        let &(_, reply) = BOOK.iter().find(|(key, _)| key.as_bytes() == image)?;
+        state
+            .available_moves()
+            .find(|&coords| map(coords, size - 1) == reply)
+    })
+}
+
+/// The outcome of a position under optimal play by both sides
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    /// How the game ends; never `InProgress`
+    pub result: GameResult,
+    /// Every move, in row-major order, that keeps that result for the player
+    /// to move, or nothing if the game is already over
+    pub moves: Vec<(usize, usize)>,
+}
+
+/// Solves `state` by searching the whole game tree, failing if it has more
+/// than two players, since then a loss doesn't say who wins
+pub fn solve(state: &BoardState) -> anyhow::Result<Solution> {
+    two_players(state)?;
+    let status = state.status();
+    if status != GameResult::InProgress {
+        return Ok(Solution {
+            result: status,
+            moves: Vec::new(),
+        });
+    }
+
+    let scores = move_scores(state)
+        .into_iter()
+        .map(|(coords, score)| (coords, score.signum()))
+        .collect::<Vec<_>>();
+    let best = scores.iter().map(|&(_, score)| score).max().unwrap();
+
+    Ok(Solution {
+        result: outcome(state, best),
+        moves: scores
+            .into_iter()
+            .filter(|&(_, score)| score == best)
+            .map(|(coords, _)| coords)
+            .collect(),
+    })
+}
+
+/// Fails unless `state` has two players, whose results the solver can name
+fn two_players(state: &BoardState) -> anyhow::Result<()> {
+    match state.players() {
+        2 => Ok(()),
+        players => Err(anyhow_error!(
+            "only two-player games can be solved, not {}-player ones",
+            players
+        )),
+    }
+}
+
+/// A legal move and how the game ends if it is played, under optimal play
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveValue {
+    pub coords: (usize, usize),
+    pub result: GameResult,
+    /// 1 for the best moves, and otherwise one more than the number of moves
+    /// that are better. Of two moves with the same result, the one that wins
+    /// sooner or loses later is better.
+    pub rank: usize,
+}
+
+/// Values every legal move on `state`, best first, breaking ties by row-major
+/// order, or returns nothing if the game is over. Fails like [`solve`] for
+/// more than two players.
+pub fn analyze(state: &BoardState) -> anyhow::Result<Vec<MoveValue>> {
+    two_players(state)?;
+    if state.status() != GameResult::InProgress {
+        return Ok(Vec::new());
+    }
+
+    let mut scores = move_scores(state);
+    scores.sort_by_key(|&(_, score)| Reverse(score));
+    Ok(scores
+        .iter()
+        .map(|&(coords, score)| MoveValue {
+            coords,
+            result: outcome(state, score.signum()),
+            rank: 1 + scores.iter().filter(|&&(_, other)| other > score).count(),
+        })
+        .collect())
+}
+
+/// The exact score of each move on `state`, which must be in progress, in
+/// row-major order
+fn move_scores(state: &BoardState) -> Vec<((usize, usize), i32)> {
+    let mut search = Search::with_table();
+    let root = state.next();
+    state
+        .available_moves()
+        .map(|coords| {
+            let score = search.alphabeta(&after(state, coords), 1, i32::MIN, i32::MAX, root);
+            (coords, score)
+        })
+        .collect()
+}
+
+/// The result of the two-player `state` for a score of the given sign
+fn outcome(state: &BoardState, sign: i32) -> GameResult {
+    match sign {
+        1 => GameResult::Win(state.next()),
+        0 => GameResult::Draw,
+        _ => GameResult::Win(state.next().opponent()),
+    }
+}
+
+/// Searches `depth` moves ahead of `state` and draws the tree explored as a
+/// Graphviz DOT graph, which `dot -Tpng` can render. Each node is labeled
+/// with the compact encoding of its position and each edge with its move.
+///
+/// Nodes are colored by their minimax value for `state.next()`: green for a
+/// good position, red for a bad one, and grey for an even one. Positions at
+/// the depth limit are valued by [`evaluate`].
+pub fn search_dot(state: &BoardState, depth: usize) -> String {
+    let mut dot = String::from("digraph search {\n");
+    dot.push_str("  node [shape=box, style=filled, fontname=monospace];\n");
+    dot_node(state, depth, state.next(), &mut 0, &mut dot);
+    dot.push_str("}\n");
+    dot
+}
+
+/// Writes `state` and the tree below it into `dot`, numbering nodes from
+/// `next_id`, and returns the node's id and value for `root`
+fn dot_node(
+    state: &BoardState,
+    depth: usize,
+    root: Player,
+    next_id: &mut usize,
+    dot: &mut String,
+) -> (usize, i32) {
+    let id = *next_id;
+    *next_id += 1;
+
+    let mut children = Vec::new();
+    let value = match state.status() {
+        GameResult::Draw => 0,
+        GameResult::InProgress if depth > 0 => {
+            for coords in state.available_moves() {
+                let (child, value) = dot_node(&after(state, coords), depth - 1, root, next_id, dot);
+                children.push((coords, child, value));
+            }
+
+            let values = children.iter().map(|&(_, _, value)| value);
+            if state.next() == root {
+                values.max()
+            } else {
+                values.min()
+            }
+            .unwrap()
+        }
+        _ => evaluate(state, root),
+    };
+
+    let color = match value.signum() {
+        1 => "palegreen",
+        0 => "lightgrey",
+        _ => "lightpink",
+    };
+    dot.push_str(&format!(
+        "  n{} [label=\"{}\", fillcolor={}];\n",
+        id,
+        state.to_compact(),
+        color
+    ));
+    for (coords, child, _) in children {
+        dot.push_str(&format!(
+            "  n{} -> n{} [label=\"{}, {}\"];\n",
+            id, child, coords.0, coords.1
+        ));
+    }
+
+    (id, value)
+}
+
+/// Counts the distinct ways the game can play out from `state`, each ending
+/// at the first win or a full board. A finished game counts as one.
+pub fn count_games(state: &BoardState) -> u64 {
+    if state.status() != GameResult::InProgress {
+        return 1;
+    }
+
+    state
+        .available_moves()
+        .map(|coords| count_games(&after(state, coords)))
+        .sum()
+}
+
+/// Iterates over every distinct finished position reachable by play on a
+/// `size` by `size` board with the given win length, with its result.
+///
+/// This explores every reachable position, so it is only practical for very
+/// small boards.
+///
+/// # Panics
+///
+/// Panics if the size or win length are invalid, as
+/// [`BoardState::with_win_length`] does.
+pub fn terminal_positions(
+    size: usize,
+    win_length: usize,
+) -> impl Iterator<Item = (BoardState, GameResult)> {
+    let start = BoardState::with_win_length(size, win_length);
+    let mut seen = HashSet::new();
+    let mut unexplored = vec![start];
+    let mut terminals = Vec::new();
+
+    while let Some(state) = unexplored.pop() {
+        if seen.contains(&state) {
+            continue;
+        }
+
+        match state.status() {
+            GameResult::InProgress => {
+                unexplored.extend(state.available_moves().map(|coords| after(&state, coords)));
+            }
+            result => terminals.push((state.clone(), result)),
+        }
+
+        seen.insert(state);
+    }
+
+    terminals.into_iter()
+}
+
+/// A game tree search, counting the positions it visits
+#[derive(Debug, Default)]
+struct Search {
+    nodes: usize,
+    /// Scores of positions already searched, if the search caches them
+    table: Option<HashMap<BoardState, (i32, Bound)>>,
+}
+
+/// How a cached alpha-beta score relates to the position's true score
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Bound {
+    Exact,
+    /// The true score is at least this
+    Lower,
+    /// The true score is at most this
+    Upper,
+}
+
+impl Search {
+    /// Creates a search that caches the scores of positions it has seen, so
+    /// that positions reached by several move orders are only searched once.
+    ///
+    /// Scores depend on how deep a position is, but any two paths to the same
+    /// position have the same length, so a table is only valid for searches
+    /// from a single root.
+    fn with_table() -> Self {
+        Search {
+            table: Some(HashMap::new()),
+            ..Default::default()
+        }
+    }
+
+    /// Finds the move [`best_move`] would, pruning the search with alpha-beta
+    /// if `pruned` and exploring the full tree with minimax otherwise
+    fn best_move(&mut self, state: &BoardState, pruned: bool) -> Option<(usize, usize)> {
+        if state.won().is_some() {
+            return None;
+        }
+
+        let root = state.next();
+        let mut best = None;
+
+        for coords in state.available_moves() {
+            let state = after(state, coords);
+            let score = if pruned {
+                // Only a strictly better move can replace the best so far
+                let alpha = best.map_or(i32::MIN, |(best_score, _)| best_score);
+                self.alphabeta(&state, 1, alpha, i32::MAX, root)
+            } else {
+                self.minimax(&state, 1, root)
+            };
+
+            if best.is_none_or(|(best_score, _)| score > best_score) {
+                best = Some((score, coords));
+            }
+        }
+
+        best.map(|(_, coords)| coords)
+    }
+
+    /// Scores `state`, `depth` moves into the search, for `root`, the player
+    /// to move at the root of the search, against everyone else
+    fn minimax(&mut self, state: &BoardState, depth: i32, root: Player) -> i32 {
+        self.nodes += 1;
+
+        if let Some(score) = terminal_score(state, depth, root) {
+            return score;
+        }
+
+        let scores = state
+            .available_moves()
+            .map(|coords| self.minimax(&after(state, coords), depth + 1, root));
+
+        if state.next() == root {
+            scores.max()
+        } else {
+            scores.min()
+        }
+        .unwrap()
+    }
+
+    /// Scores `state` like [`minimax`](Self::minimax), but skips moves once
+    /// they cannot bring the score within `alpha..beta`. Scores outside that
+    /// window are only bounds: a score of at most `alpha` means the position
+    /// is no better than `alpha`, and likewise for `beta`.
+    fn alphabeta(
+        &mut self,
+        state: &BoardState,
+        depth: i32,
+        mut alpha: i32,
+        mut beta: i32,
+        root: Player,
+    ) -> i32 {
+        self.nodes += 1;
+
+        if let Some(score) = terminal_score(state, depth, root) {
+            return score;
+        }
+
+        if let Some(&(score, bound)) = self.table.as_ref().and_then(|table| table.get(state)) {
+            match bound {
+                Bound::Exact => return score,
+                Bound::Lower => alpha = alpha.max(score),
+                Bound::Upper => beta = beta.min(score),
+            }
+
+            if alpha >= beta {
+                return score;
+            }
+        }
+
+        let window = (alpha, beta);
+        let maximizing = state.next() == root;
+        let mut best = if maximizing { i32::MIN } else { i32::MAX };
+
+        for coords in state.available_moves() {
+            let score = self.alphabeta(&after(state, coords), depth + 1, alpha, beta, root);
+
+            if maximizing {
+                best = best.max(score);
+                alpha = alpha.max(best);
+            } else {
+                best = best.min(score);
+                beta = beta.min(best);
+            }
+
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        if let Some(table) = &mut self.table {
+            let bound = if best <= window.0 {
+                Bound::Upper
+            } else if best >= window.1 {
+                Bound::Lower
+            } else {
+                Bound::Exact
+            };
+            table.insert(state.clone(), (best, bound));
+        }
+
+        best
+    }
+}
+
+/// Scores `state` for `root` as [`Search::minimax`] would if the game is over
+fn terminal_score(state: &BoardState, depth: i32, root: Player) -> Option<i32> {
+    match state.status() {
+        GameResult::InProgress => None,
+        GameResult::Win(winner) if winner == root => Some(WIN - depth),
+        GameResult::Win(_) => Some(depth - WIN),
+        GameResult::Draw => Some(0),
+    }
+}
+
+/// Chooses a move for `state.next()` by Monte Carlo tree search, running
+/// `iterations` random playouts drawn from `rng` and returning the most
+/// explored move. Ties go to the first such move in row-major order.
+///
+/// Unlike [`best_move`], the cost depends only on `iterations`, not on the
+/// size of the game tree, so this suits boards too large to search fully.
+///
+/// # Panics
+///
+/// Panics if the game is already over or `iterations` is 0.
+pub fn mcts_move(state: &BoardState, iterations: usize, rng: &mut impl Rng) -> (usize, usize) {
+    assert_eq!(
+        state.status(),
+        GameResult::InProgress,
+        "cannot choose a move once the game is over"
+    );
+    assert!(iterations > 0, "cannot search without any iterations");
+
+    let mut tree = vec![MctsNode::new(state.clone(), None, None)];
+
+    for _ in 0..iterations {
+        // Select a promising node that still has untried moves
+        let mut node = 0;
+        while tree[node].untried.is_empty() && !tree[node].children.is_empty() {
+            let parent_visits = tree[node].visits;
+            node = *tree[node]
+                .children
+                .iter()
+                .max_by(|&&a, &&b| {
+                    tree[a]
+                        .uct(parent_visits)
+                        .total_cmp(&tree[b].uct(parent_visits))
+                })
+                .unwrap();
+        }
+
+        // Expand it by one of those moves
+        if !tree[node].untried.is_empty() {
+            let index = rng.gen_range(0..tree[node].untried.len());
+            let coords = tree[node].untried.swap_remove(index);
+            let child = MctsNode::new(after(&tree[node].state, coords), Some(coords), Some(node));
+            tree.push(child);
+            let child = tree.len() - 1;
+            tree[node].children.push(child);
+            node = child;
+        }
+
+        // Play out the rest of the game at random
+        let mut playout = tree[node].state.clone();
+        let result = loop {
+            match playout.status() {
+                GameResult::InProgress => {
+                    let coords = playout.available_moves().choose(rng).unwrap();
+                    playout.play(coords).unwrap();
+                }
+                result => break result,
+            }
+        };
+
+        // Credit each node on the path with the result
+        let mut next = Some(node);
+        while let Some(node) = next {
+            let node = &mut tree[node];
+            node.visits += 1;
+            // Nodes are scored for the player who moved into them
+            let mover = node.state.next().previous_player(node.state.players());
+            node.reward += match result {
+                GameResult::Win(winner) if winner == mover => 1.0,
+                GameResult::Win(_) => 0.0,
+                _ => 0.5,
+            };
+            next = node.parent;
+        }
+    }
+
+    tree[0]
+        .children
+        .iter()
+        .map(|&child| &tree[child])
+        .max_by_key(|child| (child.visits, Reverse(child.coords)))
+        .and_then(|child| child.coords)
+        .unwrap()
+}
+
+/// A position in a Monte Carlo search tree
+#[derive(Debug)]
+struct MctsNode {
+    state: BoardState,
+    /// The move that led here from the parent
+    coords: Option<(usize, usize)>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Moves from here that have no node yet
+    untried: Vec<(usize, usize)>,
+    visits: u32,
+    /// The total score of playouts through here, counting wins for the player
+    /// who moved into this position as 1 and draws as 1/2
+    reward: f64,
+}
+
+impl MctsNode {
+    fn new(state: BoardState, coords: Option<(usize, usize)>, parent: Option<usize>) -> Self {
+        let untried = match state.status() {
+            GameResult::InProgress => state.available_moves().collect(),
+            _ => Vec::new(),
+        };
+
+        MctsNode {
+            state,
+            coords,
+            parent,
+            children: Vec::new(),
+            untried,
+            visits: 0,
+            reward: 0.0,
+        }
+    }
+
+    /// The upper confidence bound used to select among siblings
+    fn uct(&self, parent_visits: u32) -> f64 {
+        let visits = f64::from(self.visits);
+        self.reward / visits + EXPLORATION * (f64::from(parent_visits).ln() / visits).sqrt()
+    }
+}
+
+/// Scores `state` from `player`'s point of view without searching ahead.
+///
+/// A win scores 100 and a loss -100. Otherwise, the score is the sum, for
+/// `player` and negated for their opponents together, of:
+///
+/// - 10 for each run of winning length that holds all but one of the
+///   player's tiles and none of any opponent's, which is a threat to win
+/// - 1 for each other run holding some of the player's tiles and none of
+///   any opponent's, which could still become a win
+/// - 3 for each center tile held: the middle tile, or the middle four on an
+///   even board
+/// - 2 for each corner held
+///
+/// clamped so that only wins and losses reach ±100.
+pub fn evaluate(state: &BoardState, player: Player) -> i32 {
+    match state.status() {
+        GameResult::Win(winner) if winner == player => return WIN,
+        GameResult::Win(_) => return -WIN,
+        _ => {}
+    }
+
+    let size = state.size();
+    let mut score = 0;
+
+    for line in state.iter_lines() {
+        for run in line.windows(state.win_length()) {
+            let count = |tile| run.iter().filter(|&&coords| state[coords] == tile).count();
+            let ours = count(player.into());
+            let theirs = run.len() - ours - count(TileState::Empty);
+            let open_score = |held| match held {
+                0 => 0,
+                held if held + 1 == run.len() => 10,
+                _ => 1,
+            };
+
+            if theirs == 0 {
+                score += open_score(ours);
+            } else if ours == 0 {
+                score -= open_score(theirs);
+            }
+        }
+    }
+
+    let middle = [(size - 1) / 2, size / 2];
+    let corners = [(0, 0), (size - 1, 0), (0, size - 1), (size - 1, size - 1)];
+    for y in 0..size {
+        for x in 0..size {
+            let weight = if middle.contains(&x) && middle.contains(&y) {
+                3
+            } else if corners.contains(&(x, y)) {
+                2
+            } else {
+                continue;
+            };
+
+            match Option::<Player>::from(state[(x, y)]) {
+                Some(owner) if owner == player => score += weight,
+                Some(_) => score -= weight,
+                None => {}
+            }
+        }
+    }
+
+    score.clamp(1 - WIN, WIN - 1)
+}
+
+/// Returns the empty tiles, in row-major order, where `player` would win at
+/// once, whether or not it is `player`'s turn
+pub fn winning_moves(state: &BoardState, player: Player) -> Vec<(usize, usize)> {
+    if state.status() != GameResult::InProgress {
+        return Vec::new();
+    }
+
+    state
+        .available_moves()
+        .filter(|&coords| {
+            let mut state = state.clone();
+            state[coords] = player.into();
+            state.won() == Some(player)
+        })
+        .collect()
+}
+
+/// Whether `player` taking `coords` would leave them two or more ways to win
+/// on their next move, which the opponent cannot both block
+pub fn creates_fork(state: &BoardState, coords: (usize, usize), player: Player) -> bool {
+    if state[coords] != TileState::Empty {
+        return false;
+    }
+
+    let mut state = state.clone();
+    state[coords] = player.into();
+    winning_moves(&state, player).len() >= 2
+}
+
+/// Returns a copy of `state` with `coords` played
+fn after(state: &BoardState, coords: (usize, usize)) -> BoardState {
+    let mut state = state.clone();
+    state.play(coords).unwrap();
+    state
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    /// Plays out `state` with both sides at `difficulty`, returning the final
+    /// board
+    fn self_play(mut state: BoardState, difficulty: Difficulty, rng: &mut impl Rng) -> BoardState {
+        while state.status() == GameResult::InProgress {
+            let coords = choose_move(&state, difficulty, rng);
+            assert_eq!(state[coords], TileState::Empty);
+            state.play(coords).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn difficulty_from_str() {
+        assert_eq!("easy".parse::<Difficulty>().unwrap(), Difficulty::Easy);
+        assert_eq!("Medium".parse::<Difficulty>().unwrap(), Difficulty::Medium);
+        assert_eq!("HARD".parse::<Difficulty>().unwrap(), Difficulty::Hard);
+        assert!("impossible".parse::<Difficulty>().is_err());
+    }
+
+    #[test]
+    fn play_ai_game() {
+        for seed in 0..3 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let state = super::play_ai_game(Difficulty::Hard, Difficulty::Hard, &mut rng);
+            assert!(state.drawn());
+        }
+
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let state = super::play_ai_game(Difficulty::Hard, Difficulty::Easy, &mut rng);
+            assert_ne!(state.won(), Some(Player::O));
+            assert_ne!(state.status(), GameResult::InProgress);
+        }
+    }
+
+    #[test]
+    fn easy() {
+        for seed in 0..200 {
+            self_play(
+                BoardState::new(),
+                Difficulty::Easy,
+                &mut StdRng::seed_from_u64(seed),
+            );
+        }
+    }
+
+    #[test]
+    fn medium() {
+        let mut state = BoardState::new();
+        state.play((1, 1)).unwrap();
+        for seed in 0..20 {
+            self_play(
+                state.clone(),
+                Difficulty::Medium,
+                &mut StdRng::seed_from_u64(seed),
+            );
+        }
+    }
+
+    #[test]
+    fn hard() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let boards: [&[(usize, usize)]; 3] = [
+            &[(0, 0), (0, 1), (1, 0), (1, 1)],
+            &[(0, 0), (2, 0), (1, 1)],
+            &[(1, 1), (0, 0), (2, 2)],
+        ];
+        for moves in boards.iter() {
+            let mut state = BoardState::new();
+            for &coords in moves.iter() {
+                state.play(coords).unwrap();
+            }
+            assert_eq!(
+                choose_move(&state, Difficulty::Hard, &mut rng),
+                best_move(&state).unwrap()
+            );
+        }
+        assert_eq!(
+            self_play(BoardState::new(), Difficulty::Hard, &mut rng).won(),
+            None
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn choose_move_game_over() {
+        let mut state = BoardState::new();
+        for &coords in [(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)].iter() {
+            state.play(coords).unwrap();
+        }
+        choose_move(&state, Difficulty::Easy, &mut StdRng::seed_from_u64(0));
+    }
+
+    #[test]
+    fn book_move() {
+        let corners_and_center = [(0, 0), (2, 0), (1, 1), (0, 2), (2, 2)];
+        let reply = super::book_move(&BoardState::new()).unwrap();
+        assert!(corners_and_center.contains(&reply));
+
+        let center = BoardState::from_moves(&[(1, 1)]).unwrap();
+        let reply = super::book_move(&center).unwrap();
+        assert!(corners_and_center.contains(&reply));
+        assert_ne!(reply, (1, 1));
+
+        assert_eq!(
+            super::book_move(&BoardState::from_moves(&[(1, 1), (0, 0), (2, 2)]).unwrap()),
+            None
+        );
+        assert_eq!(super::book_move(&BoardState::with_size(4)), None);
+        assert_eq!(
+            super::book_move(&BoardState::from_notation("1. O:b2 X:a1").unwrap()),
+            None
+        );
+
+        // A tile set by hand leaves it unclear whose turn it is
+        let mut board = BoardState::new();
+        board[(0, 0)] = TileState::X;
+        assert_eq!(super::book_move(&board), None);
+    }
+
+    #[test]
+    fn book_agrees_with_minimax() {
+        let mut positions = vec![BoardState::new()];
+        let mut covered = 0;
+        while let Some(state) = positions.pop() {
+            let reply = super::book_move(&state).unwrap();
+            assert!(
+                super::solve(&state).unwrap().moves.contains(&reply),
+                "{:?} is not optimal on\n{}",
+                reply,
+                state
+            );
+            covered += 1;
+
+            if state.history().len() < 2 {
+                positions.extend(state.available_moves().map(|coords| after(&state, coords)));
+            }
+        }
+        assert_eq!(covered, 1 + 9 + 9 * 8);
+    }
+
+    /// Asserts that `ai` never loses from `state` against any sequence of
+    /// opponent moves
+    fn assert_never_loses(state: &BoardState, ai: Player) {
+        if let Some(winner) = state.won() {
+            assert_eq!(winner, ai, "lost on board\n{}", state);
+            return;
+        }
+
+        if state.drawn() {
+            return;
+        }
+
+        if state.next() == ai {
+            let coords = best_move(state).unwrap();
+            assert_never_loses(&after(state, coords), ai);
+        } else {
+            for coords in state.available_moves() {
+                assert_never_loses(&after(state, coords), ai);
+            }
+        }
+    }
+
+    #[test]
+    fn never_loses() {
+        assert_never_loses(&BoardState::new(), Player::X);
+        assert_never_loses(&BoardState::new(), Player::O);
+    }
+
+    #[test]
+    fn alphabeta() {
+        let mut plain = Search::default();
+        let mut pruned = Search::default();
+        assert_eq!(
+            plain.best_move(&BoardState::new(), false),
+            pruned.best_move(&BoardState::new(), true)
+        );
+        assert!(pruned.nodes < plain.nodes);
+
+        let boards: [&[(usize, usize)]; 4] = [
+            &[(1, 1)],
+            &[(0, 0)],
+            &[(0, 0), (2, 0), (1, 1)],
+            &[(1, 0), (0, 0), (2, 2)],
+        ];
+        for moves in boards.iter() {
+            let state = BoardState::from_moves(moves).unwrap();
+            assert_eq!(
+                Search::default().best_move(&state, false),
+                Search::default().best_move(&state, true),
+                "disagreed on board\n{}",
+                state
+            );
+        }
+    }
+
+    #[test]
+    fn table() {
+        let mut uncached = Search::default();
+        let mut cached = Search::with_table();
+        assert_eq!(
+            uncached.best_move(&BoardState::new(), true),
+            cached.best_move(&BoardState::new(), true)
+        );
+        assert!(cached.nodes < uncached.nodes);
+
+        let boards: [(usize, &[(usize, usize)]); 3] = [
+            (4, &[(0, 0), (1, 1), (2, 2), (3, 3), (0, 3), (3, 0)]),
+            (4, &[(1, 1), (2, 2), (1, 2), (2, 1), (0, 0), (3, 3)]),
+            (3, &[(1, 0), (0, 0), (2, 2), (3, 3), (1, 1), (0, 3)]),
+        ];
+        for &(win_length, moves) in boards.iter() {
+            let mut state = BoardState::with_win_length(4, win_length);
+            for &coords in moves {
+                state.play(coords).unwrap();
+            }
+            let mut uncached = Search::default();
+            let mut cached = Search::with_table();
+            assert_eq!(
+                uncached.best_move(&state, true),
+                cached.best_move(&state, true),
+                "disagreed on board\n{}",
+                state
+            );
+            assert!(cached.nodes < uncached.nodes);
+        }
+    }
+
+    #[test]
+    fn mcts_takes_immediate_win() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..5 {
+            let state = BoardState::from_moves(&[(0, 0), (0, 1), (1, 0), (1, 1)]).unwrap();
+            assert_eq!(super::mcts_move(&state, 1000, &mut rng), (2, 0));
+
+            let state = BoardState::from_moves(&[(0, 0), (2, 0), (1, 1), (2, 1), (0, 1)]).unwrap();
+            assert_eq!(super::mcts_move(&state, 1000, &mut rng), (2, 2));
+        }
+    }
+
+    #[test]
+    fn mcts_three_players() {
+        // Z to move can win at once, as X and O could after it
+        let state = BoardState::from_compact("XX.XOO.OZZZ.X.O.").unwrap();
+        assert_eq!(state.next(), Player::Z);
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(super::mcts_move(&state, 1000, &mut rng), (3, 2));
+    }
+
+    #[test]
+    fn mcts_move() {
+        let state = BoardState::from_moves(&[(1, 1), (0, 0)]).unwrap();
+        let first = super::mcts_move(&state, 200, &mut StdRng::seed_from_u64(7));
+        assert_eq!(state[first], TileState::Empty);
+        assert_eq!(
+            super::mcts_move(&state, 200, &mut StdRng::seed_from_u64(7)),
+            first
+        );
+
+        // Only one move left
+        let state = BoardState::from_compact("XOXXOOOX.").unwrap();
+        assert_eq!(
+            super::mcts_move(&state, 1, &mut StdRng::seed_from_u64(0)),
+            (2, 2)
+        );
+
+        let state = BoardState::with_size(5);
+        let mut rng = StdRng::seed_from_u64(0);
+        let coords = super::mcts_move(&state, 100, &mut rng);
+        assert!(coords.0 < 5 && coords.1 < 5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn mcts_move_game_over() {
+        let state = BoardState::from_moves(&[(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)]).unwrap();
+        super::mcts_move(&state, 10, &mut StdRng::seed_from_u64(0));
+    }
+
+    #[test]
+    fn lookahead_move() {
+        let mut state = BoardState::with_win_length(15, 5);
+        assert_eq!(super::lookahead_move(&state, 2), Some((7, 7)));
+
+        // X has four in a row from (3, 3) to (6, 6), which O has blocked at
+        // (2, 2) but not yet at (7, 7)
+        for &coords in [(3, 3), (2, 2), (4, 4), (0, 14), (5, 5), (1, 14), (6, 6)].iter() {
+            state.play(coords).unwrap();
+        }
+        assert_eq!(super::lookahead_move(&state, 2), Some((7, 7)));
+        state.play((3, 14)).unwrap();
+        assert_eq!(super::lookahead_move(&state, 2), Some((7, 7)));
+        state.play((7, 7)).unwrap();
+        assert_eq!(state.won(), Some(Player::X));
+        assert_eq!(super::lookahead_move(&state, 2), None);
+    }
+
+    #[test]
+    fn strong_move() {
+        let state = BoardState::from_moves(&[(1, 1), (0, 0)]).unwrap();
+        assert_eq!(super::strong_move(&state), super::best_move(&state));
+
+        let mut state = BoardState::with_win_length(15, 5);
+        for &coords in [(7, 7), (7, 6), (7, 8), (0, 0), (7, 9), (0, 1), (7, 10)].iter() {
+            state.play(coords).unwrap();
+        }
+        assert_eq!(super::strong_move(&state), Some((7, 11)));
+        assert_eq!(
+            choose_move(&state, Difficulty::Hard, &mut StdRng::seed_from_u64(0)),
+            super::strong_move(&state).unwrap()
+        );
+    }
+
+    #[test]
+    fn evaluate() {
+        let won = BoardState::from_moves(&[(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)]).unwrap();
+        assert_eq!(super::evaluate(&won, Player::X), WIN);
+        assert_eq!(super::evaluate(&won, Player::O), -WIN);
+
+        let boards: [&[(usize, usize)]; 4] = [
+            &[],
+            &[(1, 1)],
+            &[(0, 0), (1, 1), (2, 2), (0, 2), (2, 0), (1, 0)],
+            &[(0, 0), (0, 1), (1, 0), (1, 1)],
+        ];
+        for moves in boards.iter() {
+            let state = BoardState::from_moves(moves).unwrap();
+            for &player in [Player::X, Player::O].iter() {
+                let score = super::evaluate(&state, player);
+                assert!(score > -WIN && score < WIN, "{} on\n{}", score, state);
+                assert_eq!(score, -super::evaluate(&state, player.opponent()));
+            }
+        }
+        assert_eq!(super::evaluate(&BoardState::new(), Player::X), 0);
+
+        // X has two open lines through the corners, one of them a threat
+        let threats = BoardState::from_compact("X.X.O...O").unwrap();
+        // Neither side has an open line
+        let blocked = BoardState::from_compact("XOX.O..XO").unwrap();
+        assert!(super::evaluate(&threats, Player::X) > super::evaluate(&blocked, Player::X));
+        assert!(super::evaluate(&threats, Player::X) > 0);
+
+        // The center is worth more than an edge
+        let center = BoardState::from_moves(&[(1, 1)]).unwrap();
+        let edge = BoardState::from_moves(&[(1, 0)]).unwrap();
+        assert!(super::evaluate(&center, Player::X) > super::evaluate(&edge, Player::X));
+
+        // Every other player's open lines count against X: a column each
+        // for O and Z, against two open lines and a corner for X
+        let three = BoardState::from_compact("XOZ.............").unwrap();
+        assert_eq!(super::evaluate(&three, Player::X), 2);
+
+        // Large boards stay below a win
+        let mut state = BoardState::with_win_length(9, 2);
+        for &coords in [(0, 0), (8, 8), (4, 4), (0, 8)].iter() {
+            state.play(coords).unwrap();
+        }
+        assert!(super::evaluate(&state, Player::X) < WIN);
+    }
+
+    #[test]
+    fn winning_moves() {
+        let state = BoardState::from_moves(&[(0, 0), (0, 1), (1, 0), (1, 1)]).unwrap();
+        assert_eq!(super::winning_moves(&state, Player::X), vec![(2, 0)]);
+        assert_eq!(super::winning_moves(&state, Player::O), vec![(2, 1)]);
+
+        let state = BoardState::from_compact("X.XO.O...").unwrap();
+        assert_eq!(super::winning_moves(&state, Player::X), vec![(1, 0)]);
+        assert_eq!(super::winning_moves(&BoardState::new(), Player::X), vec![]);
+
+        let won = BoardState::from_moves(&[(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)]).unwrap();
+        assert_eq!(super::winning_moves(&won, Player::O), vec![]);
+    }
+
+    #[test]
+    fn creates_fork() {
+        // X holds opposite corners around O's center
+        let state = BoardState::from_moves(&[(0, 0), (1, 1), (2, 2)]).unwrap();
+        assert!(super::creates_fork(&state, (2, 0), Player::X));
+        assert!(super::creates_fork(&state, (0, 2), Player::X));
+        for &coords in [(1, 0), (0, 1), (2, 1), (1, 2), (1, 1)].iter() {
+            assert!(!super::creates_fork(&state, coords, Player::X));
+        }
+        assert!(!super::creates_fork(&state, (2, 0), Player::O));
+
+        // O must preempt the fork with an edge, forcing X to block instead
+        let coords = best_move(&state).unwrap();
+        assert!([(1, 0), (0, 1), (2, 1), (1, 2)].contains(&coords));
+        let state = after(&state, coords);
+        assert_eq!(super::winning_moves(&state, Player::O).len(), 1);
+
+        // Taking a corner instead lets X block and fork at once
+        let state = BoardState::from_moves(&[(0, 0), (1, 1), (2, 2), (0, 2)]).unwrap();
+        assert_eq!(super::winning_moves(&state, Player::O), vec![(2, 0)]);
+        assert!(super::creates_fork(&state, (2, 0), Player::X));
+    }
+
+    #[test]
+    fn best_move_three_players() {
+        // Z must block X, since O and X are both against it
+        let state = BoardState::from_compact("XXX.OO.ZZ.O.....").unwrap();
+        assert_eq!(state.next(), Player::Z);
+        assert_eq!(super::best_move(&state), Some((3, 0)));
+
+        assert!(super::solve(&state).is_err());
+        assert!(super::analyze(&state).is_err());
+    }
+
+    #[test]
+    fn solve() {
+        let solution = super::solve(&BoardState::new()).unwrap();
+        assert_eq!(solution.result, GameResult::Draw);
+        assert_eq!(solution.moves.len(), 9);
+
+        let state = BoardState::from_moves(&[(0, 0), (0, 1), (1, 0), (1, 1)]).unwrap();
+        assert_eq!(
+            super::solve(&state).unwrap(),
+            Solution {
+                result: GameResult::Win(Player::X),
+                moves: vec![(2, 0)],
+            }
+        );
+
+        // A corner reply to a corner opening loses to a fork
+        let state = BoardState::from_moves(&[(0, 0), (1, 1), (2, 2)]).unwrap();
+        let solution = super::solve(&state).unwrap();
+        assert_eq!(solution.result, GameResult::Draw);
+        assert_eq!(solution.moves, vec![(1, 0), (0, 1), (2, 1), (1, 2)]);
+        let state = after(&state, (2, 0));
+        assert_eq!(
+            super::solve(&state).unwrap().result,
+            GameResult::Win(Player::X)
+        );
+
+        // O faces two threats, so every move loses
+        let state = BoardState::from_moves(&[(1, 1), (0, 1), (0, 0), (2, 2), (2, 0)]).unwrap();
+        let solution = super::solve(&state).unwrap();
+        assert_eq!(solution.result, GameResult::Win(Player::X));
+        assert_eq!(solution.moves, state.available_moves().collect::<Vec<_>>());
+
+        let won = BoardState::from_moves(&[(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)]).unwrap();
+        assert_eq!(
+            super::solve(&won).unwrap(),
+            Solution {
+                result: GameResult::Win(Player::X),
+                moves: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn analyze() {
+        let values = super::analyze(&BoardState::new()).unwrap();
+        assert_eq!(values.len(), 9);
+        for value in values.iter() {
+            assert_eq!(value.result, GameResult::Draw);
+            assert_eq!(value.rank, 1);
+        }
+        assert_eq!(
+            values.iter().map(|value| value.coords).collect::<Vec<_>>(),
+            BoardState::new().available_moves().collect::<Vec<_>>()
+        );
+
+        // X can win at once, can only draw by blocking O, and loses otherwise
+        let state = BoardState::from_moves(&[(0, 0), (0, 1), (1, 0), (1, 1)]).unwrap();
+        let values = super::analyze(&state).unwrap();
+        assert_eq!(
+            values[0],
+            MoveValue {
+                coords: (2, 0),
+                result: GameResult::Win(Player::X),
+                rank: 1,
+            }
+        );
+        assert_eq!(
+            values[1],
+            MoveValue {
+                coords: (2, 1),
+                result: GameResult::Draw,
+                rank: 2,
+            }
+        );
+        for (value, &coords) in values[2..].iter().zip([(0, 2), (1, 2), (2, 2)].iter()) {
+            assert_eq!(value.coords, coords);
+            assert_eq!(value.result, GameResult::Win(Player::O));
+            assert_eq!(value.rank, 3);
+        }
+
+        let solution = super::solve(&state).unwrap();
+        let best = values
+            .iter()
+            .filter(|value| value.result == solution.result)
+            .map(|value| value.coords);
+        assert!(best.eq(solution.moves.iter().copied()));
+
+        let won = BoardState::from_moves(&[(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)]).unwrap();
+        assert_eq!(super::analyze(&won).unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn search_dot() {
+        let dot = super::search_dot(&BoardState::new(), 2);
+        assert!(dot.starts_with("digraph search {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert_eq!(dot.matches('{').count(), dot.matches('}').count());
+        assert_eq!(dot.matches(" -> ").count(), 9 + 9 * 8);
+        assert_eq!(dot.matches("[label=\"").count(), 1 + 2 * (9 + 9 * 8));
+        assert!(dot.contains("  n0 [label=\".........\", "));
+        assert!(dot.contains("  n0 -> n1 [label=\"0, 0\"];\n"));
+        assert!(dot.contains("  n2 [label=\"XO.......\", "));
+
+        // X wins at once, so the root is good for X and the win is shown
+        let state = BoardState::from_moves(&[(0, 0), (0, 1), (1, 0), (1, 1)]).unwrap();
+        let dot = super::search_dot(&state, 1);
+        assert!(dot.contains("  n0 [label=\"XX.OO....\", fillcolor=palegreen];\n"));
+        assert!(dot.contains("[label=\"XXXOO....\", fillcolor=palegreen];\n"));
+        assert_eq!(dot.matches(" -> ").count(), 5);
+
+        let dot = super::search_dot(&state, 0);
+        assert_eq!(dot.matches(" -> ").count(), 0);
+        assert_eq!(dot.matches("[label=\"").count(), 1);
+    }
+
+    #[test]
+    fn count_games() {
+        assert_eq!(super::count_games(&BoardState::new()), 255_168);
+
+        // X wins two of these games, O one, and two are drawn
+        let state = BoardState::from_compact("XOXXOO...").unwrap();
+        assert_eq!(super::count_games(&state), 5);
+
+        let won = BoardState::from_moves(&[(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)]).unwrap();
+        assert_eq!(super::count_games(&won), 1);
+    }
+
+    #[test]
+    fn terminal_positions() {
+        let terminals = super::terminal_positions(3, 3).collect::<Vec<_>>();
+        let count = |target| {
+            terminals
+                .iter()
+                .filter(|&&(_, result)| result == target)
+                .count()
+        };
+        assert_eq!(count(GameResult::Win(Player::X)), 626);
+        assert_eq!(count(GameResult::Win(Player::O)), 316);
+        assert_eq!(count(GameResult::Draw), 16);
+
+        for (state, result) in terminals.iter() {
+            assert_eq!(state.status(), *result);
+            assert!(state.is_valid());
+        }
+        let distinct = terminals
+            .iter()
+            .map(|(state, _)| state)
+            .collect::<HashSet<_>>();
+        assert_eq!(distinct.len(), terminals.len());
+
+        // The first player always wins at once
+        let terminals = super::terminal_positions(2, 1).collect::<Vec<_>>();
+        assert_eq!(terminals.len(), 4);
+        assert!(terminals
+            .iter()
+            .all(|&(_, result)| result == GameResult::Win(Player::X)));
+    }
+
+    #[test]
+    fn takes_immediate_win() {
+        let mut state = BoardState::new();
+        for &coords in [(0, 0), (0, 1), (1, 0), (1, 1)].iter() {
+            state.play(coords).unwrap();
+        }
+        assert_eq!(best_move(&state), Some((2, 0)));
+
+        let mut state = BoardState::new();
+        for &coords in [(0, 0), (2, 0), (1, 1), (2, 1), (0, 1)].iter() {
+            state.play(coords).unwrap();
+        }
+        assert_eq!(best_move(&state), Some((2, 2)));
+    }
+
+    #[test]
+    fn game_over() {
+        let mut state = BoardState::new();
+        for &coords in [(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)].iter() {
+            state.play(coords).unwrap();
+        }
+        assert_eq!(best_move(&state), None);
+
+        let mut state = BoardState::new();
+        for &coords in [
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (1, 1),
+            (0, 1),
+            (0, 2),
+            (2, 1),
+            (2, 2),
+            (1, 2),
+        ]
+        .iter()
+        {
+            state.play(coords).unwrap();
+        }
+        assert!(state.drawn());
+        assert_eq!(best_move(&state), None);
+    }
+}
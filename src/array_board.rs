@@ -0,0 +1,300 @@
+//! A board whose size is fixed at compile time, held entirely on the stack
+
+use core::{
+    fmt::{self, Display, Formatter},
+    ops::{Index, IndexMut},
+};
+
+use crate::state::{all_eq, BoardState, GameError, GameResult, Player, TileState, BOARD_SIZE};
+
+/// An `N` by `N` two-player board, won by `N` in a row, that stores its
+/// tiles in an array rather than on the heap. Like a [`Bitboard`] it keeps no
+/// history, but its tiles can be edited through `IndexMut`.
+///
+/// [`Bitboard`]: crate::bitboard::Bitboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ArrayBoard<const N: usize> {
+    tiles: [[TileState; N]; N],
+    next: Player,
+}
+
+/// The standard 3 by 3 board, without any allocation
+pub type Board3 = ArrayBoard<BOARD_SIZE>;
+
+/// A 4 by 4 board, won by 4 in a row
+pub type Board4 = ArrayBoard<4>;
+
+impl<const N: usize> ArrayBoard<N> {
+    /// Creates an empty board
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is 0.
+    pub fn new() -> Self {
+        assert!(N > 0, "board size must be positive");
+        ArrayBoard {
+            tiles: [[TileState::Empty; N]; N],
+            next: Player::X,
+        }
+    }
+
+    /// Places the next player's piece at `coords`
+    pub fn play(&mut self, (x, y): (usize, usize)) -> Result<&mut Self, GameError> {
+        if x >= N || y >= N {
+            return Err(GameError::OutOfBounds { x, y });
+        }
+
+        match self[(x, y)] {
+            TileState::Empty => {
+                self[(x, y)] = self.next.into();
+                self.next = self.next.opponent();
+                Ok(self)
+            }
+            _ => Err(GameError::AlreadyPlayed { x, y }),
+        }
+    }
+
+    pub fn next(&self) -> Player {
+        self.next
+    }
+
+    /// The side length of the board
+    pub fn size(&self) -> usize {
+        N
+    }
+
+    /// Iterates over the coordinates of every empty tile in row-major order
+    pub fn available_moves(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..N)
+            .flat_map(|y| (0..N).map(move |x| (x, y)))
+            .filter(move |&coords| self[coords] == TileState::Empty)
+    }
+
+    /// The number of empty tiles
+    pub fn remaining(&self) -> usize {
+        self.tiles
+            .iter()
+            .flatten()
+            .filter(|&&tile| tile == TileState::Empty)
+            .count()
+    }
+
+    pub fn iter_row(&self, row: usize) -> impl Iterator<Item = TileState> + '_ {
+        self.tiles[row].iter().copied()
+    }
+
+    pub fn iter_col(&self, col: usize) -> impl Iterator<Item = TileState> + '_ {
+        self.tiles.iter().map(move |row| row[col])
+    }
+
+    pub fn iter_diag(&self, sinister: bool) -> impl Iterator<Item = TileState> + '_ {
+        (0..N).map(move |i| self[(if sinister { N - 1 - i } else { i }, i)])
+    }
+
+    pub fn status(&self) -> GameResult {
+        if let Some(player) = self.won() {
+            GameResult::Win(player)
+        } else if self.remaining() == 0 {
+            GameResult::Draw
+        } else {
+            GameResult::InProgress
+        }
+    }
+
+    /// The winner, checking rows, then columns, then diagonals, as
+    /// [`BoardState::winning_line`] does
+    pub fn won(&self) -> Option<Player> {
+        let rows = (0..N).map(|row| all_eq(self.iter_row(row)));
+        let cols = (0..N).map(|col| all_eq(self.iter_col(col)));
+        let diags = [false, true]
+            .iter()
+            .map(|&sinister| all_eq(self.iter_diag(sinister)));
+
+        rows.chain(cols)
+            .chain(diags)
+            .find_map(|tile| tile.and_then(Option::<Player>::from))
+    }
+
+    pub fn drawn(&self) -> bool {
+        self.status() == GameResult::Draw
+    }
+}
+
+impl<const N: usize> Default for ArrayBoard<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Index<(usize, usize)> for ArrayBoard<N> {
+    type Output = TileState;
+
+    fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
+        &self.tiles[y][x]
+    }
+}
+
+impl<const N: usize> IndexMut<(usize, usize)> for ArrayBoard<N> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut Self::Output {
+        &mut self.tiles[y][x]
+    }
+}
+
+impl<const N: usize> Display for ArrayBoard<N> {
+    /// Draws the same grid as [`BoardState`]'s `Display`
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), fmt::Error> {
+        let mut board = BoardState::with_size(N);
+        for y in 0..N {
+            for x in 0..N {
+                board[(x, y)] = self[(x, y)];
+            }
+        }
+        board.fmt(fmt)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::{collection, prelude::*};
+
+    use super::*;
+    use crate::mirror_test;
+
+    #[test]
+    fn play() {
+        let mut board = Board3::new();
+        board.play((1, 1)).unwrap().play((0, 0)).unwrap();
+        assert_eq!(board[(1, 1)], TileState::X);
+        assert_eq!(board[(0, 0)], TileState::O);
+        assert_eq!(board.next(), Player::X);
+        assert_eq!(
+            board.play((1, 1)).unwrap_err(),
+            GameError::AlreadyPlayed { x: 1, y: 1 }
+        );
+        assert_eq!(
+            board.play((0, 3)).unwrap_err(),
+            GameError::OutOfBounds { x: 0, y: 3 }
+        );
+    }
+
+    #[test]
+    fn won() {
+        use TileState::*;
+        assert_eq!(Board3::new().won(), None);
+        let lines = [
+            [(0, 0), (1, 0), (2, 0)],
+            [(0, 1), (1, 1), (2, 1)],
+            [(0, 2), (1, 2), (2, 2)],
+            [(0, 0), (0, 1), (0, 2)],
+            [(1, 0), (1, 1), (1, 2)],
+            [(2, 0), (2, 1), (2, 2)],
+            [(0, 0), (1, 1), (2, 2)],
+            [(2, 0), (1, 1), (0, 2)],
+        ];
+        for &line in lines.iter() {
+            let mut board = Board3::new();
+            for &coord in line.iter() {
+                board[coord] = O;
+            }
+            assert_eq!(board.won(), Some(Player::O));
+            assert_eq!(board.status(), GameResult::Win(Player::O));
+        }
+    }
+
+    #[test]
+    fn drawn() {
+        let mut board = Board3::new();
+        for &coords in [
+            (1, 1),
+            (0, 0),
+            (2, 2),
+            (0, 2),
+            (0, 1),
+            (2, 1),
+            (1, 0),
+            (1, 2),
+        ]
+        .iter()
+        {
+            board.play(coords).unwrap();
+            assert!(!board.drawn());
+        }
+        board.play((2, 0)).unwrap();
+        assert!(board.drawn());
+        assert_eq!(board.won(), None);
+    }
+
+    #[test]
+    fn display() {
+        let mut board = Board3::new();
+        board.play((1, 1)).unwrap();
+        insta::assert_snapshot!(format!("{}", board), @r###"
+          0 1 2
+
+        0  | | 
+          -+-+-
+        1  |X| 
+          -+-+-
+        2  | | 
+        "###);
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(Board3::new().size(), 3);
+        assert_eq!(ArrayBoard::<3>::new(), Board3::default());
+        assert_eq!(Board3::new().remaining(), 9);
+
+        let mut board = Board4::new();
+        assert_eq!(board.size(), 4);
+        assert_eq!(board.remaining(), 16);
+        for x in 0..3 {
+            board.play((x, 0)).unwrap().play((x, 1)).unwrap();
+        }
+        assert_eq!(board.won(), None);
+        board.play((3, 0)).unwrap();
+        assert_eq!(board.won(), Some(Player::X));
+        assert_eq!(board.remaining(), 9);
+
+        let mut board = Board4::new();
+        for i in 0..4 {
+            board[(3 - i, i)] = TileState::O;
+        }
+        assert_eq!(board.won(), Some(Player::O));
+        insta::assert_snapshot!(format!("{}", board), @r###"
+          0 1 2 3
+
+        0  | | |O
+          -+-+-+-
+        1  | |O| 
+          -+-+-+-
+        2  |O| | 
+          -+-+-+-
+        3 O| | | 
+        "###);
+    }
+
+    #[test]
+    #[should_panic]
+    fn empty() {
+        ArrayBoard::<0>::new();
+    }
+
+    proptest! {
+        #[test]
+        fn matches_board_state(choices in collection::vec(0..16usize, 0..16)) {
+            fn check<const N: usize>(choices: &[usize]) {
+                let (array, board) = mirror_test::play_alongside(
+                    ArrayBoard::<N>::new(),
+                    BoardState::with_size(N),
+                    choices,
+                );
+                assert_eq!(array.to_string(), board.to_string());
+            }
+
+            check::<1>(&choices);
+            check::<3>(&choices);
+            check::<4>(&choices);
+        }
+    }
+}
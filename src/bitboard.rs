@@ -0,0 +1,293 @@
+//! A compact two-player board for searches that play many moves
+
+use core::ops::Index;
+
+use anyhow::{self, anyhow as anyhow_error};
+
+use crate::state::{BoardState, GameError, GameResult, Player, TileState, BOARD_SIZE};
+
+/// The side length of the largest board a [`Bitboard`] can hold
+pub const MAX_SIZE: usize = 8;
+
+/// A two-player board of up to 8 by 8 that stores each player's tiles as a
+/// bitmask, so that playing a move or checking for a win takes a handful of
+/// bit operations.
+///
+/// Bitboards answer the same questions as [`BoardState`], with the same
+/// answers, but keep no history and cannot be edited tile by tile.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Bitboard {
+    size: usize,
+    win_length: usize,
+    x: u64,
+    o: u64,
+    next: Player,
+    /// A mask for every run of tiles that wins, in the order
+    /// [`BoardState::winning_line`] checks them
+    runs: Vec<u64>,
+}
+
+impl Bitboard {
+    /// Creates an empty standard board
+    pub fn new() -> Self {
+        Self::with_win_length(BOARD_SIZE, BOARD_SIZE)
+    }
+
+    /// Creates an empty `size` by `size` board, won by `win_length` in a row
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0 or greater than [`MAX_SIZE`], or if `win_length`
+    /// is 0 or greater than `size`.
+    pub fn with_win_length(size: usize, win_length: usize) -> Self {
+        assert!(
+            size <= MAX_SIZE,
+            "a bitboard can be at most {} by {}",
+            MAX_SIZE,
+            MAX_SIZE
+        );
+        let runs = BoardState::with_win_length(size, win_length)
+            .iter_lines()
+            .flat_map(|line| {
+                line.windows(win_length)
+                    .map(|run| {
+                        run.iter()
+                            .fold(0, |mask, &(x, y)| mask | 1 << (x + y * size))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        Bitboard {
+            size,
+            win_length,
+            x: 0,
+            o: 0,
+            next: Player::X,
+            runs,
+        }
+    }
+
+    /// Copies the tiles and player to move of `board`. Fails if the board is
+    /// bigger than [`MAX_SIZE`] or has three players.
+    pub fn from_board(board: &BoardState) -> anyhow::Result<Self> {
+        if board.size() > MAX_SIZE {
+            return Err(anyhow_error!(
+                "a {} by {} board is too big for a bitboard",
+                board.size(),
+                board.size()
+            ));
+        }
+        if board.players() != 2 {
+            return Err(anyhow_error!("a bitboard only holds two players"));
+        }
+
+        let mut bitboard = Self::with_win_length(board.size(), board.win_length());
+        for y in 0..board.size() {
+            for x in 0..board.size() {
+                match board[(x, y)] {
+                    TileState::X => bitboard.x |= bitboard.bit((x, y)),
+                    TileState::O => bitboard.o |= bitboard.bit((x, y)),
+                    _ => {}
+                }
+            }
+        }
+        bitboard.next = board.next();
+        Ok(bitboard)
+    }
+
+    /// The mask of the tile at `coords`
+    fn bit(&self, (x, y): (usize, usize)) -> u64 {
+        assert!(
+            x < self.size && y < self.size,
+            "({}, {}) is off the board",
+            x,
+            y
+        );
+        1 << (x + y * self.size)
+    }
+
+    /// The mask of every tile on the board
+    fn full(&self) -> u64 {
+        u64::MAX >> (64 - self.size * self.size)
+    }
+
+    /// Places the next player's piece at `coords`
+    pub fn play(&mut self, (x, y): (usize, usize)) -> Result<&mut Self, GameError> {
+        if x >= self.size || y >= self.size {
+            return Err(GameError::OutOfBounds { x, y });
+        }
+
+        let bit = self.bit((x, y));
+        if (self.x | self.o) & bit != 0 {
+            return Err(GameError::AlreadyPlayed { x, y });
+        }
+
+        match self.next {
+            Player::X => self.x |= bit,
+            _ => self.o |= bit,
+        }
+        self.next = self.next.opponent();
+        Ok(self)
+    }
+
+    pub fn next(&self) -> Player {
+        self.next
+    }
+
+    /// The side length of the board
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// How many identical tiles in a row win
+    pub fn win_length(&self) -> usize {
+        self.win_length
+    }
+
+    /// Iterates over the coordinates of every empty tile in row-major order
+    pub fn available_moves(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.size)
+            .flat_map(move |y| (0..self.size).map(move |x| (x, y)))
+            .filter(move |&coords| (self.x | self.o) & self.bit(coords) == 0)
+    }
+
+    /// The number of empty tiles
+    pub fn remaining(&self) -> usize {
+        (self.size * self.size) - (self.x | self.o).count_ones() as usize
+    }
+
+    pub fn iter_row(&self, row: usize) -> impl Iterator<Item = TileState> + '_ {
+        (0..self.size).map(move |x| self[(x, row)])
+    }
+
+    pub fn iter_col(&self, col: usize) -> impl Iterator<Item = TileState> + '_ {
+        (0..self.size).map(move |y| self[(col, y)])
+    }
+
+    pub fn status(&self) -> GameResult {
+        if let Some(player) = self.won() {
+            GameResult::Win(player)
+        } else if self.x | self.o == self.full() {
+            GameResult::Draw
+        } else {
+            GameResult::InProgress
+        }
+    }
+
+    pub fn won(&self) -> Option<Player> {
+        self.runs.iter().find_map(|&run| {
+            if self.x & run == run {
+                Some(Player::X)
+            } else if self.o & run == run {
+                Some(Player::O)
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn drawn(&self) -> bool {
+        self.status() == GameResult::Draw
+    }
+}
+
+impl Default for Bitboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Index<(usize, usize)> for Bitboard {
+    type Output = TileState;
+
+    fn index(&self, coords: (usize, usize)) -> &Self::Output {
+        let bit = self.bit(coords);
+        if self.x & bit != 0 {
+            &TileState::X
+        } else if self.o & bit != 0 {
+            &TileState::O
+        } else {
+            &TileState::Empty
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::{collection, prelude::*};
+
+    use super::*;
+    use crate::mirror_test::{self, assert_same};
+
+    #[test]
+    fn play() {
+        let mut bitboard = Bitboard::new();
+        bitboard.play((1, 1)).unwrap().play((0, 0)).unwrap();
+        assert_eq!(bitboard[(1, 1)], TileState::X);
+        assert_eq!(bitboard[(0, 0)], TileState::O);
+        assert_eq!(bitboard[(2, 2)], TileState::Empty);
+        assert_eq!(bitboard.next(), Player::X);
+        assert_eq!(
+            bitboard.play((1, 1)).unwrap_err(),
+            GameError::AlreadyPlayed { x: 1, y: 1 }
+        );
+        assert_eq!(
+            bitboard.play((3, 0)).unwrap_err(),
+            GameError::OutOfBounds { x: 3, y: 0 }
+        );
+        assert_same(
+            &bitboard,
+            &BoardState::from_moves(&[(1, 1), (0, 0)]).unwrap(),
+        );
+    }
+
+    #[test]
+    fn from_board() {
+        for compact in [
+            ".........",
+            "X...O....",
+            "XXXOO....",
+            "XOXOXOXOX",
+            "XOXXOOOXX",
+            "O.X.X.X.O",
+            "XXOO....X...O...",
+        ]
+        .iter()
+        {
+            let board = BoardState::from_compact(compact).unwrap();
+            assert_same(&Bitboard::from_board(&board).unwrap(), &board);
+        }
+
+        let mut board = BoardState::with_win_length(MAX_SIZE, 5);
+        for x in 0..4 {
+            board.play((x, MAX_SIZE - 1)).unwrap().play((x, 0)).unwrap();
+        }
+        board.play((4, MAX_SIZE - 1)).unwrap();
+        assert_eq!(board.won(), Some(Player::X));
+        assert_same(&Bitboard::from_board(&board).unwrap(), &board);
+
+        assert!(Bitboard::from_board(&BoardState::with_size(MAX_SIZE + 1)).is_err());
+        assert!(Bitboard::from_board(&BoardState::with_players(4, 3, 3)).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_big() {
+        Bitboard::with_win_length(MAX_SIZE + 1, 3);
+    }
+
+    proptest! {
+        #[test]
+        fn matches_board_state(
+            (size, win_length) in (1..=MAX_SIZE).prop_flat_map(|size| (Just(size), 1..=size)),
+            choices in collection::vec(0..64usize, 0..64),
+        ) {
+            mirror_test::play_alongside(
+                Bitboard::with_win_length(size, win_length),
+                BoardState::with_win_length(size, win_length),
+                &choices,
+            );
+        }
+    }
+}
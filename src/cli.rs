@@ -0,0 +1,617 @@
+use core::{str::FromStr, time::Duration};
+use std::path::PathBuf;
+
+use anyhow::{self, anyhow as anyhow_error};
+
+use tic_tac_toe::{
+    ai::Difficulty,
+    game::{Names, Variant},
+    input::Indexing,
+    messages::Language,
+    state::BoardState,
+    timer::{self, OnTimeout},
+};
+
+/// The parsed command line
+#[derive(Debug, Clone, PartialEq)]
+pub struct Args {
+    pub mode: Mode,
+    /// Seeds all of the program's randomness, if given, so that runs can be
+    /// reproduced
+    pub seed: Option<u64>,
+}
+
+/// How to play interactively, by default a single standard game typed in
+/// with zero-based coordinates
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PlayOptions {
+    /// The rules every game follows
+    pub variant: Variant,
+    pub indexing: Indexing,
+    pub names: Names,
+    /// How many games make up a match, if more than one is played
+    pub best_of: Option<usize>,
+    /// Whether the first move alternates between rounds
+    pub alternate: bool,
+    /// Whether a coin flip decides who moves first, rather than X
+    pub random_start: bool,
+    /// Whether players steer a cursor in a full-screen interface instead of
+    /// typing coordinates
+    pub tui: bool,
+    /// Whether the screen is cleared before each board
+    pub clear: bool,
+    /// Whether the terminal bell rings when a game ends
+    pub bell: bool,
+    /// Whether only results are printed
+    pub quiet: bool,
+    /// A file to play a single game's moves from, instead of the keyboard
+    pub script: Option<PathBuf>,
+    /// How long each player has to move, if there is a limit
+    pub time_limit: Option<Duration>,
+    /// What happens to a player who runs past `time_limit`
+    pub on_timeout: OnTimeout,
+    /// The language of messages, or else the one the `LANG` environment
+    /// variable names
+    pub language: Option<Language>,
+}
+
+/// What the program has been asked to do
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mode {
+    /// Plays an interactive game or match
+    Play(PlayOptions),
+    /// Reports the outcome of a position under optimal play
+    Solve { board: BoardState },
+    /// Ranks every legal move on a position by its outcome under optimal play
+    Analyze { board: BoardState },
+    /// Draws the tree searched `depth` moves ahead of `board` as a Graphviz
+    /// DOT graph
+    Tree { board: BoardState, depth: usize },
+    /// Plays `games` games between AIs and tallies the results
+    Simulate {
+        games: usize,
+        x: Difficulty,
+        o: Difficulty,
+    },
+    /// Shows the saved game or move notation at `path` move by move, waiting
+    /// `delay` milliseconds between moves
+    Replay { path: PathBuf, delay: u64 },
+    /// Plays a game against an opponent over the network, either hosting it
+    /// at `address` or joining the one hosted or served there, or else serves
+    /// games at `address` between the players who join. Moves are typed with
+    /// `indexing`, and messages are in `language` as for [`Mode::Play`].
+    Netplay {
+        role: NetRole,
+        address: String,
+        indexing: Indexing,
+        language: Option<Language>,
+    },
+}
+
+/// Which end of a network game to play
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetRole {
+    /// Waits for an opponent and plays X
+    Host,
+    /// Connects to a waiting host or a server and plays whichever player it
+    /// says
+    Join,
+    /// Pairs up the players who join into games, without playing
+    Serve,
+}
+
+/// Parses the command line arguments, not including the program name.
+///
+/// `--seed` may appear anywhere; everything else depends on the mode.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> anyhow::Result<Args> {
+    let mut args = args.into_iter();
+    let mut seed = None;
+    let mut rest = Vec::new();
+
+    while let Some(arg) = args.next() {
+        if arg == "--seed" {
+            seed = Some(parse_value(&arg, &value(&mut args, &arg)?)?);
+        } else {
+            rest.push(arg);
+        }
+    }
+
+    Ok(Args {
+        mode: parse_mode(rest.into_iter())?,
+        seed,
+    })
+}
+
+fn parse_mode(mut args: impl Iterator<Item = String>) -> anyhow::Result<Mode> {
+    match args.next().as_deref() {
+        Some(mode @ "solve") | Some(mode @ "analyze") => {
+            let mut board = None;
+
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--board" => board = Some(parse_board(&value(&mut args, &arg)?)?),
+                    _ => return Err(anyhow_error!("unexpected argument {:?}", arg)),
+                }
+            }
+
+            let board = board.ok_or_else(|| anyhow_error!("{} needs a --board", mode))?;
+            match mode {
+                "solve" => Ok(Mode::Solve { board }),
+                _ => Ok(Mode::Analyze { board }),
+            }
+        }
+        Some("tree") => {
+            let mut board = BoardState::new();
+            let mut depth = 2;
+
+            while let Some(arg) = args.next() {
+                let value = value(&mut args, &arg)?;
+                match arg.as_str() {
+                    "--board" => board = parse_board(&value)?,
+                    "--depth" => depth = parse_value(&arg, &value)?,
+                    _ => return Err(anyhow_error!("unexpected argument {:?}", arg)),
+                }
+            }
+
+            Ok(Mode::Tree { board, depth })
+        }
+        Some("simulate") => {
+            let mut games = 100;
+            let mut x = Difficulty::Hard;
+            let mut o = Difficulty::Hard;
+
+            while let Some(arg) = args.next() {
+                let value = value(&mut args, &arg)?;
+                match arg.as_str() {
+                    "--games" => games = parse_value(&arg, &value)?,
+                    "--x" => x = value.parse()?,
+                    "--o" => o = value.parse()?,
+                    _ => return Err(anyhow_error!("unexpected argument {:?}", arg)),
+                }
+            }
+
+            Ok(Mode::Simulate { games, x, o })
+        }
+        Some("replay") => {
+            let mut path = None;
+            let mut delay = 500;
+
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--delay" => delay = parse_value(&arg, &value(&mut args, &arg)?)?,
+                    _ if path.is_none() && !arg.starts_with("--") => path = Some(arg.into()),
+                    _ => return Err(anyhow_error!("unexpected argument {:?}", arg)),
+                }
+            }
+
+            let path = path.ok_or_else(|| anyhow_error!("replay needs a file to replay"))?;
+            Ok(Mode::Replay { path, delay })
+        }
+        Some("netplay") => {
+            let role = match args.next().as_deref() {
+                Some("host") => NetRole::Host,
+                Some("join") => NetRole::Join,
+                Some("serve") => NetRole::Serve,
+                _ => return Err(anyhow_error!("netplay needs host, join, or serve")),
+            };
+            let mut address = None;
+            let mut indexing = Indexing::ZeroBased;
+            let mut language = None;
+
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--one-based" => indexing = Indexing::OneBased,
+                    "--lang" => language = Some(value(&mut args, &arg)?.parse()?),
+                    _ if address.is_none() && !arg.starts_with("--") => address = Some(arg),
+                    _ => return Err(anyhow_error!("unexpected argument {:?}", arg)),
+                }
+            }
+
+            let address = address.ok_or_else(|| anyhow_error!("netplay needs an address"))?;
+            Ok(Mode::Netplay {
+                role,
+                address,
+                indexing,
+                language,
+            })
+        }
+        first => {
+            let mut options = PlayOptions::default();
+            let mut args = first.map(str::to_owned).into_iter().chain(args);
+
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--variant" => options.variant = value(&mut args, &arg)?.parse()?,
+                    "--one-based" => options.indexing = Indexing::OneBased,
+                    "--alternate" => options.alternate = true,
+                    "--random-start" => options.random_start = true,
+                    "--tui" => options.tui = true,
+                    "--clear" => options.clear = true,
+                    "--bell" => options.bell = true,
+                    "--quiet" => options.quiet = true,
+                    "--x-name" => options.names.x = Some(value(&mut args, &arg)?),
+                    "--o-name" => options.names.o = Some(value(&mut args, &arg)?),
+                    "--script" => options.script = Some(value(&mut args, &arg)?.into()),
+                    "--time-limit" => {
+                        options.time_limit =
+                            timer::time_limit(parse_value(&arg, &value(&mut args, &arg)?)?)
+                    }
+                    "--forfeit" => options.on_timeout = value(&mut args, &arg)?.parse()?,
+                    "--lang" => options.language = Some(value(&mut args, &arg)?.parse()?),
+                    "--best-of" => match parse_value(&arg, &value(&mut args, &arg)?)? {
+                        0 => return Err(anyhow_error!("{} must be at least 1", arg)),
+                        rounds => options.best_of = Some(rounds),
+                    },
+                    _ => return Err(anyhow_error!("unexpected argument {:?}", arg)),
+                }
+            }
+
+            if options.script.is_some() && (options.tui || options.best_of.is_some()) {
+                return Err(anyhow_error!(
+                    "--script can't be combined with --tui or --best-of"
+                ));
+            }
+            if options.time_limit.is_some() && (options.tui || options.script.is_some()) {
+                return Err(anyhow_error!(
+                    "--time-limit can't be combined with --tui or --script"
+                ));
+            }
+            // The cursor only ever places the player's own mark
+            if options.tui && options.variant.rules().wild {
+                return Err(anyhow_error!("--tui can't play the wild variant"));
+            }
+
+            Ok(Mode::Play(options))
+        }
+    }
+}
+
+/// Takes the value following `flag`
+fn value(args: &mut impl Iterator<Item = String>, flag: &str) -> anyhow::Result<String> {
+    args.next()
+        .ok_or_else(|| anyhow_error!("{} needs a value", flag))
+}
+
+/// Parses the number given for `flag`
+fn parse_value<T: FromStr>(flag: &str, value: &str) -> anyhow::Result<T> {
+    value
+        .parse()
+        .map_err(|_| anyhow_error!("{} expects a number, got {:?}", flag, value))
+}
+
+/// Parses a board in the compact encoding, rejecting boards that could not
+/// arise from play
+fn parse_board(compact: &str) -> anyhow::Result<BoardState> {
+    let board = BoardState::from_compact(compact)?;
+    if board.is_valid() {
+        Ok(board)
+    } else {
+        Err(anyhow_error!("{:?} could not arise from play", compact))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(args: &[&str]) -> anyhow::Result<Mode> {
+        parse_with_seed(args).map(|args| args.mode)
+    }
+
+    fn parse_with_seed(args: &[&str]) -> anyhow::Result<Args> {
+        parse_args(args.iter().map(|&arg| arg.to_owned()))
+    }
+
+    #[test]
+    fn play() {
+        assert_eq!(parse(&[]).unwrap(), Mode::Play(PlayOptions::default()));
+        assert_eq!(
+            parse(&["--one-based"]).unwrap(),
+            Mode::Play(PlayOptions {
+                indexing: Indexing::OneBased,
+                ..Default::default()
+            })
+        );
+        assert!(parse(&["--two-based"]).is_err());
+        assert_eq!(
+            parse(&["--o-name", "Bob", "--x-name", "Alice"]).unwrap(),
+            Mode::Play(PlayOptions {
+                names: Names {
+                    x: Some("Alice".to_owned()),
+                    o: Some("Bob".to_owned()),
+                },
+                ..Default::default()
+            })
+        );
+        assert!(parse(&["--x-name"]).is_err());
+        assert_eq!(
+            parse(&["--best-of", "5", "--one-based"]).unwrap(),
+            Mode::Play(PlayOptions {
+                indexing: Indexing::OneBased,
+                best_of: Some(5),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            parse(&["--best-of", "3", "--alternate"]).unwrap(),
+            Mode::Play(PlayOptions {
+                best_of: Some(3),
+                alternate: true,
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            parse(&["--random-start"]).unwrap(),
+            Mode::Play(PlayOptions {
+                random_start: true,
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            parse(&["--tui", "--best-of", "3"]).unwrap(),
+            Mode::Play(PlayOptions {
+                best_of: Some(3),
+                tui: true,
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            parse(&["--clear"]).unwrap(),
+            Mode::Play(PlayOptions {
+                clear: true,
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            parse(&["--bell", "--clear"]).unwrap(),
+            Mode::Play(PlayOptions {
+                clear: true,
+                bell: true,
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            parse(&["--quiet"]).unwrap(),
+            Mode::Play(PlayOptions {
+                quiet: true,
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            parse(&["--one-based", "--script", "moves.txt"]).unwrap(),
+            Mode::Play(PlayOptions {
+                indexing: Indexing::OneBased,
+                script: Some(PathBuf::from("moves.txt")),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            parse(&["--time-limit", "10", "--forfeit", "turn"]).unwrap(),
+            Mode::Play(PlayOptions {
+                time_limit: Some(Duration::from_secs(10)),
+                on_timeout: OnTimeout::ForfeitTurn,
+                ..Default::default()
+            })
+        );
+        assert_eq!(parse(&["--time-limit", "0"]).unwrap(), parse(&[]).unwrap());
+        assert!(parse(&["--time-limit", "soon"]).is_err());
+        assert!(parse(&["--forfeit", "match"]).is_err());
+        assert_eq!(
+            parse(&["--lang", "es"]).unwrap(),
+            Mode::Play(PlayOptions {
+                language: Some(Language::Spanish),
+                ..Default::default()
+            })
+        );
+        assert!(parse(&["--lang", "fr"]).is_err());
+        assert_eq!(
+            parse(&["--variant", "gomoku"]).unwrap(),
+            Mode::Play(PlayOptions {
+                variant: Variant::Gomoku,
+                ..Default::default()
+            })
+        );
+        assert!(matches!(
+            parse(&["--variant", "Notakto"]).unwrap(),
+            Mode::Play(PlayOptions {
+                variant: Variant::Notakto,
+                ..
+            })
+        ));
+        assert!(parse(&["--variant", "renju"]).is_err());
+        assert!(parse(&["--lang"]).is_err());
+        assert!(parse(&["--time-limit", "5", "--tui"]).is_err());
+        assert!(parse(&["--script"]).is_err());
+        assert!(parse(&["--script", "moves.txt", "--tui"]).is_err());
+        assert!(parse(&["--best-of", "3", "--script", "moves.txt"]).is_err());
+        assert_eq!(
+            parse(&["--tui", "--variant", "wild"])
+                .unwrap_err()
+                .to_string(),
+            "--tui can't play the wild variant"
+        );
+        assert!(parse(&["--tui", "--variant", "notakto"]).is_ok());
+        assert!(parse(&["--best-of", "0"]).is_err());
+        assert!(parse(&["--best-of", "three"]).is_err());
+    }
+
+    #[test]
+    fn solve() {
+        assert_eq!(
+            parse(&["solve", "--board", "X...O...."]).unwrap(),
+            Mode::Solve {
+                board: BoardState::from_compact("X...O....").unwrap()
+            }
+        );
+        assert!(parse(&["solve"]).is_err());
+        assert!(parse(&["solve", "--board"]).is_err());
+        assert!(parse(&["solve", "--board", "X..."]).is_ok());
+        assert!(parse(&["solve", "--board", "XX......."]).is_err());
+        assert!(parse(&["solve", "--board", "X........", "--one-based"]).is_err());
+        assert_eq!(
+            parse(&["solve", "--board", "XXXOOO..."])
+                .unwrap_err()
+                .to_string(),
+            "\"XXXOOO...\" could not arise from play"
+        );
+    }
+
+    #[test]
+    fn analyze() {
+        assert_eq!(
+            parse(&["analyze", "--board", "........."]).unwrap(),
+            Mode::Analyze {
+                board: BoardState::new()
+            }
+        );
+        assert_eq!(
+            parse(&["analyze"]).unwrap_err().to_string(),
+            "analyze needs a --board"
+        );
+        assert!(parse(&["analyze", "--board", "XX......."]).is_err());
+    }
+
+    #[test]
+    fn tree() {
+        assert_eq!(
+            parse(&["tree"]).unwrap(),
+            Mode::Tree {
+                board: BoardState::new(),
+                depth: 2,
+            }
+        );
+        assert_eq!(
+            parse(&["tree", "--depth", "1", "--board", "X...O...."]).unwrap(),
+            Mode::Tree {
+                board: BoardState::from_compact("X...O....").unwrap(),
+                depth: 1,
+            }
+        );
+        assert!(parse(&["tree", "--depth"]).is_err());
+        assert!(parse(&["tree", "--depth", "-1"]).is_err());
+        assert!(parse(&["tree", "--board", "XX......."]).is_err());
+    }
+
+    #[test]
+    fn simulate() {
+        assert_eq!(
+            parse(&["simulate"]).unwrap(),
+            Mode::Simulate {
+                games: 100,
+                x: Difficulty::Hard,
+                o: Difficulty::Hard,
+            }
+        );
+        assert_eq!(
+            parse(&["simulate", "--games", "5", "--x", "easy", "--o", "Medium"]).unwrap(),
+            Mode::Simulate {
+                games: 5,
+                x: Difficulty::Easy,
+                o: Difficulty::Medium,
+            }
+        );
+        assert!(parse(&["simulate", "--games"]).is_err());
+        assert!(parse(&["simulate", "--games", "many"]).is_err());
+        assert!(parse(&["simulate", "--x", "impossible"]).is_err());
+        assert!(parse(&["simulate", "--board", "........."]).is_err());
+    }
+
+    #[test]
+    fn replay() {
+        assert_eq!(
+            parse(&["replay", "game.txt"]).unwrap(),
+            Mode::Replay {
+                path: PathBuf::from("game.txt"),
+                delay: 500,
+            }
+        );
+        assert_eq!(
+            parse(&["replay", "--delay", "0", "game.txt"]).unwrap(),
+            Mode::Replay {
+                path: PathBuf::from("game.txt"),
+                delay: 0,
+            }
+        );
+        assert!(parse(&["replay"]).is_err());
+        assert!(parse(&["replay", "a.txt", "b.txt"]).is_err());
+        assert!(parse(&["replay", "game.txt", "--delay"]).is_err());
+        assert!(parse(&["replay", "game.txt", "--delay", "-5"]).is_err());
+    }
+
+    #[test]
+    fn netplay() {
+        assert_eq!(
+            parse(&["netplay", "host", "0.0.0.0:7878"]).unwrap(),
+            Mode::Netplay {
+                role: NetRole::Host,
+                address: "0.0.0.0:7878".to_owned(),
+                indexing: Indexing::ZeroBased,
+                language: None,
+            }
+        );
+        assert_eq!(
+            parse(&[
+                "netplay",
+                "join",
+                "--one-based",
+                "example.com:7878",
+                "--lang",
+                "es"
+            ])
+            .unwrap(),
+            Mode::Netplay {
+                role: NetRole::Join,
+                address: "example.com:7878".to_owned(),
+                indexing: Indexing::OneBased,
+                language: Some(Language::Spanish),
+            }
+        );
+        assert_eq!(
+            parse(&["netplay", "0.0.0.0:7878"]).unwrap_err().to_string(),
+            "netplay needs host, join, or serve"
+        );
+        assert_eq!(
+            parse(&["netplay", "serve", "0.0.0.0:7878"]).unwrap(),
+            Mode::Netplay {
+                role: NetRole::Serve,
+                address: "0.0.0.0:7878".to_owned(),
+                indexing: Indexing::ZeroBased,
+                language: None,
+            }
+        );
+        assert_eq!(
+            parse(&["netplay", "join"]).unwrap_err().to_string(),
+            "netplay needs an address"
+        );
+        assert!(parse(&["netplay", "host", "a:1", "b:2"]).is_err());
+        assert!(parse(&["netplay", "host", "a:1", "--tui"]).is_err());
+    }
+
+    #[test]
+    fn seed() {
+        assert_eq!(parse_with_seed(&[]).unwrap().seed, None);
+        assert_eq!(
+            parse_with_seed(&["simulate", "--seed", "42", "--games", "5"]).unwrap(),
+            Args {
+                mode: Mode::Simulate {
+                    games: 5,
+                    x: Difficulty::Hard,
+                    o: Difficulty::Hard,
+                },
+                seed: Some(42),
+            }
+        );
+        assert_eq!(
+            parse_with_seed(&["--seed", "7", "--one-based"]).unwrap(),
+            Args {
+                mode: Mode::Play(PlayOptions {
+                    indexing: Indexing::OneBased,
+                    ..Default::default()
+                }),
+                seed: Some(7),
+            }
+        );
+        assert!(parse_with_seed(&["--seed"]).is_err());
+        assert!(parse_with_seed(&["simulate", "--seed", "-1"]).is_err());
+    }
+}
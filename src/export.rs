@@ -0,0 +1,391 @@
+//! Renderings of boards for use outside the terminal
+
+use anyhow::{self, anyhow as anyhow_error};
+
+use crate::state::{parse_mark, BoardState, Player, TileState};
+
+/// The header of the settings row that starts a CSV move list
+const CSV_SETTINGS: &str = "size,win_length,players";
+
+/// The header of the rows of moves in a CSV move list
+const CSV_HEADER: &str = "move_number,player,x,y,mark";
+
+/// The header of move lists written before moves noted their marks, in which
+/// every player placed their own
+const CSV_HEADER_UNMARKED: &str = "move_number,player,x,y";
+
+impl BoardState {
+    /// Renders the board as an HTML `<table>` with a cell per tile, classed
+    /// `cell-x`, `cell-o`, `cell-z`, or `cell-empty` for styling
+    pub fn to_html(&self) -> String {
+        let mut html = String::from("<table class=\"board\">\n");
+
+        for y in 0..self.size() {
+            html.push_str("  <tr>");
+
+            for x in 0..self.size() {
+                let class = match self[(x, y)] {
+                    TileState::X => "cell-x",
+                    TileState::O => "cell-o",
+                    TileState::Z => "cell-z",
+                    TileState::Empty => "cell-empty",
+                };
+                let symbol = match self[(x, y)] {
+                    TileState::Empty => String::new(),
+                    tile => tile.to_string(),
+                };
+                html.push_str(&format!("<td class=\"{}\">{}</td>", class, symbol));
+            }
+
+            html.push_str("</tr>\n");
+        }
+
+        html.push_str("</table>\n");
+        html
+    }
+
+    /// Renders the board as a GitHub-flavored Markdown table, labeled with
+    /// its indices, with empty tiles shown as `·` to keep the columns aligned
+    pub fn to_markdown(&self) -> String {
+        let width = (self.size() - 1).to_string().len();
+        let row = |label: String, cells: Vec<String>| {
+            let cells = cells
+                .iter()
+                .map(|cell| format!(" {:^1$} |", cell, width))
+                .collect::<String>();
+            format!("| {:>1$} |{2}\n", label, width, cells)
+        };
+
+        let mut markdown = row(
+            String::new(),
+            (0..self.size()).map(|x| x.to_string()).collect(),
+        );
+        let rule = "-".repeat(width + 2);
+        markdown.push_str(&format!("|{}|\n", vec![rule; self.size() + 1].join("|")));
+
+        for y in 0..self.size() {
+            let cells = (0..self.size())
+                .map(|x| match self[(x, y)] {
+                    TileState::Empty => "·".to_owned(),
+                    tile => tile.to_string(),
+                })
+                .collect();
+            markdown.push_str(&row(y.to_string(), cells));
+        }
+
+        markdown
+    }
+
+    /// Draws the board as an SVG image, `cell_size` pixels per tile, with a
+    /// `<line>` for each inner grid line, a `<path>` for each X or Z, and a
+    /// `<circle>` for each O
+    pub fn to_svg(&self, cell_size: usize) -> String {
+        let side = cell_size * self.size();
+        // Marks are inset from the edges of their tiles
+        let inset = cell_size / 5;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" \
+             viewBox=\"0 0 {0} {0}\" fill=\"none\" stroke=\"black\">\n",
+            side
+        );
+
+        for i in 1..self.size() {
+            let offset = i * cell_size;
+            svg.push_str(&format!(
+                "  <line x1=\"{0}\" y1=\"0\" x2=\"{0}\" y2=\"{1}\"/>\n",
+                offset, side
+            ));
+            svg.push_str(&format!(
+                "  <line x1=\"0\" y1=\"{0}\" x2=\"{1}\" y2=\"{0}\"/>\n",
+                offset, side
+            ));
+        }
+
+        for y in 0..self.size() {
+            for x in 0..self.size() {
+                let (left, top) = (x * cell_size + inset, y * cell_size + inset);
+                let (right, bottom) = ((x + 1) * cell_size - inset, (y + 1) * cell_size - inset);
+
+                match self[(x, y)] {
+                    TileState::X => svg.push_str(&format!(
+                        "  <path class=\"x\" d=\"M {0} {1} L {2} {3} M {2} {1} L {0} {3}\"/>\n",
+                        left, top, right, bottom
+                    )),
+                    TileState::O => svg.push_str(&format!(
+                        "  <circle class=\"o\" cx=\"{}\" cy=\"{}\" r=\"{}\"/>\n",
+                        (left + right) / 2,
+                        (top + bottom) / 2,
+                        (right - left) / 2
+                    )),
+                    TileState::Z => svg.push_str(&format!(
+                        "  <path class=\"z\" d=\"M {0} {1} L {2} {1} L {0} {3} L {2} {3}\"/>\n",
+                        left, top, right, bottom
+                    )),
+                    TileState::Empty => {}
+                }
+            }
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Writes the moves played so far as CSV: a row of the board's settings
+    /// under the header `size,win_length,players`, then one row per move
+    /// under the header `move_number,player,x,y,mark`, counting moves from 1.
+    /// The mark differs from the player only in wild games.
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!(
+            "{}\n{},{},{}\n{}\n",
+            CSV_SETTINGS,
+            self.size(),
+            self.win_length(),
+            self.players(),
+            CSV_HEADER
+        );
+        for (i, (player, (x, y), mark)) in self.moves().enumerate() {
+            csv.push_str(&format!("{},{},{},{},{}\n", i + 1, player, x, y, mark));
+        }
+
+        csv
+    }
+
+    /// Reads back what [`to_csv`](Self::to_csv) writes, on a standard board if
+    /// the settings are left out. Without the `mark` column, each player is
+    /// taken to have placed their own mark. Whoever made the first move is
+    /// taken to have started. Fails if a header is missing, a row is malformed or
+    /// misnumbered, the settings don't fit, or a move is illegal or out of
+    /// turn.
+    pub fn from_csv(csv: &str) -> anyhow::Result<Self> {
+        let mut rows = csv
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .peekable();
+        let mut board = if rows.peek().map(|header| header.trim()) == Some(CSV_SETTINGS) {
+            rows.next();
+            let settings = rows.next().unwrap_or_default();
+            let numbers = settings
+                .split(',')
+                .map(|field| field.trim().parse::<usize>())
+                .collect::<Result<Vec<_>, _>>();
+            match numbers.as_deref() {
+                Ok(&[size, win_length, players]) => BoardState::checked(size, win_length, players)?,
+                _ => {
+                    return Err(anyhow_error!(
+                        "expected the size, win length, and players, got {:?}",
+                        settings
+                    ))
+                }
+            }
+        } else {
+            BoardState::new()
+        };
+
+        let marked = match rows.next() {
+            Some(header) if header.trim() == CSV_HEADER => true,
+            Some(header) if header.trim() == CSV_HEADER_UNMARKED => false,
+            header => {
+                return Err(anyhow_error!(
+                    "expected the header {:?}, got {:?}",
+                    CSV_HEADER,
+                    header.unwrap_or_default()
+                ))
+            }
+        };
+        let columns = if marked { 5 } else { 4 };
+
+        for (i, row) in rows.enumerate() {
+            let fields = row.split(',').map(str::trim).collect::<Vec<_>>();
+            let (number, player, x, y, mark) = match fields[..] {
+                [number, player, x, y] if !marked => (number, player, x, y, None),
+                [number, player, x, y, mark] if marked => (number, player, x, y, Some(mark)),
+                _ => {
+                    return Err(anyhow_error!(
+                        "expected {} columns in row {}, got {}",
+                        columns,
+                        i + 1,
+                        fields.len()
+                    ))
+                }
+            };
+            let parse = |field: &str| {
+                field.parse::<usize>().map_err(|_| {
+                    anyhow_error!("expected a number in row {}, got {:?}", i + 1, field)
+                })
+            };
+
+            if parse(number)? != i + 1 {
+                return Err(anyhow_error!(
+                    "expected move number {} in row {}, got {}",
+                    i + 1,
+                    i + 1,
+                    number
+                ));
+            }
+
+            let player = player.parse::<Player>()?;
+            if i == 0 {
+                board.start_with(player);
+            }
+
+            if player != board.next() {
+                return Err(anyhow_error!(
+                    "{} moved out of turn in row {}",
+                    player,
+                    i + 1
+                ));
+            }
+
+            let mark = mark.map(parse_mark).transpose()?;
+            board.replay_mark((parse(x)?, parse(y)?), mark)?;
+        }
+
+        Ok(board)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn to_html() {
+        let board = BoardState::from_moves(&[(1, 1), (0, 0), (2, 0)]).unwrap();
+        let html = board.to_html();
+        assert_eq!(html.matches("<td").count(), 9);
+        assert_eq!(html.matches("<tr>").count(), 3);
+        assert_eq!(html.matches("class=\"cell-x\">X</td>").count(), 2);
+        assert_eq!(html.matches("class=\"cell-o\">O</td>").count(), 1);
+        assert_eq!(html.matches("class=\"cell-empty\"></td>").count(), 6);
+        assert_eq!(
+            html.lines().nth(1).unwrap(),
+            "  <tr><td class=\"cell-o\">O</td><td class=\"cell-empty\"></td><td class=\"cell-x\">X</td></tr>"
+        );
+        assert!(html.starts_with("<table class=\"board\">\n"));
+        assert!(html.ends_with("</table>\n"));
+
+        assert_eq!(
+            BoardState::with_size(5).to_html().matches("<td").count(),
+            25
+        );
+    }
+
+    #[test]
+    fn to_svg() {
+        let board = BoardState::from_moves(&[(1, 1), (0, 0), (2, 0), (2, 2)]).unwrap();
+        let svg = board.to_svg(50);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"150\""));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<line").count(), 4);
+        assert_eq!(svg.matches("<path class=\"x\"").count(), 2);
+        assert_eq!(svg.matches("<circle class=\"o\"").count(), 2);
+        assert!(svg.contains("<path class=\"x\" d=\"M 60 60 L 90 90 M 90 60 L 60 90\"/>"));
+        assert!(svg.contains("<circle class=\"o\" cx=\"25\" cy=\"25\" r=\"15\"/>"));
+
+        let svg = BoardState::with_size(5).to_svg(20);
+        assert!(svg.contains("viewBox=\"0 0 100 100\""));
+        assert_eq!(svg.matches("<line").count(), 8);
+        assert!(!svg.contains("<path"));
+        assert!(!svg.contains("<circle"));
+    }
+
+    #[test]
+    fn to_csv() {
+        let board = BoardState::from_moves(&[(1, 1), (0, 0), (2, 0)]).unwrap();
+        assert_eq!(
+            board.to_csv(),
+            "size,win_length,players\n3,3,2\nmove_number,player,x,y,mark\n\
+             1,X,1,1,X\n2,O,0,0,O\n3,X,2,0,X\n"
+        );
+        let parsed = BoardState::from_csv(&board.to_csv()).unwrap();
+        assert_eq!(parsed, board);
+        assert_eq!(parsed.history(), board.history());
+
+        let mut board = BoardState::with_players(5, 4, 3);
+        board.set_first_player(Player::Z);
+        for &coords in [(0, 0), (4, 4), (2, 2)].iter() {
+            board.play(coords).unwrap();
+        }
+        assert!(board
+            .to_csv()
+            .starts_with("size,win_length,players\n5,4,3\n"));
+        let parsed = BoardState::from_csv(&board.to_csv()).unwrap();
+        assert_eq!(parsed, board);
+        assert_eq!(parsed.first_player(), Player::Z);
+
+        assert_eq!(BoardState::from_csv(CSV_HEADER).unwrap(), BoardState::new());
+        let parsed = BoardState::from_csv("move_number,player,x,y\r\n1, O, 2, 2\r\n\n").unwrap();
+        assert_eq!(parsed.first_player(), Player::O);
+        assert_eq!(parsed[(2, 2)], TileState::O);
+
+        let mut board = BoardState::new();
+        board.play_mark((1, 1), TileState::O).unwrap();
+        board.play_mark((0, 0), TileState::O).unwrap();
+        assert!(board.to_csv().ends_with("1,X,1,1,O\n2,O,0,0,O\n"));
+        let parsed = BoardState::from_csv(&board.to_csv()).unwrap();
+        assert_eq!(parsed, board);
+        assert_eq!(parsed[(1, 1)], TileState::O);
+    }
+
+    #[test]
+    fn from_csv_errors() {
+        for csv in [
+            "",
+            "1,X,1,1\n",
+            "move,player,x,y\n1,X,1,1\n",
+            "move_number,player,x,y\n1,X,1\n",
+            "move_number,player,x,y\n1,X,1,1,1\n",
+            "move_number,player,x,y\n2,X,1,1\n",
+            "move_number,player,x,y\n1,X,one,1\n",
+            "move_number,player,x,y\n1,Y,1,1\n",
+            "move_number,player,x,y\n1,Z,1,1\n",
+            "move_number,player,x,y\n1,X,1,1\n2,X,0,0\n",
+            "move_number,player,x,y\n1,X,1,1\n2,O,1,1\n",
+            "move_number,player,x,y\n1,X,3,0\n",
+            "move_number,player,x,y,mark\n1,X,1,1\n",
+            "move_number,player,x,y,mark\n1,X,1,1,Y\n",
+            "move_number,player,x,y,mark\n1,X,1,1,Z\n",
+            "size,win_length,players\n",
+            "size,win_length,players\n3,3\nmove_number,player,x,y\n",
+            "size,win_length,players\n3,4,2\nmove_number,player,x,y\n",
+            "size,win_length,players\n3,3,3\nmove_number,player,x,y\n",
+            "size,win_length,players\n4,4,2\n",
+            "size,win_length,players\n27,3,2\nmove_number,player,x,y,mark\n",
+            "size,win_length,players\n4294967296,3,2\nmove_number,player,x,y,mark\n",
+        ]
+        .iter()
+        {
+            assert!(BoardState::from_csv(csv).is_err(), "{:?}", csv);
+        }
+        assert_eq!(
+            BoardState::from_csv("move_number,player,x,y\n1,X,1,1\n2,O\n")
+                .unwrap_err()
+                .to_string(),
+            "expected 4 columns in row 2, got 2"
+        );
+    }
+
+    #[test]
+    fn to_markdown() {
+        let board = BoardState::from_moves(&[(1, 1), (0, 0), (2, 0)]).unwrap();
+        assert_eq!(
+            board.to_markdown(),
+            "\
+|   | 0 | 1 | 2 |
+|---|---|---|---|
+| 0 | O | · | X |
+| 1 | · | X | · |
+| 2 | · | · | · |
+"
+        );
+
+        let mut board = BoardState::with_size(11);
+        board.play((10, 10)).unwrap();
+        let markdown = board.to_markdown();
+        assert!(markdown.starts_with("|    | 0  | 1  |"));
+        assert!(markdown.contains("\n|----|----|"));
+        assert!(
+            markdown.ends_with("| 10 | ·  | ·  | ·  | ·  | ·  | ·  | ·  | ·  | ·  | ·  | X  |\n")
+        );
+    }
+}
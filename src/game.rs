@@ -0,0 +1,716 @@
+use core::str::FromStr;
+
+use anyhow::{self, anyhow as anyhow_error};
+
+use crate::state::{BoardState, GameError, GameResult, Player, TileState, BOARD_SIZE};
+
+/// The rules a game is played by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rules {
+    /// The side length of the board
+    pub size: usize,
+    /// How many identical tiles in a row complete a line
+    pub win_length: usize,
+    /// How many players take turns, either 2 or 3
+    pub players: usize,
+    pub first_player: Player,
+    /// Whether completing a line loses instead of winning
+    pub misere: bool,
+    /// Whether players may place any player's mark, with a line won by
+    /// whoever completes it whatever mark it holds
+    pub wild: bool,
+    /// The player whose mark every player places, if they share one, with a
+    /// line won by whoever completes it
+    pub shared_mark: Option<Player>,
+}
+
+impl Rules {
+    /// The mark `player` places unless they choose another: their own, or
+    /// the shared mark
+    pub fn mark(&self, player: Player) -> TileState {
+        self.shared_mark.unwrap_or(player).into()
+    }
+
+    /// Whether each player places only their own mark, so that a line is won
+    /// by whoever's mark fills it
+    pub fn own_marks(&self) -> bool {
+        !self.wild && self.shared_mark.is_none()
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            size: BOARD_SIZE,
+            win_length: BOARD_SIZE,
+            players: 2,
+            first_player: Player::X,
+            misere: false,
+            wild: false,
+            shared_mark: None,
+        }
+    }
+}
+
+/// A preset of rules under a familiar name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Variant {
+    /// Three in a row on a 3 by 3 board
+    #[default]
+    Standard,
+    /// Five in a row on a 15 by 15 board
+    Gomoku,
+    /// Three in a row on a 3 by 3 board, placing either mark on each turn
+    Wild,
+    /// Notakto: both players place X on a 3 by 3 board, and whoever completes
+    /// a line loses
+    Notakto,
+}
+
+impl Variant {
+    pub fn rules(self) -> Rules {
+        match self {
+            Self::Standard => Rules::default(),
+            Self::Gomoku => Rules {
+                size: 15,
+                win_length: 5,
+                ..Rules::default()
+            },
+            Self::Wild => Rules {
+                wild: true,
+                ..Rules::default()
+            },
+            Self::Notakto => Rules {
+                misere: true,
+                shared_mark: Some(Player::X),
+                ..Rules::default()
+            },
+        }
+    }
+}
+
+impl FromStr for Variant {
+    type Err = anyhow::Error;
+
+    /// Parses a variant by name, ignoring case
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "standard" => Ok(Self::Standard),
+            "gomoku" => Ok(Self::Gomoku),
+            "wild" => Ok(Self::Wild),
+            "notakto" => Ok(Self::Notakto),
+            _ => Err(anyhow_error!(
+                "unknown variant {:?}; expected standard, gomoku, wild, or notakto",
+                s
+            )),
+        }
+    }
+}
+
+/// What to call each player, by default just their symbol
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Names {
+    pub x: Option<String>,
+    pub o: Option<String>,
+}
+
+impl Names {
+    /// The player's name, or their symbol if they have none
+    pub fn name(&self, player: Player) -> String {
+        self.get(player)
+            .map_or_else(|| player.to_string(), str::to_owned)
+    }
+
+    /// The player's name followed by their symbol, as in `Alice (X)`, or just
+    /// their symbol if they have no name
+    pub fn label(&self, player: Player) -> String {
+        match self.get(player) {
+            Some(name) => format!("{} ({})", name, player),
+            None => player.to_string(),
+        }
+    }
+
+    fn get(&self, player: Player) -> Option<&str> {
+        match player {
+            Player::X => self.x.as_deref(),
+            Player::O => self.o.as_deref(),
+            Player::Z => None,
+        }
+    }
+}
+
+/// A board together with the rules it is played by
+#[derive(Debug, Clone, PartialEq)]
+pub struct Game {
+    board: BoardState,
+    rules: Rules,
+}
+
+impl Game {
+    /// Starts a standard game
+    pub fn new() -> Self {
+        Self::with_rules(Rules::default())
+    }
+
+    /// Starts configuring a game, from the standard rules
+    pub fn builder() -> GameBuilder {
+        GameBuilder::default()
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the size, win length, or number of players are invalid, as
+    /// [`BoardState::with_players`] does, or if misère play has other than two
+    /// players, since then a loss doesn't say who wins.
+    pub fn with_rules(rules: Rules) -> Self {
+        assert!(
+            !rules.misere || rules.players == 2,
+            "misère play needs two players, not {}",
+            rules.players
+        );
+        let mut board = BoardState::with_players(rules.size, rules.win_length, rules.players);
+        board.set_first_player(rules.first_player);
+        Game { board, rules }
+    }
+
+    /// Resumes a game from `board`, under rules taken from its size, win
+    /// length, and players, where completing a line with your own mark wins
+    pub fn from_board(board: BoardState) -> Self {
+        Self::with_board(board, Rules::default()).unwrap()
+    }
+
+    /// Resumes a game from `board` under `rules`, which say how lines are won
+    /// and marks are chosen, with the size, win length, players, and first
+    /// player taken from the board. Fails if misère play would have other
+    /// than two players.
+    pub fn with_board(board: BoardState, rules: Rules) -> anyhow::Result<Self> {
+        if rules.misere && board.players() != 2 {
+            return Err(anyhow_error!(
+                "misère play needs two players, not {}",
+                board.players()
+            ));
+        }
+
+        let rules = Rules {
+            size: board.size(),
+            win_length: board.win_length(),
+            players: board.players(),
+            first_player: board.first_player(),
+            ..rules
+        };
+        Ok(Game { board, rules })
+    }
+
+    pub fn board(&self) -> &BoardState {
+        &self.board
+    }
+
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    pub fn next(&self) -> Player {
+        self.board.next()
+    }
+
+    /// Plays `coords` for the next player, failing if the move is illegal or
+    /// the game is over
+    pub fn play(&mut self, coords: (usize, usize)) -> Result<&mut Self, GameError> {
+        self.play_mark(coords, self.rules.mark(self.next()))
+    }
+
+    /// Places `mark` at `coords` for the next player, failing if the move is
+    /// illegal, the game is over, or the rules don't let the player place
+    /// that mark
+    pub fn play_mark(
+        &mut self,
+        coords: (usize, usize),
+        mark: TileState,
+    ) -> Result<&mut Self, GameError> {
+        if self.result() != GameResult::InProgress {
+            return Err(GameError::GameOver);
+        }
+        let allowed = match self.rules.shared_mark {
+            Some(shared) => mark == shared.into(),
+            None => self.rules.wild || mark == self.next().into(),
+        };
+        if !allowed {
+            return Err(GameError::WrongMark { mark });
+        }
+
+        #[cfg(feature = "logging")]
+        let player = self.board.next();
+        self.board.play_mark(coords, mark)?;
+
+        #[cfg(feature = "logging")]
+        {
+            log::debug!("move played: {} at {:?}", player, coords);
+            match self.result() {
+                GameResult::InProgress => {}
+                GameResult::Win(player) => log::debug!("game won by {}", player),
+                GameResult::Draw => log::debug!("game drawn"),
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// The state of the game under its rules: unless players place only
+    /// their own marks, whoever completes a line wins it whatever mark it
+    /// holds, and in misère play, whoever completes a line loses
+    pub fn result(&self) -> GameResult {
+        match self.board.status() {
+            GameResult::Win(player) => {
+                let player = if !self.rules.own_marks() {
+                    // Play stops at the first line, so the last mover made it
+                    self.next().previous_player(self.board.players())
+                } else {
+                    player
+                };
+                GameResult::Win(if self.rules.misere {
+                    player.opponent()
+                } else {
+                    player
+                })
+            }
+            result => result,
+        }
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Configures the rules of a [`Game`]; see [`Game::builder`]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GameBuilder {
+    rules: Rules,
+    /// Whether the win length has been set, rather than following the size
+    win_length_set: bool,
+}
+
+impl GameBuilder {
+    /// Sets the side length of the board, and the win length too unless it
+    /// has been set explicitly
+    pub fn size(mut self, size: usize) -> Self {
+        self.rules.size = size;
+        if !self.win_length_set {
+            self.rules.win_length = size;
+        }
+        self
+    }
+
+    pub fn win_length(mut self, win_length: usize) -> Self {
+        self.rules.win_length = win_length;
+        self.win_length_set = true;
+        self
+    }
+
+    /// Sets how many players take turns; three need a board bigger than 3 by
+    /// 3
+    pub fn players(mut self, players: usize) -> Self {
+        self.rules.players = players;
+        self
+    }
+
+    pub fn first_player(mut self, first_player: Player) -> Self {
+        self.rules.first_player = first_player;
+        self
+    }
+
+    /// Sets whether whoever completes a line loses, which needs two players
+    pub fn misere(mut self, misere: bool) -> Self {
+        self.rules.misere = misere;
+        self
+    }
+
+    pub fn wild(mut self, wild: bool) -> Self {
+        self.rules.wild = wild;
+        self
+    }
+
+    pub fn shared_mark(mut self, shared_mark: Option<Player>) -> Self {
+        self.rules.shared_mark = shared_mark;
+        self
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the rules are invalid, as [`Game::with_rules`] does.
+    pub fn build(self) -> Game {
+        Game::with_rules(self.rules)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    mod names {
+        use super::*;
+
+        #[test]
+        fn name() {
+            let names = Names {
+                x: Some("Alice".to_owned()),
+                o: None,
+            };
+            assert_eq!(names.name(Player::X), "Alice");
+            assert_eq!(names.name(Player::O), "O");
+            assert_eq!(names.label(Player::X), "Alice (X)");
+            assert_eq!(names.label(Player::O), "O");
+            assert_eq!(Names::default().label(Player::X), "X");
+        }
+    }
+
+    mod variant {
+        use super::*;
+
+        #[test]
+        fn rules() {
+            assert_eq!(Variant::default().rules(), Rules::default());
+            let game = Game::with_rules(Variant::Gomoku.rules());
+            assert_eq!(game.board(), &BoardState::with_win_length(15, 5));
+        }
+
+        #[test]
+        fn from_str() {
+            assert_eq!("gomoku".parse::<Variant>().unwrap(), Variant::Gomoku);
+            assert_eq!("Standard".parse::<Variant>().unwrap(), Variant::Standard);
+            assert_eq!("WILD".parse::<Variant>().unwrap(), Variant::Wild);
+            assert_eq!("notakto".parse::<Variant>().unwrap(), Variant::Notakto);
+            assert!("renju".parse::<Variant>().is_err());
+        }
+    }
+
+    mod game {
+        use super::*;
+        use crate::state::TileState;
+
+        #[cfg(feature = "logging")]
+        #[test]
+        fn logging() {
+            use std::{
+                sync::Mutex,
+                thread::{self, ThreadId},
+            };
+
+            /// Captures the messages logged on each thread, since tests run
+            /// in parallel
+            struct Capture(Mutex<Vec<(ThreadId, String)>>);
+
+            impl log::Log for Capture {
+                fn enabled(&self, _metadata: &log::Metadata) -> bool {
+                    true
+                }
+
+                fn log(&self, record: &log::Record) {
+                    let message = (thread::current().id(), record.args().to_string());
+                    self.0.lock().unwrap().push(message);
+                }
+
+                fn flush(&self) {}
+            }
+
+            static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+            log::set_logger(&CAPTURE).unwrap();
+            log::set_max_level(log::LevelFilter::Debug);
+
+            let mut game = Game::new();
+            for &coords in [(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)].iter() {
+                game.play(coords).unwrap();
+            }
+            assert!(game.play((2, 2)).is_err());
+
+            let messages = CAPTURE
+                .0
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(thread, _)| *thread == thread::current().id())
+                .map(|(_, message)| message.clone())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                messages,
+                vec![
+                    "move played: X at (0, 0)",
+                    "move played: O at (0, 1)",
+                    "move played: X at (1, 0)",
+                    "move played: O at (1, 1)",
+                    "move played: X at (2, 0)",
+                    "game won by X",
+                ]
+            );
+        }
+
+        #[test]
+        fn builder() {
+            let game = Game::builder().build();
+            assert_eq!(game, Game::new());
+            assert_eq!(game.board(), &BoardState::new());
+            assert_eq!(game.rules(), &Rules::default());
+
+            let game = Game::builder().size(5).win_length(4).build();
+            assert_eq!(game.board(), &BoardState::with_win_length(5, 4));
+            assert_eq!(game.rules().win_length, 4);
+
+            let game = Game::builder().win_length(4).size(5).build();
+            assert_eq!(game.board(), &BoardState::with_win_length(5, 4));
+
+            let game = Game::builder().size(4).build();
+            assert_eq!(game.rules().win_length, 4);
+        }
+
+        #[test]
+        #[should_panic]
+        fn builder_win_length_too_long() {
+            Game::builder().win_length(4).build();
+        }
+
+        #[test]
+        fn play() {
+            let mut game = Game::builder().size(5).win_length(4).build();
+            for &coords in [(0, 0), (0, 1), (1, 0), (1, 1), (2, 0), (2, 1)].iter() {
+                game.play(coords).unwrap();
+            }
+            assert_eq!(game.result(), GameResult::InProgress);
+            assert_eq!(
+                game.play((5, 0)).unwrap_err(),
+                GameError::OutOfBounds { x: 5, y: 0 }
+            );
+            game.play((3, 0)).unwrap();
+            assert_eq!(game.result(), GameResult::Win(Player::X));
+            assert_eq!(game.play((4, 4)).unwrap_err(), GameError::GameOver);
+            assert_eq!(game.board().history().len(), 7);
+        }
+
+        #[test]
+        fn three_players() {
+            let mut game = Game::builder().size(5).win_length(3).players(3).build();
+            assert_eq!(game.board().players(), 3);
+            for &coords in [(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2)].iter() {
+                game.play(coords).unwrap();
+            }
+            assert_eq!(game.next(), Player::X);
+            game.play((2, 0)).unwrap();
+            assert_eq!(game.result(), GameResult::Win(Player::X));
+        }
+
+        #[test]
+        #[should_panic]
+        fn three_players_too_small() {
+            Game::builder().players(3).build();
+        }
+
+        #[test]
+        #[should_panic]
+        fn misere_three_players() {
+            Game::builder().size(4).players(3).misere(true).build();
+        }
+
+        #[test]
+        fn from_board() {
+            let mut board = BoardState::with_win_length(4, 3);
+            board.play((1, 1)).unwrap();
+            let game = Game::from_board(board.clone());
+            assert_eq!(game.board(), &board);
+            assert_eq!(game.next(), Player::O);
+            assert_eq!(
+                game.rules(),
+                &Rules {
+                    size: 4,
+                    win_length: 3,
+                    ..Rules::default()
+                }
+            );
+        }
+
+        #[test]
+        fn with_board() {
+            let mut board = BoardState::with_win_length(4, 3);
+            board.set_first_player(Player::O);
+            board.play((1, 1)).unwrap();
+            let game = Game::with_board(board.clone(), Variant::Notakto.rules()).unwrap();
+            assert_eq!(game.board(), &board);
+            assert_eq!(
+                game.rules(),
+                &Rules {
+                    size: 4,
+                    win_length: 3,
+                    first_player: Player::O,
+                    ..Variant::Notakto.rules()
+                }
+            );
+
+            assert!(
+                Game::with_board(BoardState::with_players(4, 4, 3), Variant::Notakto.rules())
+                    .is_err()
+            );
+            let game =
+                Game::with_board(BoardState::with_players(4, 4, 3), Variant::Wild.rules()).unwrap();
+            assert!(game.rules().wild);
+        }
+
+        #[test]
+        fn first_player() {
+            let mut game = Game::builder().first_player(Player::O).build();
+            assert_eq!(game.next(), Player::O);
+            game.play((1, 1)).unwrap();
+            assert_eq!(game.next(), Player::X);
+            assert_eq!(game.board()[(1, 1)], TileState::O);
+        }
+
+        #[test]
+        fn misere() {
+            let mut game = Game::builder().misere(true).build();
+            for &coords in [(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)].iter() {
+                game.play(coords).unwrap();
+            }
+            assert_eq!(game.board().won(), Some(Player::X));
+            assert_eq!(game.result(), GameResult::Win(Player::O));
+            assert_eq!(game.play((2, 2)).unwrap_err(), GameError::GameOver);
+        }
+
+        #[test]
+        fn misere_reuses_win_detection() {
+            let moves = [(0, 0), (1, 1), (0, 1), (2, 2), (0, 2)];
+            for &(misere, winner) in [(false, Player::X), (true, Player::O)].iter() {
+                let mut game = Game::builder().misere(misere).build();
+                for &coords in moves.iter() {
+                    assert_eq!(game.result(), GameResult::InProgress);
+                    game.play(coords).unwrap();
+                }
+                assert_eq!(game.result(), GameResult::Win(winner));
+                assert_eq!(
+                    game.board().winning_line(),
+                    Some((Player::X, vec![(0, 0), (0, 1), (0, 2)]))
+                );
+            }
+
+            // Draws are draws either way
+            let moves = [
+                (0, 0),
+                (1, 0),
+                (2, 0),
+                (1, 1),
+                (0, 1),
+                (0, 2),
+                (2, 1),
+                (2, 2),
+                (1, 2),
+            ];
+            let mut game = Game::builder().misere(true).build();
+            for &coords in moves.iter() {
+                game.play(coords).unwrap();
+            }
+            assert_eq!(game.result(), GameResult::Draw);
+        }
+
+        #[test]
+        fn wild() {
+            use TileState::*;
+
+            // O completes a line of X's
+            let mut game = Game::with_rules(Variant::Wild.rules());
+            for &(coords, mark) in [((0, 0), X), ((2, 2), O), ((1, 0), X)].iter() {
+                game.play_mark(coords, mark).unwrap();
+                assert_eq!(game.result(), GameResult::InProgress);
+            }
+            assert_eq!(game.next(), Player::O);
+            game.play_mark((2, 0), X).unwrap();
+            assert_eq!(game.board().won(), Some(Player::X));
+            assert_eq!(game.result(), GameResult::Win(Player::O));
+
+            // X, moving first, completes a line of O's
+            let mut game = Game::with_rules(Variant::Wild.rules());
+            for &(coords, mark) in [((0, 0), O), ((2, 2), X), ((1, 0), O), ((1, 1), X)].iter() {
+                game.play_mark(coords, mark).unwrap();
+            }
+            assert_eq!(game.next(), Player::X);
+            game.play_mark((2, 0), O).unwrap();
+            assert_eq!(game.result(), GameResult::Win(Player::X));
+            assert_eq!(game.play_mark((2, 1), X).unwrap_err(), GameError::GameOver);
+
+            let mut game = Game::builder().wild(true).misere(true).build();
+            for &coords in [(0, 0), (1, 0), (2, 0)].iter() {
+                game.play_mark(coords, X).unwrap();
+            }
+            assert_eq!(game.result(), GameResult::Win(Player::O));
+        }
+
+        #[test]
+        fn notakto() {
+            // Both players place X, and X completes the first line
+            let mut game = Game::with_rules(Variant::Notakto.rules());
+            for &coords in [(0, 0), (1, 1), (2, 1), (1, 0)].iter() {
+                game.play(coords).unwrap();
+                assert_eq!(game.board()[coords], TileState::X);
+                assert_eq!(game.result(), GameResult::InProgress);
+            }
+            assert_eq!(game.next(), Player::X);
+            game.play((1, 2)).unwrap();
+            assert_eq!(game.board().won(), Some(Player::X));
+            assert_eq!(game.result(), GameResult::Win(Player::O));
+
+            // O completes it instead
+            let mut game = Game::with_rules(Variant::Notakto.rules());
+            for &coords in [(0, 0), (1, 1), (2, 1)].iter() {
+                game.play(coords).unwrap();
+            }
+            assert_eq!(game.next(), Player::O);
+            game.play_mark((2, 2), TileState::X).unwrap();
+            assert_eq!(game.board().won(), Some(Player::X));
+            assert_eq!(game.result(), GameResult::Win(Player::X));
+            assert_eq!(game.play((2, 0)).unwrap_err(), GameError::GameOver);
+
+            let mut game = Game::with_rules(Variant::Notakto.rules());
+            game.play((0, 0)).unwrap();
+            assert_eq!(
+                game.play_mark((1, 1), TileState::O).unwrap_err(),
+                GameError::WrongMark { mark: TileState::O }
+            );
+            assert_eq!(game.next(), Player::O);
+        }
+
+        #[test]
+        fn wild_alternates_turns() {
+            use TileState::*;
+
+            let mut game = Game::builder().wild(true).build();
+            let mut players = Vec::new();
+            for &(coords, mark) in [((0, 0), X), ((0, 1), X), ((0, 2), O), ((1, 1), O)].iter() {
+                players.push(game.next());
+                game.play_mark(coords, mark).unwrap();
+            }
+            assert_eq!(players, vec![Player::X, Player::O, Player::X, Player::O]);
+            assert_eq!(game.next(), Player::X);
+            assert_eq!(game.board().history().len(), 4);
+
+            assert_eq!(
+                game.play_mark((2, 2), Empty).unwrap_err(),
+                GameError::WrongMark { mark: Empty }
+            );
+            assert_eq!(
+                game.play_mark((2, 2), Z).unwrap_err(),
+                GameError::WrongMark { mark: Z }
+            );
+            assert_eq!(
+                game.play_mark((1, 1), X).unwrap_err(),
+                GameError::AlreadyPlayed { x: 1, y: 1 }
+            );
+            assert_eq!(game.next(), Player::X);
+
+            // Outside wild play, each player places only their own mark
+            let mut game = Game::new();
+            assert_eq!(
+                game.play_mark((0, 0), O).unwrap_err(),
+                GameError::WrongMark { mark: O }
+            );
+            game.play_mark((0, 0), X).unwrap();
+            assert_eq!(game.board()[(0, 0)], X);
+        }
+    }
+}
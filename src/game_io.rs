@@ -0,0 +1,365 @@
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, Write},
+};
+
+use anyhow::{self, anyhow as anyhow_error};
+
+use crate::{
+    game::Names,
+    input::{input_command, BlankInput, Command, Indexing},
+    messages::{Language, Message},
+    state::BoardState,
+};
+
+/// The ANSI escape codes that clear the screen and move the cursor home
+const CLEAR: &str = "\x1b[2J\x1b[H";
+
+/// The interface through which a game talks to its players
+pub trait GameIO {
+    /// Asks the next player on `board` what to do.
+    ///
+    /// Errors are fatal to the game; an implementation that can recover from
+    /// bad input should keep asking until it gets a command.
+    fn read_command(&mut self, board: &BoardState) -> anyhow::Result<Command>;
+
+    fn show_board(&mut self, board: &BoardState) -> anyhow::Result<()>;
+
+    fn show_message(&mut self, message: &str) -> anyhow::Result<()>;
+
+    /// Announces how a game or match ended, which even the quietest interface
+    /// should show
+    fn show_result(&mut self, message: &str) -> anyhow::Result<()> {
+        self.show_message(message)
+    }
+
+    /// Suggests a move, or reports that there is none to suggest
+    fn show_hint(&mut self, hint: Option<(usize, usize)>) -> anyhow::Result<()> {
+        let message = match hint {
+            Some((x, y)) => Message::Hint { x, y },
+            None => Message::NoHint,
+        };
+        self.show_message(&self.language().text(message))
+    }
+
+    /// Reminds the player how to enter a move after a command that wasn't one
+    fn show_coordinate_help(&mut self) -> anyhow::Result<()> {
+        self.show_message(&self.language().text(Message::EnterCoordinates))
+    }
+
+    /// The language in which to address the players, English by default
+    fn language(&self) -> Language {
+        Language::default()
+    }
+
+    /// Gets the attention of players who may not be watching, such as when a
+    /// game ends. Does nothing by default.
+    fn alert(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Plays over a text stream such as stdin and stdout, prompting for moves
+pub struct StdioGameIO<R, W> {
+    input: R,
+    output: W,
+    indexing: Indexing,
+    names: Names,
+    /// Whether to clear the screen before each board
+    clear: bool,
+    /// Whether to ring the terminal bell for alerts
+    bell: bool,
+    /// Whether to show only results, without boards, prompts, or messages
+    quiet: bool,
+    language: Language,
+}
+
+impl<R: BufRead, W: Write> StdioGameIO<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Self::with_indexing(input, output, Indexing::default())
+    }
+
+    pub fn with_indexing(input: R, output: W, indexing: Indexing) -> Self {
+        StdioGameIO {
+            input,
+            output,
+            indexing,
+            names: Names::default(),
+            clear: false,
+            bell: false,
+            quiet: false,
+            language: Language::default(),
+        }
+    }
+
+    /// Addresses the players by `names` in prompts
+    pub fn with_names(mut self, names: Names) -> Self {
+        self.names = names;
+        self
+    }
+
+    /// Clears the screen before showing each board, if `clear` is set, so
+    /// that only the current board is visible. The output should be a
+    /// terminal that understands ANSI escape codes.
+    pub fn with_clear(mut self, clear: bool) -> Self {
+        self.clear = clear;
+        self
+    }
+
+    /// Rings the terminal bell for alerts, if `bell` is set
+    pub fn with_bell(mut self, bell: bool) -> Self {
+        self.bell = bell;
+        self
+    }
+
+    /// Shows only results, if `quiet` is set, for scripts that just want to
+    /// know how the game ended
+    pub fn with_quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Prompts in `language`
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+}
+
+impl<R: BufRead, W: Write> GameIO for StdioGameIO<R, W> {
+    fn read_command(&mut self, board: &BoardState) -> anyhow::Result<Command> {
+        loop {
+            let mut sink = io::sink();
+            let mut output: &mut dyn Write = if self.quiet {
+                &mut sink
+            } else {
+                &mut self.output
+            };
+
+            match input_command(
+                &mut self.input,
+                &mut output,
+                board,
+                self.indexing,
+                &self.names,
+                self.language,
+            ) {
+                Ok(command) => return Ok(command),
+                Err(error) if error.is::<io::Error>() => return Err(error),
+                Err(error) if error.is::<BlankInput>() => continue,
+                Err(error) => writeln!(output, "{}", error)?,
+            }
+
+            writeln!(output, "{}", self.language.text(Message::EnterCoordinates))?;
+        }
+    }
+
+    fn show_board(&mut self, board: &BoardState) -> anyhow::Result<()> {
+        if self.quiet {
+            return Ok(());
+        }
+
+        #[cfg(feature = "color")]
+        let render = board.display_colored();
+        #[cfg(not(feature = "color"))]
+        let render = board.render();
+        if self.clear {
+            write!(self.output, "{}", CLEAR)?;
+        }
+        writeln!(self.output, "\n{}\n", render.with_highlight(true))?;
+        Ok(())
+    }
+
+    fn show_message(&mut self, message: &str) -> anyhow::Result<()> {
+        if !self.quiet {
+            writeln!(self.output, "{}", message)?;
+        }
+        Ok(())
+    }
+
+    fn show_result(&mut self, message: &str) -> anyhow::Result<()> {
+        writeln!(self.output, "{}", message)?;
+        Ok(())
+    }
+
+    fn show_hint(&mut self, hint: Option<(usize, usize)>) -> anyhow::Result<()> {
+        let message = match hint.map(|coords| self.indexing.to_player(coords)) {
+            Some((x, y)) => Message::Hint { x, y },
+            None => Message::NoHint,
+        };
+        self.show_message(&self.language.text(message))
+    }
+
+    fn language(&self) -> Language {
+        self.language
+    }
+
+    fn alert(&mut self) -> anyhow::Result<()> {
+        if self.bell {
+            write!(self.output, "\x07")?;
+            self.output.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Plays a fixed list of commands, recording everything shown
+#[derive(Debug, Default)]
+pub struct ScriptedGameIO {
+    commands: VecDeque<Command>,
+    pub boards: Vec<BoardState>,
+    pub messages: Vec<String>,
+}
+
+impl ScriptedGameIO {
+    pub fn new(moves: &[(usize, usize)]) -> Self {
+        Self::with_commands(moves.iter().copied().map(Command::Move))
+    }
+
+    pub fn with_commands(commands: impl IntoIterator<Item = Command>) -> Self {
+        ScriptedGameIO {
+            commands: commands.into_iter().collect(),
+            ..Default::default()
+        }
+    }
+}
+
+impl GameIO for ScriptedGameIO {
+    fn read_command(&mut self, _board: &BoardState) -> anyhow::Result<Command> {
+        self.commands
+            .pop_front()
+            .ok_or_else(|| anyhow_error!("the script has run out of commands"))
+    }
+
+    fn show_board(&mut self, board: &BoardState) -> anyhow::Result<()> {
+        self.boards.push(board.clone());
+        Ok(())
+    }
+
+    fn show_message(&mut self, message: &str) -> anyhow::Result<()> {
+        self.messages.push(message.to_owned());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    mod stdio_game_io {
+        use super::*;
+
+        #[test]
+        fn read_command() {
+            let mut output = Vec::new();
+            let mut io = StdioGameIO::new("1,2,3\n1,x\n2,1\nhint\nc1\n".as_bytes(), &mut output);
+            let mut board = BoardState::new();
+            board.play((0, 0)).unwrap();
+            assert_eq!(io.read_command(&board).unwrap(), Command::Move((2, 1)));
+            assert_eq!(io.read_command(&board).unwrap(), Command::Hint);
+            assert_eq!(io.read_command(&board).unwrap(), Command::Move((2, 0)));
+            let output = String::from_utf8(output).unwrap();
+            assert!(output.starts_with("Move 2 — O > "));
+            assert_eq!(output.matches("Enter coordinates x, y").count(), 2);
+        }
+
+        #[test]
+        fn read_command_blank() {
+            let mut output = Vec::new();
+            let mut io = StdioGameIO::new("\n  \n1,1\n".as_bytes(), &mut output);
+            assert_eq!(
+                io.read_command(&BoardState::new()).unwrap(),
+                Command::Move((1, 1))
+            );
+            assert_eq!(
+                String::from_utf8(output).unwrap(),
+                "Move 1 — X > Move 1 — X > Move 1 — X > "
+            );
+        }
+
+        #[test]
+        fn with_names() {
+            let mut output = Vec::new();
+            let names = Names {
+                x: Some("Alice".to_owned()),
+                o: Some("Bob".to_owned()),
+            };
+            let mut io = StdioGameIO::new("1,1\n0,0\n".as_bytes(), &mut output).with_names(names);
+            let mut board = BoardState::new();
+            assert_eq!(io.read_command(&board).unwrap(), Command::Move((1, 1)));
+            board.play((1, 1)).unwrap();
+            assert_eq!(io.read_command(&board).unwrap(), Command::Move((0, 0)));
+            assert_eq!(
+                String::from_utf8(output).unwrap(),
+                "Move 1 — Alice (X) > Move 2 — Bob (O) > "
+            );
+        }
+
+        #[test]
+        fn with_clear() {
+            let board = BoardState::new();
+            let mut output = Vec::new();
+            let mut io = StdioGameIO::new("".as_bytes(), &mut output).with_clear(true);
+            io.show_board(&board).unwrap();
+            io.show_message("X wins!").unwrap();
+            io.show_board(&board).unwrap();
+            let output = String::from_utf8(output).unwrap();
+            assert!(output.starts_with("\x1b[2J\x1b[H\n"));
+            assert_eq!(output.matches("\x1b[2J\x1b[H").count(), 2);
+
+            let mut output = Vec::new();
+            StdioGameIO::new("".as_bytes(), &mut output)
+                .show_board(&board)
+                .unwrap();
+            assert!(!String::from_utf8(output).unwrap().contains('\x1b'));
+        }
+
+        #[test]
+        fn with_quiet() {
+            let mut output = Vec::new();
+            let mut io = StdioGameIO::new("1,x\n1,1\n".as_bytes(), &mut output).with_quiet(true);
+            let board = BoardState::new();
+            io.show_board(&board).unwrap();
+            assert_eq!(io.read_command(&board).unwrap(), Command::Move((1, 1)));
+            io.show_message("Try 1, 1").unwrap();
+            io.show_hint(Some((1, 1))).unwrap();
+            io.show_result("Draw!").unwrap();
+            assert_eq!(String::from_utf8(output).unwrap(), "Draw!\n");
+        }
+
+        #[test]
+        fn with_indexing() {
+            let mut output = Vec::new();
+            let mut io = StdioGameIO::with_indexing(
+                "0,1\n1,3\n".as_bytes(),
+                &mut output,
+                Indexing::OneBased,
+            );
+            assert_eq!(
+                io.read_command(&BoardState::new()).unwrap(),
+                Command::Move((0, 2))
+            );
+            io.show_hint(Some((0, 2))).unwrap();
+            let output = String::from_utf8(output).unwrap();
+            assert!(output.contains("coordinates start at 1"));
+            assert!(output.ends_with("Try 1, 3\n"));
+        }
+    }
+
+    mod scripted_game_io {
+        use super::*;
+
+        #[test]
+        fn read_command() {
+            let board = BoardState::new();
+            let mut io = ScriptedGameIO::new(&[(0, 0), (2, 1)]);
+            assert_eq!(io.read_command(&board).unwrap(), Command::Move((0, 0)));
+            assert_eq!(io.read_command(&board).unwrap(), Command::Move((2, 1)));
+            assert!(io.read_command(&board).is_err());
+
+            let mut io = ScriptedGameIO::with_commands(vec![Command::Hint]);
+            assert_eq!(io.read_command(&board).unwrap(), Command::Hint);
+            assert!(io.read_command(&board).is_err());
+        }
+    }
+}
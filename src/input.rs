@@ -1,31 +1,521 @@
-use core::str::FromStr;
-use std::io::{self, Write};
+use core::{
+    convert::TryFrom,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+use std::{
+    io::{self, BufRead, Write},
+    path::PathBuf,
+};
 
 use anyhow::{self, anyhow as anyhow_error};
 
-use crate::state::Player;
+use crate::{
+    game::Names,
+    messages::{Language, Message},
+    notation::parse_algebraic,
+    state::{BoardState, TileState},
+};
 
-pub fn input_coords(next: Player) -> anyhow::Result<(usize, usize)> {
-    let string = prompt(&format!("{} > ", next))?;
-    let numbers = parse_list::<usize>(&string)?;
-    if numbers.len() == 2 {
-        Ok((numbers[0], numbers[1]))
+/// Something a player can enter at the prompt
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Move((usize, usize)),
+    /// Places a chosen mark, as players may in a wild game
+    Place((usize, usize), TileState),
+    /// Asks for a suggested move
+    Hint,
+    /// Abandons the game, as when input runs out
+    Quit,
+    /// Starts over from an empty board
+    Restart,
+    /// Writes the game so far to a file
+    Save(PathBuf),
+    /// Replaces the game with one saved to a file
+    Load(PathBuf),
+    /// Gives up the game, as when a player runs out of time
+    Forfeit,
+}
+
+/// The error for a blank line of input, which callers should answer by
+/// prompting again rather than by complaining
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlankInput;
+
+impl Display for BlankInput {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "no input was entered")
+    }
+}
+
+impl std::error::Error for BlankInput {}
+
+/// Whether players count coordinates and cell indices from 0 or from 1
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Indexing {
+    #[default]
+    ZeroBased,
+    OneBased,
+}
+
+impl Indexing {
+    /// Converts coordinates as entered by a player to board coordinates
+    pub fn to_board(self, (x, y): (usize, usize)) -> anyhow::Result<(usize, usize)> {
+        Ok((self.to_board_number(x)?, self.to_board_number(y)?))
+    }
+
+    /// Converts a single coordinate or cell index as entered by a player
+    fn to_board_number(self, number: usize) -> anyhow::Result<usize> {
+        match self {
+            Self::ZeroBased => Ok(number),
+            Self::OneBased => number
+                .checked_sub(1)
+                .ok_or_else(|| anyhow_error!("coordinates start at 1")),
+        }
+    }
+
+    /// Converts board coordinates to coordinates as a player would enter them
+    pub fn to_player(self, (x, y): (usize, usize)) -> (usize, usize) {
+        match self {
+            Self::ZeroBased => (x, y),
+            Self::OneBased => (x + 1, y + 1),
+        }
+    }
+}
+
+/// Prompts the next player on `board`, called by their name from `names`, for
+/// a command in `language`, numbering the move they are about to make
+pub fn input_command(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    board: &BoardState,
+    indexing: Indexing,
+    names: &Names,
+    language: Language,
+) -> anyhow::Result<Command> {
+    let message = language.text(Message::Prompt {
+        number: board.move_count() + 1,
+        label: &names.label(board.next()),
+    });
+    match prompt(input, output, &message)? {
+        Some(string) => parse_command(&string, indexing, board.size()),
+        None => Ok(Command::Quit),
+    }
+}
+
+/// Parses a command for a `size` by `size` board, accepting moves either as
+/// numeric coordinates or in algebraic notation, optionally followed by the
+/// mark to place, as in `1, 1 O`
+pub fn parse_command(input: &str, indexing: Indexing, size: usize) -> anyhow::Result<Command> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        Err(BlankInput.into())
+    } else if trimmed.eq_ignore_ascii_case("hint") {
+        Ok(Command::Hint)
+    } else if trimmed.eq_ignore_ascii_case("quit") {
+        Ok(Command::Quit)
+    } else if trimmed.eq_ignore_ascii_case("restart") {
+        Ok(Command::Restart)
+    } else if let Some(path) = strip_command(trimmed, "save") {
+        match path {
+            "" => Err(anyhow_error!("save needs a file to save to")),
+            path => Ok(Command::Save(PathBuf::from(path))),
+        }
+    } else if let Some(path) = strip_command(trimmed, "load") {
+        match path {
+            "" => Err(anyhow_error!("load needs a file to load from")),
+            path => Ok(Command::Load(PathBuf::from(path))),
+        }
+    } else if let Some((coords, mark)) = strip_mark(trimmed) {
+        match parse_command(coords, indexing, size)? {
+            Command::Move(coords) => Ok(Command::Place(coords, mark)),
+            _ => Err(anyhow_error!("only a move can be given a mark")),
+        }
+    } else if trimmed.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        parse_algebraic(trimmed, size).map(Command::Move)
     } else {
-        Err(anyhow_error!(
-            "expected exactly 2 input numbers, got {}",
+        parse_coords(input, indexing, size).map(Command::Move)
+    }
+}
+
+/// If `input` ends with a player's mark after whitespace, returns what comes
+/// before it and the mark
+fn strip_mark(input: &str) -> Option<(&str, TileState)> {
+    let (rest, mark) = input.rsplit_once(char::is_whitespace)?;
+    let mut chars = mark.chars();
+    match (chars.next(), chars.next()) {
+        (Some(mark), None) if mark.is_ascii_alphabetic() => {
+            Some((rest, TileState::try_from(mark).ok()?))
+        }
+        _ => None,
+    }
+}
+
+/// If `input` is the command `name`, case-insensitively, followed by nothing
+/// or by whitespace, returns whatever follows
+fn strip_command<'a>(input: &'a str, name: &str) -> Option<&'a str> {
+    let rest = input.get(name.len()..)?;
+    if input[..name.len()].eq_ignore_ascii_case(name)
+        && (rest.is_empty() || rest.starts_with(char::is_whitespace))
+    {
+        Some(rest.trim())
+    } else {
+        None
+    }
+}
+
+/// Parses numeric coordinates `x, y`, or a single row-major cell index, on a
+/// `size` by `size` board
+pub fn parse_coords(
+    input: &str,
+    indexing: Indexing,
+    size: usize,
+) -> anyhow::Result<(usize, usize)> {
+    let numbers = tokens(input)
+        .map(parse_number)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    match numbers[..] {
+        [index] => {
+            let cell = indexing.to_board_number(index)?;
+            if cell < size * size {
+                Ok((cell % size, cell / size))
+            } else {
+                Err(anyhow_error!("cell {} is off the board", index))
+            }
+        }
+        [x, y] => indexing.to_board((x, y)),
+        _ => Err(anyhow_error!(
+            "expected 1 or 2 input numbers, got {}",
             numbers.len()
-        ))
+        )),
     }
 }
 
-pub fn prompt(prompt: &str) -> io::Result<String> {
-    print!("{}", prompt);
-    io::stdout().flush()?;
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)?;
-    Ok(input)
+/// Writes `prompt` and reads a line of input, returning `None` if the input
+/// has ended
+pub fn prompt(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    prompt: &str,
+) -> io::Result<Option<String>> {
+    write!(output, "{}", prompt)?;
+    output.flush()?;
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        // Finish the prompt's line
+        writeln!(output)?;
+        Ok(None)
+    } else {
+        Ok(Some(line))
+    }
 }
 
+/// Parses items separated by commas, whitespace, or both, ignoring empty
+/// items between consecutive separators
 pub fn parse_list<T: FromStr>(input: &str) -> Result<Vec<T>, <T as FromStr>::Err> {
-    input.split(",").map(|item| item.trim().parse()).collect()
+    tokens(input).map(str::parse).collect()
+}
+
+/// Splits `input` into the items [`parse_list`] would parse
+fn tokens(input: &str) -> impl Iterator<Item = &str> {
+    input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|item| !item.is_empty())
+}
+
+/// Parses a coordinate or cell index, explaining which token was wrong
+fn parse_number(token: &str) -> anyhow::Result<usize> {
+    token.parse().map_err(|_| {
+        let negative = token.strip_prefix('-').is_some_and(|digits| {
+            !digits.is_empty() && digits.bytes().all(|byte| byte.is_ascii_digit())
+        });
+        if negative {
+            anyhow_error!("coordinates cannot be negative, got {}", token)
+        } else {
+            anyhow_error!("could not parse '{}' as a number", token)
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    use Indexing::*;
+
+    #[test]
+    fn prompt() {
+        let mut output = Vec::new();
+        let mut input = "a\nb".as_bytes();
+        assert_eq!(
+            super::prompt(&mut input, &mut output, "> ").unwrap(),
+            Some("a\n".to_owned())
+        );
+        assert_eq!(
+            super::prompt(&mut input, &mut output, "> ").unwrap(),
+            Some("b".to_owned())
+        );
+        assert_eq!(super::prompt(&mut input, &mut output, "> ").unwrap(), None);
+        assert_eq!(output, b"> > > \n");
+    }
+
+    #[test]
+    fn parse_command() {
+        assert_eq!(
+            super::parse_command("1, 2\n", ZeroBased, 3).unwrap(),
+            Command::Move((1, 2))
+        );
+        assert_eq!(
+            super::parse_command("1, 2\n", OneBased, 3).unwrap(),
+            Command::Move((0, 1))
+        );
+        assert_eq!(
+            super::parse_command("hint\n", ZeroBased, 3).unwrap(),
+            Command::Hint
+        );
+        assert_eq!(
+            super::parse_command(" HINT ", OneBased, 3).unwrap(),
+            Command::Hint
+        );
+        assert!(super::parse_command("hints\n", ZeroBased, 3).is_err());
+        assert_eq!(
+            super::parse_command("quit\n", ZeroBased, 3).unwrap(),
+            Command::Quit
+        );
+        assert_eq!(
+            super::parse_command("Quit\n", ZeroBased, 3).unwrap(),
+            Command::Quit
+        );
+        assert_eq!(
+            super::parse_command("RESTART\n", ZeroBased, 3).unwrap(),
+            Command::Restart
+        );
+        assert_eq!(
+            super::parse_command("restart", OneBased, 3).unwrap(),
+            Command::Restart
+        );
+        assert_eq!(
+            super::parse_command("b3\n", ZeroBased, 3).unwrap(),
+            Command::Move((1, 2))
+        );
+        assert_eq!(
+            super::parse_command(" a1 ", OneBased, 3).unwrap(),
+            Command::Move((0, 0))
+        );
+    }
+
+    #[test]
+    fn parse_command_mark() {
+        assert_eq!(
+            super::parse_command("1, 2 O\n", ZeroBased, 3).unwrap(),
+            Command::Place((1, 2), TileState::O)
+        );
+        assert_eq!(
+            super::parse_command("1,2  x", OneBased, 3).unwrap(),
+            Command::Place((0, 1), TileState::X)
+        );
+        assert_eq!(
+            super::parse_command("b3 o", ZeroBased, 3).unwrap(),
+            Command::Place((1, 2), TileState::O)
+        );
+        assert_eq!(
+            super::parse_command("1 2", ZeroBased, 3).unwrap(),
+            super::parse_command("1, 2", ZeroBased, 3).unwrap()
+        );
+        assert!(super::parse_command("1, 2 Q", ZeroBased, 3).is_err());
+        assert!(super::parse_command("1, 2 O X", ZeroBased, 3).is_err());
+        assert!(super::parse_command("restart O", ZeroBased, 3).is_err());
+        assert!(super::parse_command("O", ZeroBased, 3).is_err());
+    }
+
+    #[test]
+    fn parse_command_save() {
+        assert_eq!(
+            super::parse_command("save game.txt\n", ZeroBased, 3).unwrap(),
+            Command::Save(PathBuf::from("game.txt"))
+        );
+        assert_eq!(
+            super::parse_command("SAVE  my games/1.txt ", ZeroBased, 3).unwrap(),
+            Command::Save(PathBuf::from("my games/1.txt"))
+        );
+        assert!(super::parse_command("save\n", ZeroBased, 3).is_err());
+        assert!(super::parse_command("saved game.txt\n", ZeroBased, 3).is_err());
+    }
+
+    #[test]
+    fn parse_command_load() {
+        assert_eq!(
+            super::parse_command("load game.txt\n", ZeroBased, 3).unwrap(),
+            Command::Load(PathBuf::from("game.txt"))
+        );
+        assert_eq!(
+            super::parse_command("Load /tmp/game.txt", OneBased, 3).unwrap(),
+            Command::Load(PathBuf::from("/tmp/game.txt"))
+        );
+        assert!(super::parse_command("load \n", ZeroBased, 3).is_err());
+    }
+
+    #[test]
+    fn parse_command_blank() {
+        for input in ["", "\n", " \t\n"].iter() {
+            let error = super::parse_command(input, ZeroBased, 3).unwrap_err();
+            assert_eq!(error.downcast_ref::<BlankInput>(), Some(&BlankInput));
+        }
+        let error = super::parse_command(",\n", ZeroBased, 3).unwrap_err();
+        assert!(!error.is::<BlankInput>());
+    }
+
+    #[test]
+    fn parse_coords() {
+        assert_eq!(super::parse_coords("0,2\n", ZeroBased, 3).unwrap(), (0, 2));
+        assert_eq!(super::parse_coords("0,0\n", ZeroBased, 3).unwrap(), (0, 0));
+        assert!(super::parse_coords("1,2,3\n", ZeroBased, 3).is_err());
+        assert!(super::parse_coords("1,x\n", ZeroBased, 3).is_err());
+    }
+
+    #[test]
+    fn parse_list() {
+        for input in ["1 1", "1, 1", " 1 ,  1 ", "1,1\n", "1\t1", "1,,1"].iter() {
+            assert_eq!(super::parse_list::<usize>(input).unwrap(), vec![1, 1]);
+        }
+        assert_eq!(super::parse_list::<usize>(" 2 0 ").unwrap(), vec![2, 0]);
+        assert!(super::parse_list::<usize>("\n").unwrap().is_empty());
+        assert!(super::parse_list::<usize>("1 x").is_err());
+    }
+
+    #[test]
+    fn parse_coords_separators() {
+        for input in ["2 1", "2, 1", " 2 ,  1 ", "2 ,1\n"].iter() {
+            assert_eq!(super::parse_coords(input, ZeroBased, 3).unwrap(), (2, 1));
+        }
+        assert_eq!(super::parse_coords("2 1 ", OneBased, 3).unwrap(), (1, 0));
+        assert!(super::parse_coords("2 1 0", ZeroBased, 3).is_err());
+    }
+
+    #[test]
+    fn parse_coords_errors() {
+        let error = |input| {
+            super::parse_coords(input, ZeroBased, 3)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(error("1,a"), "could not parse 'a' as a number");
+        assert_eq!(error("x1 2"), "could not parse 'x1' as a number");
+        assert_eq!(error("1.5, 2"), "could not parse '1.5' as a number");
+        assert_eq!(error("-1, 2"), "coordinates cannot be negative, got -1");
+        assert_eq!(error("1 -20"), "coordinates cannot be negative, got -20");
+        assert_eq!(error("-, 2"), "could not parse '-' as a number");
+        assert_eq!(error("--1, 2"), "could not parse '--1' as a number");
+        assert_eq!(error("-+1, 2"), "could not parse '-+1' as a number");
+    }
+
+    #[test]
+    fn parse_coords_index() {
+        assert_eq!(super::parse_coords("4\n", ZeroBased, 3).unwrap(), (1, 1));
+        assert_eq!(super::parse_coords("0\n", ZeroBased, 3).unwrap(), (0, 0));
+        assert_eq!(super::parse_coords("5\n", ZeroBased, 3).unwrap(), (2, 1));
+        assert_eq!(super::parse_coords("8\n", ZeroBased, 3).unwrap(), (2, 2));
+        assert_eq!(super::parse_coords("9\n", ZeroBased, 4).unwrap(), (1, 2));
+        assert_eq!(
+            super::parse_coords("9\n", ZeroBased, 3)
+                .unwrap_err()
+                .to_string(),
+            "cell 9 is off the board"
+        );
+        assert_eq!(super::parse_coords("9\n", OneBased, 3).unwrap(), (2, 2));
+        assert_eq!(super::parse_coords("1\n", OneBased, 3).unwrap(), (0, 0));
+        assert!(super::parse_coords("0\n", OneBased, 3).is_err());
+        assert!(super::parse_coords("10\n", OneBased, 3).is_err());
+    }
+
+    #[test]
+    fn parse_coords_one_based() {
+        assert_eq!(super::parse_coords("1,1\n", OneBased, 3).unwrap(), (0, 0));
+        assert_eq!(super::parse_coords("3,3\n", OneBased, 3).unwrap(), (2, 2));
+        assert_eq!(super::parse_coords("1,3\n", OneBased, 3).unwrap(), (0, 2));
+        for input in ["0,1\n", "1,0\n", "0,0\n"].iter() {
+            let error = super::parse_coords(input, OneBased, 3).unwrap_err();
+            assert_eq!(error.to_string(), "coordinates start at 1");
+        }
+    }
+
+    /// Runs `input` through every parser, which may reject it but must not
+    /// panic
+    fn parse_everything(input: &str, size: usize) {
+        let _ = super::parse_list::<usize>(input);
+        let _ = super::parse_list::<i64>(input);
+        let _ = super::parse_algebraic(input, size);
+        for &indexing in [ZeroBased, OneBased].iter() {
+            let _ = super::parse_coords(input, indexing, size);
+            let _ = super::parse_command(input, indexing, size);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn parse_arbitrary(input in any::<String>(), size in 1..=26usize) {
+            parse_everything(&input, size);
+        }
+
+        #[test]
+        fn parse_almost_coords(
+            input in "[0-9a-z,\\- \t\n\u{0}+.٣１¹]{0,40}",
+            size in 1..=26usize,
+        ) {
+            parse_everything(&input, size);
+        }
+    }
+
+    #[test]
+    fn parse_weird_input() {
+        for input in [
+            "\0",
+            "1\0,1",
+            "1,\u{0}1",
+            "a\0",
+            "99999999999999999999999999",
+            "18446744073709551615",
+            "18446744073709551616, 0",
+            "0, 18446744073709551616",
+            "a18446744073709551616",
+            "z99999999999999999999999999",
+            "-18446744073709551616",
+            "٣,١",
+            "١",
+            "１,２",
+            "¹ ²",
+            "½",
+            "a٣",
+            "é1",
+            "savé",
+            "loadé",
+            "hint\0",
+        ]
+        .iter()
+        {
+            parse_everything(input, 3);
+            assert!(
+                super::parse_coords(input, ZeroBased, 3).is_err(),
+                "{:?}",
+                input
+            );
+            assert!(
+                super::parse_command(input, ZeroBased, 3).is_err(),
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn indexing() {
+        assert_eq!(ZeroBased.to_player((0, 2)), (0, 2));
+        assert_eq!(OneBased.to_player((0, 2)), (1, 3));
+        assert_eq!(
+            OneBased.to_board(OneBased.to_player((2, 1))).unwrap(),
+            (2, 1)
+        );
+    }
 }
@@ -0,0 +1,34 @@
+//! Tic-tac-toe boards of any size, an AI to play on them, and a text
+//! interface for human players
+//!
+//! The boards, rules, and AI do no IO of their own. The interfaces that do,
+//! `game_io`, `input`, `timer`, and `tui`, need the default `io` feature, so
+//! that building without it leaves an engine that can run anywhere,
+//! including in the browser through the `wasm` feature.
+
+pub mod ai;
+pub mod array_board;
+pub mod bitboard;
+mod export;
+pub mod game;
+#[cfg(feature = "io")]
+pub mod game_io;
+#[cfg(feature = "io")]
+pub mod input;
+pub mod messages;
+#[cfg(test)]
+mod mirror_test;
+#[cfg(feature = "net")]
+pub mod net;
+pub mod notakto;
+pub mod notation;
+#[cfg(feature = "json")]
+pub mod protocol;
+pub mod state;
+#[cfg(feature = "io")]
+pub mod timer;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod ultimate;
+#[cfg(feature = "wasm")]
+pub mod wasm;
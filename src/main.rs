@@ -1,39 +1,1648 @@
-mod input;
-mod state;
+mod cli;
 
-use input::input_coords;
-use state::BoardState;
+use std::{
+    env, fs,
+    io::{self, BufReader, IsTerminal, Write},
+    path::Path,
+    process, thread,
+    time::Duration,
+};
 
-fn main() -> anyhow::Result<()> {
-    let mut state = BoardState::new();
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
-    println!("\n{}\n", state);
+use cli::{Mode, NetRole, PlayOptions};
+#[cfg(feature = "net")]
+use std::sync::Arc;
+#[cfg(feature = "net")]
+use tic_tac_toe::net::{self, GameServer, NetGameIO};
+#[cfg(feature = "tui")]
+use tic_tac_toe::tui::TuiGameIO;
+use tic_tac_toe::{
+    ai::{self, Difficulty},
+    game::{Game, Names, Rules},
+    game_io::{GameIO, StdioGameIO},
+    input::{parse_command, BlankInput, Command, Indexing},
+    messages::{Language, Message},
+    notation::format_algebraic,
+    state::{BoardState, GameResult, Player},
+    timer::{TimedGameIO, TimedInput},
+};
+
+/// The exit status of a run that failed with an error, kept apart from the
+/// statuses [`exit_code`] gives game results
+const ERROR_EXIT_CODE: i32 = 5;
+
+fn main() {
+    #[cfg(feature = "logging")]
+    env_logger::init();
+
+    if let Err(error) = run() {
+        eprintln!("Error: {:?}", error);
+        process::exit(ERROR_EXIT_CODE);
+    }
+}
+
+fn run() -> anyhow::Result<()> {
+    let args = cli::parse_args(env::args().skip(1))?;
+    let mut rng = seeded_rng(args.seed);
+
+    match args.mode {
+        Mode::Play(PlayOptions {
+            variant,
+            indexing,
+            names,
+            best_of,
+            alternate,
+            random_start,
+            tui,
+            clear,
+            bell,
+            quiet,
+            script,
+            time_limit,
+            on_timeout,
+            language,
+        }) => {
+            let language = resolve_language(language);
+            let first_player = match (random_start, quiet) {
+                (true, false) => choose_first_player(&mut rng, &names, language, io::stdout())?,
+                // The toss decides the game but isn't its result
+                (true, true) => choose_first_player(&mut rng, &names, language, io::sink())?,
+                (false, _) => Player::X,
+            };
+            let rules = Rules {
+                first_player,
+                ..variant.rules()
+            };
+
+            // Escape codes and bells would only clutter output piped to a file
+            let clear = clear && io::stdout().is_terminal();
+            let bell = bell && io::stdout().is_terminal();
+
+            let result = if let Some(path) = script {
+                let script = fs::read_to_string(&path)?;
+                let mut io = StdioGameIO::new(io::empty(), io::stdout())
+                    .with_names(names.clone())
+                    .with_language(language)
+                    .with_quiet(quiet);
+                play_script(&mut io, &script, indexing, &names, rules)?
+            } else if tui {
+                run_tui(&names, language, best_of, alternate, rules)?
+            } else if let Some(limit) = time_limit {
+                let mut input = TimedInput::new(BufReader::new(io::stdin()));
+                if !quiet {
+                    input = input.with_countdown(io::stdout(), language);
+                }
+                let deadline = input.deadline();
+                let io = StdioGameIO::with_indexing(input, io::stdout(), indexing)
+                    .with_names(names.clone())
+                    .with_language(language)
+                    .with_clear(clear)
+                    .with_bell(bell)
+                    .with_quiet(quiet);
+                let mut io = TimedGameIO::new(io, deadline, limit)
+                    .with_on_timeout(on_timeout)
+                    .with_rng(StdRng::seed_from_u64(rng.gen()));
+                play_games(&mut io, &names, best_of, alternate, rules)?
+            } else {
+                let mut io = StdioGameIO::with_indexing(io::stdin().lock(), io::stdout(), indexing)
+                    .with_names(names.clone())
+                    .with_language(language)
+                    .with_clear(clear)
+                    .with_bell(bell)
+                    .with_quiet(quiet);
+                play_games(&mut io, &names, best_of, alternate, rules)?
+            };
+
+            // Exiting skips destructors, so nothing else will flush stdout
+            io::stdout().flush()?;
+            process::exit(exit_code(result));
+        }
+        Mode::Solve { board } => run_solve(&board, io::stdout())?,
+        Mode::Analyze { board } => run_analyze(&board, io::stdout())?,
+        Mode::Tree { board, depth } => {
+            io::stdout().write_all(ai::search_dot(&board, depth).as_bytes())?
+        }
+        Mode::Simulate { games, x, o } => run_simulate(games, x, o, &mut rng, io::stdout())?,
+        Mode::Replay { path, delay } => {
+            let board = load_board(&path)?;
+            let mut io = StdioGameIO::new(io::empty(), io::stdout());
+            replay_game(&mut io, &board, Duration::from_millis(delay))?;
+        }
+        Mode::Netplay {
+            role,
+            address,
+            indexing,
+            language,
+        } => {
+            let result = run_netplay(role, &address, indexing, resolve_language(language))?;
+            io::stdout().flush()?;
+            process::exit(exit_code(result));
+        }
+    }
+
+    Ok(())
+}
+
+/// The language asked for, or else the one the `LANG` environment variable
+/// names, or else the default
+fn resolve_language(language: Option<Language>) -> Language {
+    language
+        .or_else(|| {
+            env::var("LANG")
+                .ok()
+                .and_then(|locale| Language::from_locale(&locale))
+        })
+        .unwrap_or_default()
+}
+
+/// The exit status reporting `result`, for scripts: 0 for a draw, 1, 2, or 3
+/// for a win by X, O, or Z, and 4 for a game abandoned before it ended. Errors
+/// exit with [`ERROR_EXIT_CODE`] instead.
+fn exit_code(result: GameResult) -> i32 {
+    match result {
+        GameResult::Draw => 0,
+        GameResult::Win(Player::X) => 1,
+        GameResult::Win(Player::O) => 2,
+        GameResult::Win(Player::Z) => 3,
+        GameResult::InProgress => 4,
+    }
+}
+
+/// How many games each player has won, and how many were drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Tally {
+    x_wins: usize,
+    o_wins: usize,
+    z_wins: usize,
+    draws: usize,
+}
+
+impl Tally {
+    /// Counts a finished game; games still in progress are not counted
+    fn record(&mut self, result: GameResult) {
+        match result {
+            GameResult::Win(Player::X) => self.x_wins += 1,
+            GameResult::Win(Player::O) => self.o_wins += 1,
+            GameResult::Win(Player::Z) => self.z_wins += 1,
+            GameResult::Draw => self.draws += 1,
+            GameResult::InProgress => {}
+        }
+    }
+
+    /// The outcome of a match played to a majority of `best_of`: a win for
+    /// whoever has reached it, or still in progress if nobody has
+    fn match_result(&self, best_of: usize) -> GameResult {
+        [Player::X, Player::O, Player::Z]
+            .iter()
+            .copied()
+            .find(|&player| self.wins(player) >= best_of.div_ceil(2))
+            .map_or(GameResult::InProgress, GameResult::Win)
+    }
+
+    fn wins(&self, player: Player) -> usize {
+        match player {
+            Player::X => self.x_wins,
+            Player::O => self.o_wins,
+            Player::Z => self.z_wins,
+        }
+    }
+}
+
+/// Creates the random number generator for everything random in the program,
+/// from `seed` if given and from system entropy otherwise
+fn seeded_rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
+/// Flips a coin for who moves first, and writes who won the toss to `output`
+fn choose_first_player(
+    rng: &mut impl Rng,
+    names: &Names,
+    language: Language,
+    mut output: impl Write,
+) -> anyhow::Result<Player> {
+    let player = if rng.gen() { Player::X } else { Player::O };
+    writeln!(
+        output,
+        "{}",
+        language.text(Message::GoesFirst(&names.name(player)))
+    )?;
+    Ok(player)
+}
+
+/// Writes the outcome of `board` under optimal play, and the moves that
+/// achieve it, to `output`
+fn run_solve(board: &BoardState, mut output: impl Write) -> anyhow::Result<()> {
+    let solution = ai::solve(board)?;
+
+    match solution.result {
+        GameResult::Win(player) => writeln!(output, "{} wins", player)?,
+        _ => writeln!(output, "Draw")?,
+    }
+
+    if !solution.moves.is_empty() {
+        let moves = solution
+            .moves
+            .iter()
+            .map(|(x, y)| format!("{}, {}", x, y))
+            .collect::<Vec<_>>();
+        writeln!(output, "Optimal moves: {}", moves.join("; "))?;
+    }
+
+    Ok(())
+}
+
+/// Writes every legal move on `board` to `output`, best first, with its rank
+/// and whether it wins, draws, or loses for the player to move
+fn run_analyze(board: &BoardState, mut output: impl Write) -> anyhow::Result<()> {
+    for value in ai::analyze(board)? {
+        let outcome = match value.result {
+            GameResult::Draw => "draw",
+            GameResult::Win(player) if player == board.next() => "win",
+            _ => "loss",
+        };
+        let (x, y) = value.coords;
+        writeln!(output, "{}. {}, {}: {}", value.rank, x, y, outcome)?;
+    }
+
+    Ok(())
+}
+
+/// Plays `games` games between an AI at difficulty `x` and one at `o`, and
+/// writes how many each side won and how many were drawn to `output`
+fn run_simulate(
+    games: usize,
+    x: Difficulty,
+    o: Difficulty,
+    rng: &mut impl Rng,
+    mut output: impl Write,
+) -> anyhow::Result<()> {
+    let mut tally = Tally::default();
+
+    for _ in 0..games {
+        tally.record(ai::play_ai_game(x, o, rng).status());
+    }
+
+    writeln!(output, "X wins: {}", tally.x_wins)?;
+    writeln!(output, "O wins: {}", tally.o_wins)?;
+    writeln!(output, "Draws: {}", tally.draws)?;
+    Ok(())
+}
+
+/// Plays a game, or a match if `best_of` is given, with cursor controls in a
+/// full-screen terminal interface, and returns the result of the game or match
+#[cfg(feature = "tui")]
+fn run_tui(
+    names: &Names,
+    language: Language,
+    best_of: Option<usize>,
+    alternate: bool,
+    rules: Rules,
+) -> anyhow::Result<GameResult> {
+    let mut io = TuiGameIO::new(io::stdout())?
+        .with_names(names.clone())
+        .with_language(language);
+    play_games(&mut io, names, best_of, alternate, rules)
+}
+
+#[cfg(not(feature = "tui"))]
+fn run_tui(
+    _names: &Names,
+    _language: Language,
+    _best_of: Option<usize>,
+    _alternate: bool,
+    _rules: Rules,
+) -> anyhow::Result<GameResult> {
+    Err(anyhow::anyhow!(
+        "--tui needs a build with the tui feature enabled"
+    ))
+}
+
+/// Plays a game at the terminal against an opponent at the other end of a
+/// connection, hosted at or joined from `address` as `role` says, and
+/// returns its result. Serving plays no game of its own, and so only returns
+/// if the server fails.
+#[cfg(feature = "net")]
+fn run_netplay(
+    role: NetRole,
+    address: &str,
+    indexing: Indexing,
+    language: Language,
+) -> anyhow::Result<GameResult> {
+    let connection = match role {
+        NetRole::Host => {
+            let host = net::host(address)?;
+            let address = host.local_addr()?.to_string();
+            println!("{}", language.text(Message::Hosting(&address)));
+            host.accept()?
+        }
+        NetRole::Join => net::join(address)?,
+        NetRole::Serve => {
+            let host = net::host(address)?;
+            let address = host.local_addr()?.to_string();
+            println!("{}", language.text(Message::Serving(&address)));
+            match host.serve(&Arc::new(GameServer::new()))? {}
+        }
+    };
+    let io = StdioGameIO::with_indexing(io::stdin().lock(), io::stdout(), indexing)
+        .with_language(language);
+    let mut io = NetGameIO::new(io, connection);
+    play_game(&mut io, &Names::default(), Rules::default())
+}
+
+#[cfg(not(feature = "net"))]
+fn run_netplay(
+    _role: NetRole,
+    _address: &str,
+    _indexing: Indexing,
+    _language: Language,
+) -> anyhow::Result<GameResult> {
+    Err(anyhow::anyhow!(
+        "netplay needs a build with the net feature enabled"
+    ))
+}
+
+/// Plays a game, or a match if `best_of` is given, through `io`, and returns
+/// the result of the game or match
+fn play_games(
+    io: &mut impl GameIO,
+    names: &Names,
+    best_of: Option<usize>,
+    alternate: bool,
+    rules: Rules,
+) -> anyhow::Result<GameResult> {
+    match best_of {
+        None => play_game(io, names, rules),
+        Some(best_of) => {
+            Ok(play_match(io, names, best_of, alternate, rules)?.match_result(best_of))
+        }
+    }
+}
+
+/// Plays rounds through `io` until a player has won a majority of `best_of`,
+/// showing the score after each round, and returns the final tally. Draws
+/// don't count towards the majority. If the players quit, the match ends
+/// without a winner.
+///
+/// The first player of `rules` starts every round, unless `alternate` is set,
+/// in which case they start the first round, their opponent the second, and
+/// so on.
+fn play_match(
+    io: &mut impl GameIO,
+    names: &Names,
+    best_of: usize,
+    alternate: bool,
+    mut rules: Rules,
+) -> anyhow::Result<Tally> {
+    let target = best_of.div_ceil(2);
+    let mut tally = Tally::default();
+
+    loop {
+        let result = play_game(io, names, rules)?;
+        if alternate {
+            rules.first_player = rules.first_player.opponent();
+        }
+        if result == GameResult::InProgress {
+            return Ok(tally);
+        }
+
+        tally.record(result);
+        let language = io.language();
+        io.show_message(&language.text(Message::Score {
+            x: &names.name(Player::X),
+            x_wins: tally.x_wins,
+            o: &names.name(Player::O),
+            o_wins: tally.o_wins,
+            draws: tally.draws,
+        }))?;
+
+        if let GameResult::Win(player) = result {
+            if tally.wins(player) >= target {
+                io.show_result(&language.text(Message::WinsMatch(&names.name(player))))?;
+                return Ok(tally);
+            }
+        }
+    }
+}
+
+/// Plays a game under `rules` through `io`, announcing the winner
+/// by their name in `names`, and returns the result, which is still in
+/// progress if the players quit
+fn play_game(io: &mut impl GameIO, names: &Names, rules: Rules) -> anyhow::Result<GameResult> {
+    let mut game = Game::with_rules(rules);
+
+    let language = io.language();
+    io.show_board(game.board())?;
+    if rules.wild {
+        io.show_message(&language.text(Message::ChooseMark))?;
+    }
 
     loop {
         loop {
-            match input_coords(state.next()) {
-                Ok(coords) => match state.play(coords) {
+            match io.read_command(game.board())? {
+                Command::Move(coords) => match game.play(coords) {
+                    Ok(_) => break,
+                    Err(error) => io.show_message(&language.text(Message::Error(error)))?,
+                },
+                Command::Place(coords, mark) => match game.play_mark(coords, mark) {
                     Ok(_) => break,
-                    Err(error) => println!("{}", error),
+                    Err(error) => io.show_message(&language.text(Message::Error(error)))?,
                 },
-                Err(error) => println!("{}", error),
+                Command::Hint => {
+                    // The AI only knows how to play its own mark
+                    let hint = if game.rules().own_marks() {
+                        ai::strong_move(game.board())
+                    } else {
+                        None
+                    };
+                    io.show_hint(hint)?;
+                    continue;
+                }
+                Command::Quit => {
+                    io.show_result(&language.text(Message::GameAbandoned))?;
+                    return Ok(GameResult::InProgress);
+                }
+                Command::Forfeit => {
+                    let winner = game.next().next_player(game.board().players());
+                    io.show_result(&language.text(Message::Forfeits {
+                        loser: &names.name(game.next()),
+                        winner: &names.name(winner),
+                    }))?;
+                    io.alert()?;
+                    return Ok(GameResult::Win(winner));
+                }
+                Command::Restart => {
+                    game = Game::with_rules(*game.rules());
+                    io.show_board(game.board())?;
+                    continue;
+                }
+                Command::Save(path) => {
+                    let language = io.language();
+                    let message = match fs::write(&path, game.board().to_save()) {
+                        Ok(()) => language.text(Message::Saved(&path.display().to_string())),
+                        Err(error) => language.text(Message::SaveFailed {
+                            path: &path.display().to_string(),
+                            error: &error.to_string(),
+                        }),
+                    };
+                    io.show_message(&message)?;
+                    continue;
+                }
+                Command::Load(path) => {
+                    let language = io.language();
+                    // A saved board keeps to the variant being played
+                    match load_board(&path).and_then(|board| Game::with_board(board, *game.rules()))
+                    {
+                        Ok(loaded) => {
+                            game = loaded;
+                            io.show_message(
+                                &language.text(Message::Loaded(&path.display().to_string())),
+                            )?;
+                            break;
+                        }
+                        Err(error) => {
+                            io.show_message(&language.text(Message::LoadFailed {
+                                path: &path.display().to_string(),
+                                error: &error.to_string(),
+                            }))?;
+                            continue;
+                        }
+                    }
+                }
             }
 
-            println!("Enter coordinates x, y");
+            io.show_coordinate_help()?;
         }
 
-        println!("\n{}\n", state);
+        io.show_board(game.board())?;
 
-        if let Some(player) = state.won() {
-            println!("{} wins!", player);
-            break;
+        match game.result() {
+            // There's no point playing on when nobody can complete a line
+            GameResult::InProgress if game.board().is_dead_draw() => {
+                io.show_message(&language.text(Message::DeadDraw))?;
+                io.show_result(&language.text(Message::Draw))?;
+                io.alert()?;
+                return Ok(GameResult::Draw);
+            }
+            GameResult::InProgress => {}
+            GameResult::Win(player) => {
+                io.show_result(&language.text(Message::Wins(&names.name(player))))?;
+                io.alert()?;
+                return Ok(GameResult::Win(player));
+            }
+            GameResult::Draw => {
+                io.show_result(&language.text(Message::Draw))?;
+                io.alert()?;
+                return Ok(GameResult::Draw);
+            }
         }
+    }
+}
+
+/// Plays the commands in `script`, one per line, as if they had been typed,
+/// then shows the final board and result. Only moves, `restart`, and `quit`
+/// make sense without a player at the keyboard. Fails on the first line that
+/// can't be played, naming its line number.
+fn play_script(
+    io: &mut impl GameIO,
+    script: &str,
+    indexing: Indexing,
+    names: &Names,
+    rules: Rules,
+) -> anyhow::Result<GameResult> {
+    let mut game = Game::with_rules(rules);
+
+    for (number, line) in (1..).zip(script.lines()) {
+        let command = match parse_command(line, indexing, game.board().size()) {
+            Ok(command) => command,
+            Err(error) if error.is::<BlankInput>() => continue,
+            Err(error) => return Err(anyhow::anyhow!("line {}: {}", number, error)),
+        };
 
-        if state.drawn() {
-            println!("Draw!");
-            break;
+        match command {
+            Command::Move(coords) => {
+                if let Err(error) = game.play(coords) {
+                    return Err(anyhow::anyhow!("line {}: {}", number, error));
+                }
+            }
+            Command::Place(coords, mark) => {
+                if let Err(error) = game.play_mark(coords, mark) {
+                    return Err(anyhow::anyhow!("line {}: {}", number, error));
+                }
+            }
+            Command::Restart => game = Game::with_rules(*game.rules()),
+            Command::Quit => break,
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "line {}: only moves, restart, and quit can be scripted",
+                    number
+                ))
+            }
+        }
+    }
+
+    io.show_board(game.board())?;
+    let result = game.result();
+    let language = io.language();
+    match result {
+        GameResult::InProgress => io.show_result(&language.text(Message::GameUnfinished))?,
+        GameResult::Win(player) => {
+            io.show_result(&language.text(Message::Wins(&names.name(player))))?
+        }
+        GameResult::Draw => io.show_result(&language.text(Message::Draw))?,
+    }
+    Ok(result)
+}
+
+/// Shows `board` through `io` as it was before each of its moves and after
+/// the last, announcing each move and waiting `delay` before it
+fn replay_game(io: &mut impl GameIO, game: &BoardState, delay: Duration) -> anyhow::Result<()> {
+    // Rewind to the start, then redo the moves one at a time
+    let mut board = game.clone();
+    while board.undo().is_ok() {}
+
+    io.show_board(&board)?;
+
+    for &coords in game.history() {
+        if !delay.is_zero() {
+            thread::sleep(delay);
         }
+
+        io.show_message(&format!(
+            "{} plays {}",
+            board.next(),
+            format_algebraic(coords)?
+        ))?;
+        board.redo()?;
+        io.show_board(&board)?;
     }
 
     Ok(())
 }
+
+/// Reads a board saved by the `save` command, or written in move notation
+fn load_board(path: &Path) -> anyhow::Result<BoardState> {
+    let text = fs::read_to_string(path)?;
+    // Saves start with a setting name, notation with a setting or move number
+    let first = text.split_whitespace().next().unwrap_or_default();
+    if !first.is_empty() && first.chars().all(|c| c.is_ascii_alphabetic() || c == '-') {
+        BoardState::from_save(&text)
+    } else {
+        BoardState::from_notation(&text)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tic_tac_toe::{game::Variant, game_io::ScriptedGameIO, state::TileState};
+
+    /// Runs a game on the scripted `input`, returning its result and output
+    fn run_script(input: &str) -> (GameResult, String) {
+        run_script_with(input, Indexing::ZeroBased)
+    }
+
+    fn run_script_with(input: &str, indexing: Indexing) -> (GameResult, String) {
+        let mut output = Vec::new();
+        let mut io = StdioGameIO::with_indexing(input.as_bytes(), &mut output, indexing);
+        let result = play_game(&mut io, &Names::default(), Rules::default()).unwrap();
+        (result, String::from_utf8(output).unwrap())
+    }
+
+    /// Solves the compact board, returning the output
+    fn solve_output(compact: &str) -> String {
+        let mut output = Vec::new();
+        run_solve(&BoardState::from_compact(compact).unwrap(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn solve() {
+        assert_eq!(
+            solve_output("........."),
+            "Draw\nOptimal moves: 0, 0; 1, 0; 2, 0; 0, 1; 1, 1; 2, 1; 0, 2; 1, 2; 2, 2\n"
+        );
+        assert_eq!(solve_output("XX.OO...."), "X wins\nOptimal moves: 2, 0\n");
+        assert_eq!(solve_output("XXXOO...."), "X wins\n");
+        assert_eq!(solve_output("XOXXOOOXX"), "Draw\n");
+    }
+
+    #[test]
+    fn analyze() {
+        let mut output = Vec::new();
+        run_analyze(&BoardState::new(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().count(), 9);
+        assert!(output
+            .lines()
+            .all(|line| line.starts_with("1. ") && line.ends_with(": draw")));
+
+        let mut output = Vec::new();
+        run_analyze(&BoardState::from_compact("XX.OO....").unwrap(), &mut output).unwrap();
+        insta::assert_snapshot!(String::from_utf8(output).unwrap(), @r###"
+        1. 2, 0: win
+        2. 2, 1: draw
+        3. 0, 2: loss
+        3. 1, 2: loss
+        3. 2, 2: loss
+        "###);
+    }
+
+    /// Simulates games from `seed`, returning the output
+    fn simulate_output(games: usize, x: Difficulty, o: Difficulty, seed: u64) -> String {
+        let mut output = Vec::new();
+        let mut rng = super::seeded_rng(Some(seed));
+        run_simulate(games, x, o, &mut rng, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn simulate() {
+        assert_eq!(
+            simulate_output(3, Difficulty::Hard, Difficulty::Hard, 0),
+            "X wins: 0\nO wins: 0\nDraws: 3\n"
+        );
+
+        let easy = simulate_output(50, Difficulty::Easy, Difficulty::Easy, 1);
+        assert_eq!(
+            easy,
+            simulate_output(50, Difficulty::Easy, Difficulty::Easy, 1)
+        );
+        assert!(easy.starts_with("X wins: "));
+        assert!(!easy.contains("X wins: 0\n"));
+
+        let lopsided = simulate_output(20, Difficulty::Easy, Difficulty::Hard, 2);
+        assert!(lopsided.starts_with("X wins: 0\n"));
+    }
+
+    #[test]
+    fn seeded_rng() {
+        let play = |seed| {
+            let mut rng = super::seeded_rng(seed);
+            let state = ai::play_ai_game(Difficulty::Easy, Difficulty::Medium, &mut rng);
+            state.history().to_vec()
+        };
+
+        for seed in 0..10 {
+            assert_eq!(play(Some(seed)), play(Some(seed)));
+        }
+        assert!((1..10).any(|seed| play(Some(seed)) != play(Some(0))));
+    }
+
+    #[test]
+    fn choose_first_player() {
+        let choose = |seed| {
+            let mut output = Vec::new();
+            let mut rng = super::seeded_rng(Some(seed));
+            let player = super::choose_first_player(
+                &mut rng,
+                &Names::default(),
+                Language::default(),
+                &mut output,
+            );
+            (player.unwrap(), String::from_utf8(output).unwrap())
+        };
+
+        for seed in 0..10 {
+            let (player, output) = choose(seed);
+            assert_eq!((player, output.clone()), choose(seed));
+            assert_eq!(output, format!("{} goes first\n", player));
+
+            let mut io = ScriptedGameIO::default();
+            let rules = Rules {
+                first_player: player,
+                ..Rules::default()
+            };
+            assert!(play_game(&mut io, &Names::default(), rules).is_err());
+            assert_eq!(io.boards[0].next(), player);
+        }
+        let starters = (0..10).map(|seed| choose(seed).0).collect::<Vec<_>>();
+        assert!(starters.contains(&Player::X));
+        assert!(starters.contains(&Player::O));
+
+        let names = Names {
+            x: Some("Alice".to_owned()),
+            o: Some("Bob".to_owned()),
+        };
+        let mut output = Vec::new();
+        let player = super::choose_first_player(
+            &mut super::seeded_rng(Some(0)),
+            &names,
+            Language::Spanish,
+            &mut output,
+        );
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!("{} empieza\n", names.name(player.unwrap()))
+        );
+    }
+
+    #[test]
+    fn names() {
+        let names = Names {
+            x: Some("Alice".to_owned()),
+            o: Some("Bob".to_owned()),
+        };
+        let mut output = Vec::new();
+        let input = "0,0\nfoo\n0,1\n1,0\n1,1\n2,0\n";
+        let mut io = StdioGameIO::new(input.as_bytes(), &mut output).with_names(names.clone());
+        let result = play_game(&mut io, &names, Rules::default());
+        assert_eq!(result.unwrap(), GameResult::Win(Player::X));
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("\n"));
+        assert!(output.contains("\nMove 1 — Alice (X) > "));
+        assert!(output.contains("\nMove 2 — Bob (O) > "));
+        assert!(output.ends_with("Alice wins!\n"));
+
+        let mut io = ScriptedGameIO::new(&[(1, 1), (0, 0), (2, 1), (0, 1), (2, 2), (0, 2)]);
+        assert_eq!(
+            play_game(&mut io, &names, Rules::default()).unwrap(),
+            GameResult::Win(Player::O)
+        );
+        assert_eq!(io.messages, vec!["Bob wins!"]);
+    }
+
+    #[test]
+    fn play_match() {
+        let x_wins = [(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)];
+        let o_wins = [(1, 1), (0, 0), (2, 1), (0, 1), (2, 2), (0, 2)];
+        let draw = [
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (1, 1),
+            (0, 1),
+            (0, 2),
+            (2, 1),
+            (2, 2),
+            (1, 2),
+        ];
+        let rounds = [&x_wins[..], &draw, &o_wins, &draw, &x_wins, &o_wins];
+        let mut io = ScriptedGameIO::new(&rounds.concat());
+        let tally =
+            super::play_match(&mut io, &Names::default(), 3, false, Rules::default()).unwrap();
+        assert_eq!(
+            tally,
+            Tally {
+                x_wins: 2,
+                o_wins: 1,
+                draws: 2,
+                ..Tally::default()
+            }
+        );
+        assert_eq!(tally.match_result(3), GameResult::Win(Player::X));
+        assert_eq!(
+            io.messages,
+            vec![
+                "X wins!",
+                "X wins: 1, O wins: 0, Draws: 0",
+                "Draw!",
+                "X wins: 1, O wins: 0, Draws: 1",
+                "O wins!",
+                "X wins: 1, O wins: 1, Draws: 1",
+                "Draw!",
+                "X wins: 1, O wins: 1, Draws: 2",
+                "X wins!",
+                "X wins: 2, O wins: 1, Draws: 2",
+                "X wins the match!",
+            ]
+        );
+        // Each round starts from a fresh board
+        assert_eq!(io.boards[x_wins.len() + 1], BoardState::new());
+
+        // A best of one ends with the first win, and a best of four needs two
+        let mut io = ScriptedGameIO::new(&rounds.concat());
+        assert_eq!(
+            super::play_match(&mut io, &Names::default(), 1, false, Rules::default()).unwrap(),
+            Tally {
+                x_wins: 1,
+                ..Tally::default()
+            }
+        );
+        let mut io = ScriptedGameIO::new(&rounds.concat());
+        assert_eq!(
+            super::play_match(&mut io, &Names::default(), 4, false, Rules::default())
+                .unwrap()
+                .x_wins,
+            2
+        );
+    }
+
+    #[test]
+    fn play_match_alternate() {
+        // The same moves win for whoever starts
+        let first_wins = [(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)];
+        let mut io = ScriptedGameIO::new(&[first_wins; 3].concat());
+        let tally =
+            super::play_match(&mut io, &Names::default(), 3, true, Rules::default()).unwrap();
+        assert_eq!(tally.x_wins, 2);
+        assert_eq!(tally.o_wins, 1);
+        assert_eq!(io.messages[2], "O wins!");
+        assert_eq!(io.messages.last().unwrap(), "X wins the match!");
+        let second_round = &io.boards[first_wins.len() + 1..];
+        assert_eq!(second_round[0].next(), Player::O);
+        assert_eq!(second_round[1][(0, 0)], TileState::O);
+        assert!(second_round.iter().all(BoardState::is_valid));
+    }
+
+    #[test]
+    fn play_match_quit() {
+        let mut output = Vec::new();
+        let input = "0,0\n0,1\n1,0\n1,1\n2,0\nquit\n";
+        let mut io = StdioGameIO::new(input.as_bytes(), &mut output);
+        let tally =
+            super::play_match(&mut io, &Names::default(), 3, false, Rules::default()).unwrap();
+        assert_eq!(tally.x_wins, 1);
+        assert_eq!(tally.match_result(3), GameResult::InProgress);
+        assert_eq!(tally.match_result(1), GameResult::Win(Player::X));
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("X wins: 1, O wins: 0, Draws: 0\n"));
+        assert!(output.ends_with("X > Game abandoned\n"));
+        assert!(!output.contains("the match"));
+    }
+
+    #[test]
+    fn exit_code() {
+        assert_eq!(super::exit_code(GameResult::Draw), 0);
+        assert_eq!(super::exit_code(GameResult::Win(Player::X)), 1);
+        assert_eq!(super::exit_code(GameResult::Win(Player::O)), 2);
+        assert_eq!(super::exit_code(GameResult::Win(Player::Z)), 3);
+        assert_eq!(super::exit_code(GameResult::InProgress), 4);
+        for &result in [
+            GameResult::Draw,
+            GameResult::Win(Player::X),
+            GameResult::Win(Player::O),
+            GameResult::Win(Player::Z),
+            GameResult::InProgress,
+        ]
+        .iter()
+        {
+            assert_ne!(super::exit_code(result), ERROR_EXIT_CODE);
+        }
+
+        let (result, _) = run_script("0,0\n0,1\n1,0\n1,1\n2,0\n");
+        assert_eq!(super::exit_code(result), 1);
+        let (result, _) = run_script("0,0\nquit\n");
+        assert_eq!(super::exit_code(result), 4);
+    }
+
+    #[test]
+    fn x_wins() {
+        let (result, output) = run_script("0,0\n0,1\n1,0\n1,1\n2,0\n");
+        assert_eq!(result, GameResult::Win(Player::X));
+        assert!(output.ends_with("X wins!\n"));
+        assert_eq!(output.matches(']').count(), 3);
+    }
+
+    #[test]
+    fn clear() {
+        let input = "0,0\n0,1\n1,0\n1,1\n2,0\n";
+        let mut output = Vec::new();
+        let mut io = StdioGameIO::new(input.as_bytes(), &mut output).with_clear(true);
+        let result = play_game(&mut io, &Names::default(), Rules::default()).unwrap();
+        assert_eq!(result, GameResult::Win(Player::X));
+        let output = String::from_utf8(output).unwrap();
+        // Once before the empty board and once after each of the five moves
+        assert_eq!(output.matches("\x1b[2J\x1b[H").count(), 6);
+        assert!(output.ends_with("X wins!\n"));
+
+        let (_, output) = run_script(input);
+        assert!(!output.contains("\x1b[2J\x1b[H"));
+    }
+
+    #[test]
+    fn bell() {
+        let run = |input: &str, bell| {
+            let mut output = Vec::new();
+            let mut io = StdioGameIO::new(input.as_bytes(), &mut output).with_bell(bell);
+            let result = play_game(&mut io, &Names::default(), Rules::default()).unwrap();
+            (result, String::from_utf8(output).unwrap())
+        };
+
+        let (result, output) = run("0,0\n0,1\n1,0\n1,1\n2,0\n", true);
+        assert_eq!(result, GameResult::Win(Player::X));
+        assert!(output.ends_with("X wins!\n\x07"));
+        assert_eq!(output.matches('\x07').count(), 1);
+
+        let (result, output) = run("1,1\n0,0\n2,2\n0,2\n0,1\n2,1\n1,0\n1,2\n2,0\n", true);
+        assert_eq!(result, GameResult::Draw);
+        assert!(output.ends_with("Draw!\n\x07"));
+
+        let (_, output) = run("0,0\nquit\n", true);
+        assert!(!output.contains('\x07'));
+        let (_, output) = run("0,0\n0,1\n1,0\n1,1\n2,0\n", false);
+        assert!(!output.contains('\x07'));
+    }
+
+    #[test]
+    fn quiet() {
+        let input = "0,0\nfoo\n0,1\nhint\n1,0\n1,1\n2,0\n";
+        let mut output = Vec::new();
+        let mut io = StdioGameIO::new(input.as_bytes(), &mut output).with_quiet(true);
+        let result = play_game(&mut io, &Names::default(), Rules::default()).unwrap();
+        assert_eq!(result, GameResult::Win(Player::X));
+        assert_eq!(String::from_utf8(output).unwrap(), "X wins!\n");
+
+        let (_, output) = run_script(input);
+        assert!(output.contains("-+-+-"));
+        assert!(output.ends_with("X wins!\n"));
+
+        let mut output = Vec::new();
+        let input = "0,0\n0,1\n1,0\n1,1\n2,0\nquit\n";
+        let mut io = StdioGameIO::new(input.as_bytes(), &mut output).with_quiet(true);
+        super::play_match(&mut io, &Names::default(), 1, false, Rules::default()).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "X wins!\nX wins the match!\n"
+        );
+
+        let mut output = Vec::new();
+        let mut io = StdioGameIO::new("0,0\nquit\n".as_bytes(), &mut output).with_quiet(true);
+        let result = play_game(&mut io, &Names::default(), Rules::default()).unwrap();
+        assert_eq!(result, GameResult::InProgress);
+        assert_eq!(String::from_utf8(output).unwrap(), "Game abandoned\n");
+    }
+
+    #[test]
+    fn move_numbers() {
+        let (_, output) = run_script("1,1\n1,1\n0,0\nrestart\n2,2\n");
+        let prompts = output
+            .lines()
+            .filter_map(|line| line.find("Move ").map(|start| &line[start..]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            prompts,
+            vec![
+                "Move 1 — X > ",
+                "Move 2 — O > (1, 1) has already been played",
+                "Move 2 — O > ",
+                "Move 3 — X > ",
+                "Move 1 — X > ",
+                "Move 2 — O > ",
+            ]
+        );
+    }
+
+    #[test]
+    fn one_based() {
+        let (result, output) =
+            run_script_with("1,1\n1,2\n2,1\n0,2\n2,2\nhint\n3,1\n", Indexing::OneBased);
+        assert_eq!(result, GameResult::Win(Player::X));
+        assert!(output.contains("coordinates start at 1"));
+        assert!(output.contains("Try 3, 1"));
+    }
+
+    #[test]
+    fn eof() {
+        let (result, output) = run_script("");
+        assert_eq!(result, GameResult::InProgress);
+        assert!(output.ends_with("X > \nGame abandoned\n"));
+
+        let (result, output) = run_script("1,1\n0,0\n2,x");
+        assert_eq!(result, GameResult::InProgress);
+        assert!(output.ends_with("Enter coordinates x, y\nMove 3 — X > \nGame abandoned\n"));
+
+        let mut io = ScriptedGameIO::with_commands(vec![Command::Move((1, 1)), Command::Quit]);
+        assert_eq!(
+            play_game(&mut io, &Names::default(), Rules::default()).unwrap(),
+            GameResult::InProgress
+        );
+        assert_eq!(io.messages, vec!["Game abandoned"]);
+    }
+
+    #[test]
+    fn quit() {
+        let (result, output) = run_script("1,1\nquit\n");
+        assert_eq!(result, GameResult::InProgress);
+        assert!(output.ends_with("O > Game abandoned\n"));
+    }
+
+    #[test]
+    fn restart() {
+        let mut io = ScriptedGameIO::with_commands(vec![
+            Command::Move((1, 1)),
+            Command::Move((0, 0)),
+            Command::Restart,
+        ]);
+        assert!(play_game(&mut io, &Names::default(), Rules::default()).is_err());
+        assert_eq!(io.boards.len(), 4);
+        assert_eq!(io.boards[3], BoardState::new());
+        assert_eq!(io.boards[3].history(), &[]);
+
+        let (result, output) = run_script("0,1\n2,2\nrestart\n0,0\n0,1\n1,0\n1,1\n2,0\n");
+        assert_eq!(result, GameResult::Win(Player::X));
+        assert!(output.contains(&format!("X > \n{}\n", BoardState::new())));
+    }
+
+    #[test]
+    fn draw() {
+        let (result, output) = run_script("0,0\n1,0\n2,0\n1,1\n0,1\n0,2\n2,1\n2,2\n1,2\n");
+        assert_eq!(result, GameResult::Draw);
+        assert!(output.ends_with("Draw!\n"));
+    }
+
+    #[test]
+    fn dead_draw() {
+        let moves = [
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (1, 1),
+            (0, 1),
+            (2, 1),
+            (1, 2),
+            (0, 2),
+        ];
+        let mut io = ScriptedGameIO::new(&[&moves[..], &[(2, 2)]].concat());
+        assert_eq!(
+            play_game(&mut io, &Names::default(), Rules::default()).unwrap(),
+            GameResult::Draw
+        );
+        assert_eq!(io.boards.len(), moves.len() + 1);
+        assert_eq!(io.boards.last().unwrap().remaining(), 1);
+        assert_eq!(io.messages, vec!["Nobody can complete a line", "Draw!"]);
+    }
+
+    #[test]
+    fn retries_invalid_moves() {
+        let (result, output) = run_script("0,0\n0,0\n3,3\nfoo\n0,1\n1,0\n1,1\n2,0\n");
+        assert_eq!(result, GameResult::Win(Player::X));
+        assert!(output.contains("(0, 0) has already been played"));
+        assert!(output.contains("(3, 3) is out of bounds"));
+        assert_eq!(output.matches("Enter coordinates x, y").count(), 3);
+    }
+
+    #[test]
+    fn scripted() {
+        let mut io = ScriptedGameIO::new(&[(1, 1), (0, 0), (1, 1), (2, 0), (0, 2)]);
+        assert!(play_game(&mut io, &Names::default(), Rules::default()).is_err());
+        assert_eq!(
+            io.messages,
+            vec!["(1, 1) has already been played", "Enter coordinates x, y"]
+        );
+        assert_eq!(io.boards.len(), 5);
+        assert_eq!(io.boards[0], BoardState::new());
+        assert_eq!(io.boards[4].to_compact(), "O.X.X.O..");
+
+        let mut io = ScriptedGameIO::new(&[(1, 1), (0, 0), (2, 0), (0, 1), (0, 2)]);
+        assert_eq!(
+            play_game(&mut io, &Names::default(), Rules::default()).unwrap(),
+            GameResult::Win(Player::X)
+        );
+        assert_eq!(io.messages, vec!["X wins!"]);
+        assert_eq!(io.boards.last().unwrap().won(), Some(Player::X));
+    }
+
+    #[test]
+    fn save() {
+        let path = env::temp_dir().join(format!("tic-tac-toe-save-{}.txt", std::process::id()));
+        let mut io = ScriptedGameIO::with_commands(vec![
+            Command::Move((1, 1)),
+            Command::Move((0, 2)),
+            Command::Save(path.clone()),
+            Command::Move((2, 0)),
+            Command::Save(path.clone()),
+        ]);
+        assert!(play_game(&mut io, &Names::default(), Rules::default()).is_err());
+        let saved = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let board = io.boards.last().unwrap();
+        assert_eq!(io.messages, vec![format!("Saved to {}", path.display()); 2]);
+        assert_eq!(BoardState::from_save(&saved).unwrap(), *board);
+        assert_eq!(
+            BoardState::from_save(&saved).unwrap().history(),
+            board.history()
+        );
+
+        let directory = env::temp_dir();
+        let mut io = ScriptedGameIO::with_commands(vec![Command::Save(directory.clone())]);
+        assert!(play_game(&mut io, &Names::default(), Rules::default()).is_err());
+        assert!(io.messages[0].starts_with(&format!("Could not save to {}: ", directory.display())));
+    }
+
+    #[test]
+    fn load() {
+        let path = env::temp_dir().join(format!("tic-tac-toe-load-{}.txt", std::process::id()));
+        let mut io = ScriptedGameIO::with_commands(vec![
+            Command::Move((1, 1)),
+            Command::Move((0, 0)),
+            Command::Move((2, 0)),
+            Command::Save(path.clone()),
+        ]);
+        assert!(play_game(&mut io, &Names::default(), Rules::default()).is_err());
+        let saved = io.boards.last().unwrap().clone();
+
+        // A fresh session picks up where the first left off, with O to move
+        let mut io = ScriptedGameIO::with_commands(vec![
+            Command::Move((2, 2)),
+            Command::Load(path.clone()),
+            Command::Move((0, 2)),
+        ]);
+        assert!(play_game(&mut io, &Names::default(), Rules::default()).is_err());
+        assert_eq!(io.messages, vec![format!("Loaded {}", path.display())]);
+        let loaded = &io.boards[2];
+        assert_eq!(loaded, &saved);
+        assert_eq!(loaded.history(), saved.history());
+        assert_eq!(loaded.next(), Player::O);
+        assert_eq!(io.boards[3][(0, 2)], TileState::O);
+
+        // A corrupt file leaves the game alone
+        fs::write(&path, "size 3\nmove 1 1\nmove 1 1\n").unwrap();
+        let mut io =
+            ScriptedGameIO::with_commands(vec![Command::Move((2, 2)), Command::Load(path.clone())]);
+        assert!(play_game(&mut io, &Names::default(), Rules::default()).is_err());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            io.messages,
+            vec![format!(
+                "Could not load {}: (1, 1) has already been played",
+                path.display()
+            )]
+        );
+        assert_eq!(io.boards.len(), 2);
+
+        // So does one too large to allocate
+        fs::write(&path, "size 5000000000\n").unwrap();
+        let mut io =
+            ScriptedGameIO::with_commands(vec![Command::Move((2, 2)), Command::Load(path.clone())]);
+        assert!(play_game(&mut io, &Names::default(), Rules::default()).is_err());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            io.messages,
+            vec![format!(
+                "Could not load {}: a 5000000000 by 5000000000 board is larger than the limit of 26",
+                path.display()
+            )]
+        );
+        assert_eq!(io.boards.len(), 2);
+
+        let mut io = ScriptedGameIO::with_commands(vec![Command::Load(path.clone())]);
+        assert!(play_game(&mut io, &Names::default(), Rules::default()).is_err());
+        assert!(io.messages[0].starts_with(&format!("Could not load {}: ", path.display())));
+    }
+
+    #[test]
+    fn load_keeps_rules() {
+        let path =
+            env::temp_dir().join(format!("tic-tac-toe-load-rules-{}.txt", std::process::id()));
+        fs::write(&path, "size 3\nmove 0 0\nmove 1 0 X\n").unwrap();
+
+        // In notakto, both players place X, so X completes the top row and loses
+        let mut io =
+            ScriptedGameIO::with_commands(vec![Command::Load(path.clone()), Command::Move((2, 0))]);
+        assert_eq!(
+            play_game(&mut io, &Names::default(), Variant::Notakto.rules()).unwrap(),
+            GameResult::Win(Player::O)
+        );
+        assert_eq!(io.boards.last().unwrap()[(2, 0)], TileState::X);
+
+        // Misère play can't take a third player
+        fs::write(&path, "size 4\nplayers 3\n").unwrap();
+        let mut io = ScriptedGameIO::with_commands(vec![Command::Load(path.clone())]);
+        assert!(play_game(&mut io, &Names::default(), Variant::Notakto.rules()).is_err());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            io.messages,
+            vec![format!(
+                "Could not load {}: misère play needs two players, not 3",
+                path.display()
+            )]
+        );
+    }
+
+    #[test]
+    fn load_finished() {
+        let path = env::temp_dir().join(format!("tic-tac-toe-won-{}.txt", std::process::id()));
+        let won = BoardState::from_moves(&[(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)]).unwrap();
+        fs::write(&path, won.to_save()).unwrap();
+        let mut io = ScriptedGameIO::with_commands(vec![Command::Load(path.clone())]);
+        let result = play_game(&mut io, &Names::default(), Rules::default());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(result.unwrap(), GameResult::Win(Player::X));
+        assert_eq!(io.messages.last().unwrap(), "X wins!");
+    }
+
+    #[test]
+    fn notation() {
+        let mut io = ScriptedGameIO::new(&[(1, 1), (0, 0), (2, 2), (0, 2), (0, 1), (2, 0), (2, 1)]);
+        assert_eq!(
+            play_game(&mut io, &Names::default(), Rules::default()).unwrap(),
+            GameResult::Win(Player::X)
+        );
+        assert_eq!(
+            io.boards.last().unwrap().to_notation().unwrap(),
+            "size=3 win-length=3 players=2 1. X:b2 O:a1 2. X:c3 O:a3 3. X:a2 O:c1 4. X:c2"
+        );
+        let board = io.boards.last().unwrap();
+        assert_eq!(
+            &BoardState::from_notation(&board.to_notation().unwrap()).unwrap(),
+            board
+        );
+    }
+
+    #[test]
+    fn replay_game() {
+        let mut game = BoardState::with_win_length(4, 3);
+        let moves = [(1, 1), (0, 0), (2, 2), (0, 2), (0, 1)];
+        for &coords in moves.iter() {
+            game.play(coords).unwrap();
+        }
+
+        let mut io = ScriptedGameIO::default();
+        super::replay_game(&mut io, &game, Duration::from_millis(0)).unwrap();
+        assert_eq!(io.boards.len(), moves.len() + 1);
+        assert_eq!(io.boards[0], BoardState::with_win_length(4, 3));
+        assert_eq!(
+            io.boards[2],
+            *BoardState::with_win_length(4, 3)
+                .play((1, 1))
+                .unwrap()
+                .play((0, 0))
+                .unwrap()
+        );
+        assert_eq!(io.boards.last().unwrap(), &game);
+        assert_eq!(io.messages[0], "X plays b2");
+        assert_eq!(io.messages[4], "X plays a2");
+    }
+
+    #[test]
+    fn replay_file() {
+        let path = env::temp_dir().join(format!("tic-tac-toe-replay-{}.txt", std::process::id()));
+        fs::write(&path, "1. X:b2 O:a1 2. X:c3\n").unwrap();
+        let board = load_board(&path);
+        fs::remove_file(&path).unwrap();
+        let board = board.unwrap();
+
+        let mut output = Vec::new();
+        let mut io = StdioGameIO::new(io::empty(), &mut output);
+        super::replay_game(&mut io, &board, Duration::from_millis(0)).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let renders = output.lines().filter(|line| line.starts_with("0 ")).count();
+        assert_eq!(renders, board.history().len() + 1);
+        assert!(output.contains("O plays a1\n"));
+    }
+
+    #[test]
+    fn load_board_notation() {
+        let path = env::temp_dir().join(format!("tic-tac-toe-notation-{}.txt", std::process::id()));
+        let mut board = BoardState::with_win_length(5, 4);
+        board.play((4, 4)).unwrap();
+        fs::write(&path, board.to_notation().unwrap()).unwrap();
+        let loaded = load_board(&path);
+        fs::write(&path, board.to_save()).unwrap();
+        let saved = load_board(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), board);
+        assert_eq!(saved.unwrap(), board);
+    }
+
+    #[test]
+    fn play_script() {
+        let mut io = ScriptedGameIO::default();
+        let script = "1, 1\n\n0, 0\nrestart\n0,0\n1,0\n1,1\n2,0\n  2, 2  \n";
+        assert_eq!(
+            super::play_script(
+                &mut io,
+                script,
+                Indexing::ZeroBased,
+                &Names::default(),
+                Rules::default()
+            )
+            .unwrap(),
+            GameResult::Win(Player::X)
+        );
+        assert_eq!(io.boards.len(), 1);
+        assert_eq!(
+            io.boards[0],
+            BoardState::from_moves(&[(0, 0), (1, 0), (1, 1), (2, 0), (2, 2)]).unwrap()
+        );
+        assert_eq!(io.messages, vec!["X wins!"]);
+
+        let mut io = ScriptedGameIO::default();
+        let result = super::play_script(
+            &mut io,
+            "2, 2\nquit\n1, 1\n",
+            Indexing::OneBased,
+            &Names::default(),
+            Rules::default(),
+        );
+        assert_eq!(result.unwrap(), GameResult::InProgress);
+        assert_eq!(io.boards[0][(1, 1)], TileState::X);
+        assert_eq!(io.boards[0].remaining(), 8);
+        assert_eq!(io.messages, vec!["Game unfinished"]);
+    }
+
+    #[test]
+    fn play_script_errors() {
+        for &(script, message) in [
+            (
+                "1, 1\n0, 0\n\n1, 1\n",
+                "line 4: (1, 1) has already been played",
+            ),
+            ("1, 1\n3, 0\n", "line 2: (3, 0) is out of bounds"),
+            ("1, 1\nmiddle\n", "line 2: "),
+            (
+                "0,0\n0,1\n1,0\n1,1\n2,0\n2,2\n",
+                "line 6: the game is already over",
+            ),
+            (
+                "hint\n",
+                "line 1: only moves, restart, and quit can be scripted",
+            ),
+        ]
+        .iter()
+        {
+            let mut io = ScriptedGameIO::default();
+            let error = super::play_script(
+                &mut io,
+                script,
+                Indexing::ZeroBased,
+                &Names::default(),
+                Rules::default(),
+            )
+            .unwrap_err();
+            assert!(error.to_string().starts_with(message), "{}", error);
+            assert!(io.boards.is_empty());
+        }
+    }
+
+    #[test]
+    fn forfeit() {
+        let mut io = ScriptedGameIO::with_commands(vec![Command::Move((1, 1)), Command::Forfeit]);
+        assert_eq!(
+            play_game(&mut io, &Names::default(), Rules::default()).unwrap(),
+            GameResult::Win(Player::X)
+        );
+        assert_eq!(io.messages, vec!["O forfeits! X wins!"]);
+    }
+
+    #[test]
+    fn hint() {
+        let (result, output) = run_script("0,0\n1,1\n2,0\nhint\n2,2\n1,0\n");
+        assert_eq!(result, GameResult::Win(Player::X));
+        assert!(output.contains("Try 1, 0\nMove 4 — O > "));
+        assert!(!output.contains("Enter coordinates x, y"));
+
+        let mut io = ScriptedGameIO::with_commands(vec![
+            Command::Move((0, 0)),
+            Command::Move((1, 1)),
+            Command::Move((2, 0)),
+            Command::Hint,
+            Command::Hint,
+        ]);
+        assert!(play_game(&mut io, &Names::default(), Rules::default()).is_err());
+        assert_eq!(io.messages, vec!["Try 1, 0", "Try 1, 0"]);
+        assert_eq!(io.boards.len(), 4);
+    }
+
+    #[test]
+    fn gomoku() {
+        // X lines up five from (3, 9) towards the top right, while O plays
+        // along the bottom row
+        let script = (0..5)
+            .flat_map(|i| vec![format!("{}, {}\n", 3 + i, 9 - i), format!("{}, 14\n", i)])
+            .take(9)
+            .collect::<String>();
+        let mut io = ScriptedGameIO::default();
+        assert_eq!(
+            super::play_script(
+                &mut io,
+                &script,
+                Indexing::ZeroBased,
+                &Names::default(),
+                Variant::Gomoku.rules(),
+            )
+            .unwrap(),
+            GameResult::Win(Player::X)
+        );
+        assert_eq!(io.boards[0].size(), 15);
+        assert_eq!(io.boards[0].history().len(), 9);
+
+        let mut io = ScriptedGameIO::with_commands(vec![
+            Command::Move((7, 7)),
+            Command::Move((6, 7)),
+            Command::Move((8, 7)),
+            Command::Move((0, 0)),
+            Command::Move((9, 7)),
+            Command::Move((0, 1)),
+            Command::Move((10, 7)),
+            Command::Hint,
+        ]);
+        assert!(play_game(&mut io, &Names::default(), Variant::Gomoku.rules()).is_err());
+        assert_eq!(io.messages, vec!["Try 11, 7"]);
+    }
+
+    #[test]
+    fn wild() {
+        // X completes a line of O's
+        let script = "0,0 O\n2,2 X\n1,0 O\n1,1 X\n2,0 O\n";
+        let mut io = ScriptedGameIO::default();
+        assert_eq!(
+            super::play_script(
+                &mut io,
+                script,
+                Indexing::ZeroBased,
+                &Names::default(),
+                Variant::Wild.rules(),
+            )
+            .unwrap(),
+            GameResult::Win(Player::X)
+        );
+        assert_eq!(io.boards[0].won(), Some(Player::O));
+        assert_eq!(io.messages, vec!["X wins!"]);
+
+        let error = super::play_script(
+            &mut ScriptedGameIO::default(),
+            script,
+            Indexing::ZeroBased,
+            &Names::default(),
+            Rules::default(),
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "line 1: O can't be placed on this turn");
+
+        let mut io = ScriptedGameIO::with_commands(vec![
+            Command::Place((1, 1), TileState::O),
+            Command::Hint,
+            Command::Move((0, 0)),
+        ]);
+        assert!(play_game(&mut io, &Names::default(), Variant::Wild.rules()).is_err());
+        assert_eq!(
+            io.messages,
+            vec![
+                "Follow a move with X or O to place that mark",
+                "No hint available"
+            ]
+        );
+        assert_eq!(io.boards[2][(1, 1)], TileState::O);
+        assert_eq!(io.boards[2][(0, 0)], TileState::O);
+    }
+
+    #[test]
+    fn notakto() {
+        // X completes the top row on the third move, and so loses
+        let mut io = ScriptedGameIO::default();
+        assert_eq!(
+            super::play_script(
+                &mut io,
+                "0,0\n1,0\n2,0\n",
+                Indexing::ZeroBased,
+                &Names::default(),
+                Variant::Notakto.rules(),
+            )
+            .unwrap(),
+            GameResult::Win(Player::O)
+        );
+        assert_eq!(io.boards[0][(1, 0)], TileState::X);
+        assert_eq!(io.messages, vec!["O wins!"]);
+    }
+
+    #[test]
+    fn language() {
+        let script = "0,0\n1,0\n1,1\n2,0\n2,2\n";
+        for &(language, result) in [
+            (Language::English, "X wins!\n"),
+            (Language::Spanish, "¡X gana!\n"),
+        ]
+        .iter()
+        {
+            let mut output = Vec::new();
+            let mut io = StdioGameIO::new(io::empty(), &mut output).with_language(language);
+            super::play_script(
+                &mut io,
+                script,
+                Indexing::ZeroBased,
+                &Names::default(),
+                Rules::default(),
+            )
+            .unwrap();
+            drop(io);
+            assert!(String::from_utf8(output).unwrap().ends_with(result));
+        }
+
+        let mut output = Vec::new();
+        let mut io = StdioGameIO::new("1, 1\n1, 1\n".as_bytes(), &mut output)
+            .with_language(Language::Spanish);
+        play_game(&mut io, &Names::default(), Rules::default()).unwrap();
+        drop(io);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Jugada 2 — O > (1, 1) ya está ocupada\n"));
+        assert!(output.ends_with("Partida abandonada\n"));
+    }
+
+    /// Plays a network game over the loopback interface, with X hosting and
+    /// O joining, and returns the result and messages on each side
+    #[cfg(feature = "net")]
+    fn run_netplay(
+        x: Vec<Command>,
+        o: Vec<Command>,
+    ) -> [(anyhow::Result<GameResult>, Vec<String>); 2] {
+        use tic_tac_toe::net::{self, NetGameIO};
+
+        let host = net::host("127.0.0.1:0").unwrap();
+        let address = host.local_addr().unwrap();
+        let hosting = thread::spawn(move || {
+            let mut io = NetGameIO::new(ScriptedGameIO::with_commands(x), host.accept().unwrap());
+            let result = play_game(&mut io, &Names::default(), Rules::default());
+            (result, io.into_inner().messages)
+        });
+
+        let mut io = NetGameIO::new(
+            ScriptedGameIO::with_commands(o),
+            net::join(address).unwrap(),
+        );
+        let result = play_game(&mut io, &Names::default(), Rules::default());
+        // Hanging up lets the host finish if this side gave up early
+        let joined = (result, io.into_inner().messages);
+        [hosting.join().unwrap(), joined]
+    }
+
+    #[test]
+    #[cfg(feature = "net")]
+    fn netplay() {
+        let [(x_result, x_messages), (o_result, o_messages)] = run_netplay(
+            vec![
+                Command::Move((0, 0)),
+                Command::Restart,
+                Command::Move((0, 0)),
+                Command::Move((1, 1)),
+                Command::Move((2, 2)),
+            ],
+            vec![Command::Hint, Command::Move((1, 0)), Command::Move((2, 0))],
+        );
+        assert_eq!(x_result.unwrap(), GameResult::Win(Player::X));
+        assert_eq!(o_result.unwrap(), GameResult::Win(Player::X));
+        assert!(x_messages.contains(&"(0, 0) has already been played".to_owned()));
+        assert!(x_messages.contains(&"That can't be done in a network game".to_owned()));
+        assert_eq!(x_messages.last().unwrap(), "X wins!");
+        assert_eq!(
+            o_messages
+                .iter()
+                .filter(|message| *message == "Waiting for the other player...")
+                .count(),
+            3
+        );
+        assert_eq!(o_messages.last().unwrap(), "X wins!");
+    }
+
+    #[test]
+    #[cfg(feature = "net")]
+    fn netplay_disconnect() {
+        // O's script runs out before their second move, ending their side
+        let [(x_result, x_messages), (o_result, _)] = run_netplay(
+            vec![Command::Move((0, 0)), Command::Move((1, 1))],
+            vec![Command::Move((1, 0))],
+        );
+        assert!(o_result.is_err());
+        assert_eq!(x_result.unwrap(), GameResult::Win(Player::X));
+        assert_eq!(
+            x_messages[x_messages.len() - 2..],
+            ["The other player has disconnected", "O forfeits! X wins!"]
+        );
+    }
+}
@@ -0,0 +1,318 @@
+//! The text of everything the game says to players, in each language it
+//! speaks. To add a string, add a [`Message`] and its text in every language.
+
+use core::str::FromStr;
+
+use anyhow::{self, anyhow as anyhow_error};
+
+use crate::state::{GameError, TileState};
+
+/// A language the game can speak
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    English,
+    Spanish,
+}
+
+impl Language {
+    /// The language of a locale such as the `LANG` environment variable's
+    /// `es_ES.UTF-8`, if the game speaks it
+    pub fn from_locale(locale: &str) -> Option<Self> {
+        let code = locale.split(['_', '.', '-']).next()?;
+        code.parse().ok()
+    }
+
+    /// The text of `message` in this language
+    pub fn text(self, message: Message) -> String {
+        match self {
+            Self::English => english(message),
+            Self::Spanish => spanish(message),
+        }
+    }
+}
+
+impl FromStr for Language {
+    type Err = anyhow::Error;
+
+    /// Parses a two-letter language code, ignoring case
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "en" => Ok(Self::English),
+            "es" => Ok(Self::Spanish),
+            _ => Err(anyhow_error!("unknown language {:?}; expected en or es", s)),
+        }
+    }
+}
+
+/// Something to tell the players. Names and labels are filled in as given.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Message<'a> {
+    /// Asks `label` for move number `number`
+    Prompt {
+        number: usize,
+        label: &'a str,
+    },
+    EnterCoordinates,
+    /// Says who won the toss for the first move
+    GoesFirst(&'a str),
+    /// Gives the seconds a player has for their move
+    SecondsToMove(u64),
+    /// Counts down the seconds left as a move's deadline nears
+    SecondsLeft(u64),
+    TimeUp,
+    /// Explains how to choose a mark in a wild game
+    ChooseMark,
+    /// Rejects a move
+    Error(GameError),
+    Hint {
+        x: usize,
+        y: usize,
+    },
+    NoHint,
+    GameAbandoned,
+    /// Ends a scripted game whose moves ran out before it was decided
+    GameUnfinished,
+    Wins(&'a str),
+    Draw,
+    /// Explains a draw declared before the board is full
+    DeadDraw,
+    Forfeits {
+        loser: &'a str,
+        winner: &'a str,
+    },
+    Score {
+        x: &'a str,
+        x_wins: usize,
+        o: &'a str,
+        o_wins: usize,
+        draws: usize,
+    },
+    WinsMatch(&'a str),
+    /// Tells a network game's host where to send their opponent
+    Hosting(&'a str),
+    /// Tells a game server where players can join
+    Serving(&'a str),
+    WaitingForOpponent,
+    OpponentDisconnected,
+    /// Refuses a command that only one side of a network game would follow
+    NotOverNetwork,
+    Saved(&'a str),
+    SaveFailed {
+        path: &'a str,
+        error: &'a str,
+    },
+    Loaded(&'a str),
+    LoadFailed {
+        path: &'a str,
+        error: &'a str,
+    },
+    /// Heads the full-screen board with who is to move
+    ToMove(&'a str),
+    /// Heads the full-screen board once the game is decided
+    GameOver,
+    /// Stands in for a board too big for the terminal
+    EnlargeTerminal,
+    /// Lists the keys of the full-screen interface
+    KeyHelp,
+    /// Points out the tile the cursor has jumped to for a hint
+    TryHere,
+}
+
+fn english(message: Message) -> String {
+    match message {
+        Message::Prompt { number, label } => format!("Move {} \u{2014} {} > ", number, label),
+        Message::EnterCoordinates => "Enter coordinates x, y".to_owned(),
+        Message::GoesFirst(name) => format!("{} goes first", name),
+        Message::SecondsToMove(1) => "1 second to move".to_owned(),
+        Message::SecondsToMove(seconds) => format!("{} seconds to move", seconds),
+        Message::SecondsLeft(1) => "1 second left".to_owned(),
+        Message::SecondsLeft(seconds) => format!("{} seconds left", seconds),
+        Message::TimeUp => "Time's up!".to_owned(),
+        Message::ChooseMark => "Follow a move with X or O to place that mark".to_owned(),
+        Message::Error(error) => error.to_string(),
+        Message::Hint { x, y } => format!("Try {}, {}", x, y),
+        Message::NoHint => "No hint available".to_owned(),
+        Message::GameAbandoned => "Game abandoned".to_owned(),
+        Message::GameUnfinished => "Game unfinished".to_owned(),
+        Message::Wins(name) => format!("{} wins!", name),
+        Message::Draw => "Draw!".to_owned(),
+        Message::DeadDraw => "Nobody can complete a line".to_owned(),
+        Message::Forfeits { loser, winner } => format!("{} forfeits! {} wins!", loser, winner),
+        Message::Score {
+            x,
+            x_wins,
+            o,
+            o_wins,
+            draws,
+        } => format!(
+            "{} wins: {}, {} wins: {}, Draws: {}",
+            x, x_wins, o, o_wins, draws
+        ),
+        Message::WinsMatch(name) => format!("{} wins the match!", name),
+        Message::Hosting(address) => format!("Waiting for an opponent to join {}", address),
+        Message::Serving(address) => format!("Pairing up players who join {}", address),
+        Message::WaitingForOpponent => "Waiting for the other player...".to_owned(),
+        Message::OpponentDisconnected => "The other player has disconnected".to_owned(),
+        Message::NotOverNetwork => "That can't be done in a network game".to_owned(),
+        Message::Saved(path) => format!("Saved to {}", path),
+        Message::SaveFailed { path, error } => format!("Could not save to {}: {}", path, error),
+        Message::Loaded(path) => format!("Loaded {}", path),
+        Message::LoadFailed { path, error } => format!("Could not load {}: {}", path, error),
+        Message::ToMove(label) => format!("{} to move", label),
+        Message::GameOver => "Game over".to_owned(),
+        Message::EnlargeTerminal => "Enlarge the terminal to see the board".to_owned(),
+        Message::KeyHelp => "Arrows move, Enter places, H hints, R restarts, Q quits".to_owned(),
+        Message::TryHere => "Try here".to_owned(),
+    }
+}
+
+fn spanish(message: Message) -> String {
+    match message {
+        Message::Prompt { number, label } => format!("Jugada {} \u{2014} {} > ", number, label),
+        Message::EnterCoordinates => "Introduce las coordenadas x, y".to_owned(),
+        Message::GoesFirst(name) => format!("{} empieza", name),
+        Message::SecondsToMove(1) => "1 segundo para jugar".to_owned(),
+        Message::SecondsToMove(seconds) => format!("{} segundos para jugar", seconds),
+        Message::SecondsLeft(1) => "Queda 1 segundo".to_owned(),
+        Message::SecondsLeft(seconds) => format!("Quedan {} segundos", seconds),
+        Message::TimeUp => "¡Se acabó el tiempo!".to_owned(),
+        Message::ChooseMark => "Añade X u O tras una jugada para colocar esa marca".to_owned(),
+        Message::Error(error) => match error {
+            GameError::OutOfBounds { x, y } => format!("({}, {}) está fuera del tablero", x, y),
+            GameError::AlreadyPlayed { x, y } => format!("({}, {}) ya está ocupada", x, y),
+            GameError::NothingToUndo => "no hay jugadas que deshacer".to_owned(),
+            GameError::NothingToRedo => "no hay jugadas que rehacer".to_owned(),
+            GameError::GameOver => "la partida ya ha terminado".to_owned(),
+            GameError::WrongMark {
+                mark: TileState::Empty,
+            } => "no se puede colocar una casilla vacía".to_owned(),
+            GameError::WrongMark { mark } => format!("no se puede colocar {} en este turno", mark),
+            GameError::WrongBoard { x, y } => {
+                format!("la jugada debe hacerse en el tablero ({}, {})", x, y)
+            }
+            GameError::BoardFinished { x, y } => {
+                format!("el tablero ({}, {}) ya ha terminado", x, y)
+            }
+            GameError::NoSuchBoard { board, count } => {
+                format!("no existe el tablero {}; hay {}", board, count)
+            }
+            GameError::BoardDead { board } => format!("el tablero {} ya está muerto", board),
+        },
+        Message::Hint { x, y } => format!("Prueba {}, {}", x, y),
+        Message::NoHint => "No hay ninguna pista".to_owned(),
+        Message::GameAbandoned => "Partida abandonada".to_owned(),
+        Message::GameUnfinished => "Partida sin terminar".to_owned(),
+        Message::Wins(name) => format!("¡{} gana!", name),
+        Message::Draw => "¡Empate!".to_owned(),
+        Message::DeadDraw => "Nadie puede completar una línea".to_owned(),
+        Message::Forfeits { loser, winner } => format!("¡{} se rinde! ¡{} gana!", loser, winner),
+        Message::Score {
+            x,
+            x_wins,
+            o,
+            o_wins,
+            draws,
+        } => format!(
+            "Victorias de {}: {}, victorias de {}: {}, empates: {}",
+            x, x_wins, o, o_wins, draws
+        ),
+        Message::WinsMatch(name) => format!("¡{} gana el encuentro!", name),
+        Message::Hosting(address) => format!("Esperando a que un rival se una a {}", address),
+        Message::Serving(address) => {
+            format!("Emparejando a los jugadores que se unan a {}", address)
+        }
+        Message::WaitingForOpponent => "Esperando al otro jugador...".to_owned(),
+        Message::OpponentDisconnected => "El otro jugador se ha desconectado".to_owned(),
+        Message::NotOverNetwork => "Eso no se puede hacer en una partida en red".to_owned(),
+        Message::Saved(path) => format!("Guardada en {}", path),
+        Message::SaveFailed { path, error } => {
+            format!("No se pudo guardar en {}: {}", path, error)
+        }
+        Message::Loaded(path) => format!("Cargada {}", path),
+        Message::LoadFailed { path, error } => format!("No se pudo cargar {}: {}", path, error),
+        Message::ToMove(label) => format!("Le toca a {}", label),
+        Message::GameOver => "Fin de la partida".to_owned(),
+        Message::EnlargeTerminal => "Agranda la terminal para ver el tablero".to_owned(),
+        Message::KeyHelp => {
+            "Flechas: mover, Intro: colocar, H: pista, R: reiniciar, Q: salir".to_owned()
+        }
+        Message::TryHere => "Prueba aquí".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn from_str() {
+        assert_eq!("en".parse::<Language>().unwrap(), Language::English);
+        assert_eq!("ES".parse::<Language>().unwrap(), Language::Spanish);
+        assert!("fr".parse::<Language>().is_err());
+        assert!("english".parse::<Language>().is_err());
+    }
+
+    #[test]
+    fn from_locale() {
+        assert_eq!(
+            Language::from_locale("es_ES.UTF-8"),
+            Some(Language::Spanish)
+        );
+        assert_eq!(Language::from_locale("en_GB"), Some(Language::English));
+        assert_eq!(Language::from_locale("es"), Some(Language::Spanish));
+        assert_eq!(Language::from_locale("C.UTF-8"), None);
+        assert_eq!(Language::from_locale("fr_FR.UTF-8"), None);
+        assert_eq!(Language::from_locale(""), None);
+    }
+
+    #[test]
+    fn text() {
+        assert_eq!(
+            Language::English.text(Message::Wins("Alice")),
+            "Alice wins!"
+        );
+        assert_eq!(
+            Language::Spanish.text(Message::Wins("Alice")),
+            "¡Alice gana!"
+        );
+        assert_eq!(Language::default().text(Message::Draw), "Draw!");
+
+        let error = GameError::OutOfBounds { x: 3, y: 0 };
+        assert_eq!(
+            Language::English.text(Message::Error(error)),
+            error.to_string()
+        );
+        assert_eq!(
+            Language::Spanish.text(Message::Error(error)),
+            "(3, 0) está fuera del tablero"
+        );
+        assert_eq!(
+            Language::Spanish.text(Message::Error(GameError::BoardDead { board: 1 })),
+            "el tablero 1 ya está muerto"
+        );
+        assert_eq!(
+            Language::Spanish.text(Message::Prompt {
+                number: 2,
+                label: "O"
+            }),
+            "Jugada 2 \u{2014} O > "
+        );
+        assert_eq!(
+            Language::English.text(Message::SecondsToMove(1)),
+            "1 second to move"
+        );
+        assert_eq!(
+            Language::Spanish.text(Message::SecondsToMove(30)),
+            "30 segundos para jugar"
+        );
+        assert_eq!(
+            Language::English.text(Message::SecondsLeft(5)),
+            "5 seconds left"
+        );
+        assert_eq!(
+            Language::Spanish.text(Message::SecondsLeft(1)),
+            "Queda 1 segundo"
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! Checks shared by the tests of boards that answer the same questions as
+//! [`BoardState`]
+
+use crate::{
+    array_board::ArrayBoard,
+    bitboard::Bitboard,
+    state::{BoardState, GameError, GameResult, Player, TileState},
+};
+
+/// A two-player board that should always agree with a [`BoardState`]
+pub(crate) trait Mirror {
+    fn size(&self) -> usize;
+    fn win_length(&self) -> usize;
+    fn next(&self) -> Player;
+    fn remaining(&self) -> usize;
+    fn available_moves(&self) -> Vec<(usize, usize)>;
+    fn row(&self, row: usize) -> Vec<TileState>;
+    fn col(&self, col: usize) -> Vec<TileState>;
+    fn status(&self) -> GameResult;
+    fn won(&self) -> Option<Player>;
+    fn drawn(&self) -> bool;
+    fn play(&mut self, coords: (usize, usize)) -> Result<(), GameError>;
+}
+
+impl Mirror for Bitboard {
+    fn size(&self) -> usize {
+        self.size()
+    }
+
+    fn win_length(&self) -> usize {
+        self.win_length()
+    }
+
+    fn next(&self) -> Player {
+        self.next()
+    }
+
+    fn remaining(&self) -> usize {
+        self.remaining()
+    }
+
+    fn available_moves(&self) -> Vec<(usize, usize)> {
+        self.available_moves().collect()
+    }
+
+    fn row(&self, row: usize) -> Vec<TileState> {
+        self.iter_row(row).collect()
+    }
+
+    fn col(&self, col: usize) -> Vec<TileState> {
+        self.iter_col(col).collect()
+    }
+
+    fn status(&self) -> GameResult {
+        self.status()
+    }
+
+    fn won(&self) -> Option<Player> {
+        self.won()
+    }
+
+    fn drawn(&self) -> bool {
+        self.drawn()
+    }
+
+    fn play(&mut self, coords: (usize, usize)) -> Result<(), GameError> {
+        self.play(coords).map(|_| ())
+    }
+}
+
+impl<const N: usize> Mirror for ArrayBoard<N> {
+    fn size(&self) -> usize {
+        self.size()
+    }
+
+    fn win_length(&self) -> usize {
+        N
+    }
+
+    fn next(&self) -> Player {
+        self.next()
+    }
+
+    fn remaining(&self) -> usize {
+        self.remaining()
+    }
+
+    fn available_moves(&self) -> Vec<(usize, usize)> {
+        self.available_moves().collect()
+    }
+
+    fn row(&self, row: usize) -> Vec<TileState> {
+        self.iter_row(row).collect()
+    }
+
+    fn col(&self, col: usize) -> Vec<TileState> {
+        self.iter_col(col).collect()
+    }
+
+    fn status(&self) -> GameResult {
+        self.status()
+    }
+
+    fn won(&self) -> Option<Player> {
+        self.won()
+    }
+
+    fn drawn(&self) -> bool {
+        self.drawn()
+    }
+
+    fn play(&mut self, coords: (usize, usize)) -> Result<(), GameError> {
+        self.play(coords).map(|_| ())
+    }
+}
+
+/// Checks that `mirror` answers every question as `board` does
+pub(crate) fn assert_same(mirror: &impl Mirror, board: &BoardState) {
+    assert_eq!(mirror.size(), board.size());
+    assert_eq!(mirror.win_length(), board.win_length());
+    assert_eq!(mirror.next(), board.next());
+    assert_eq!(mirror.remaining(), board.remaining());
+    assert_eq!(
+        mirror.available_moves(),
+        board.available_moves().collect::<Vec<_>>()
+    );
+    for i in 0..board.size() {
+        assert_eq!(mirror.row(i), board.iter_row(i).collect::<Vec<_>>());
+        assert_eq!(mirror.col(i), board.iter_col(i).collect::<Vec<_>>());
+    }
+    assert_eq!(mirror.status(), board.status());
+    assert_eq!(mirror.won(), board.won());
+    assert_eq!(mirror.drawn(), board.drawn());
+}
+
+/// Plays a random game on both `mirror` and `board`, which must start out the
+/// same, choosing each move by index into the moves still available, and
+/// checks that they agree after every move. Returns both boards once the
+/// game ends or the choices run out.
+pub(crate) fn play_alongside<M: Mirror>(
+    mut mirror: M,
+    mut board: BoardState,
+    choices: &[usize],
+) -> (M, BoardState) {
+    assert_same(&mirror, &board);
+
+    for &choice in choices {
+        let moves = board.available_moves().collect::<Vec<_>>();
+        if moves.is_empty() || board.won().is_some() {
+            break;
+        }
+        let coords = moves[choice % moves.len()];
+        board.play(coords).unwrap();
+        mirror.play(coords).unwrap();
+        assert_same(&mirror, &board);
+    }
+
+    (mirror, board)
+}
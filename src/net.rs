@@ -0,0 +1,584 @@
+//! Two-player games over TCP, with one player at each end of a connection.
+//!
+//! Each side sends its moves as a line of zero-based coordinates `x,y` and
+//! checks the other side's moves against its own copy of the board. The host
+//! plays X, and first tells the player who joins that they play O, as the
+//! line `O`.
+//!
+//! A host can instead [`serve`](Host::serve) games between the players who
+//! join it, pairing them up as they arrive, refereeing each pair's moves,
+//! and keeping the games apart in a [`GameServer`].
+
+use core::fmt::{self, Display, Formatter};
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{Arc, Mutex, MutexGuard},
+    thread,
+};
+
+use anyhow::{self, anyhow as anyhow_error};
+
+use crate::{
+    game_io::GameIO,
+    input::{parse_coords, Command, Indexing},
+    messages::{Language, Message},
+    state::{BoardState, GameError, GameResult, Player},
+};
+
+/// A bound socket waiting for an opponent to join
+#[derive(Debug)]
+pub struct Host {
+    listener: TcpListener,
+}
+
+impl Host {
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Waits for an opponent to join
+    pub fn accept(&self) -> io::Result<Connection> {
+        let (mut stream, _) = self.listener.accept()?;
+        tell_player(&mut stream, Player::O)?;
+        Connection::new(stream, Player::X)
+    }
+
+    /// Pairs up the players who join, in the order they join, and referees
+    /// each pair's game on a thread of its own, keeping it in `server` while
+    /// it lasts. The first of each pair plays X. Runs until accepting a
+    /// connection fails.
+    pub fn serve(&self, server: &Arc<GameServer>) -> io::Result<Infallible> {
+        let mut waiting = None;
+        loop {
+            let (mut stream, _) = self.listener.accept()?;
+            let player = if waiting.is_none() {
+                Player::X
+            } else {
+                Player::O
+            };
+            // A player who leaves before the game starts is simply dropped
+            let joined = tell_player(&mut stream, player)
+                .and_then(|()| Connection::new(stream, player.opponent()));
+            let joined = match joined {
+                Ok(joined) => joined,
+                Err(_) => continue,
+            };
+
+            match waiting.take() {
+                None => waiting = Some(joined),
+                Some(x) => {
+                    let server = Arc::clone(server);
+                    thread::spawn(move || {
+                        let id = server.create_game();
+                        // However the game ends, hanging up tells both players
+                        let _ = referee(&server, id, [x, joined]);
+                        server.remove_game(id);
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Tells the player at the other end of `stream` which player they are
+fn tell_player(stream: &mut TcpStream, player: Player) -> io::Result<()> {
+    writeln!(stream, "{}", player)?;
+    stream.flush()
+}
+
+/// Passes each move in the game `id` from the connection of the player who
+/// made it to the other, after checking it in `server`. `players` are the
+/// connections of X and then O. Stops once the game ends or a player leaves
+/// or breaks the rules.
+fn referee(server: &GameServer, id: GameId, mut players: [Connection; 2]) -> anyhow::Result<()> {
+    loop {
+        let board = server.state(id)?;
+        if board.status() != GameResult::InProgress {
+            return Ok(());
+        }
+
+        let mover = if board.next() == Player::X { 0 } else { 1 };
+        let coords = match players[mover].receive_move(board.size())? {
+            Some(coords) => coords,
+            None => return Ok(()),
+        };
+        server.play(id, coords)?;
+        players[1 - mover].send_move(coords)?;
+    }
+}
+
+/// Binds `addr` to host a game, in which the host plays X
+pub fn host(addr: impl ToSocketAddrs) -> io::Result<Host> {
+    Ok(Host {
+        listener: TcpListener::bind(addr)?,
+    })
+}
+
+/// Joins the game hosted or served at `addr`, playing whichever player it
+/// names: O for a host, or X or O for a server
+pub fn join(addr: impl ToSocketAddrs) -> io::Result<Connection> {
+    let mut connection = Connection::new(TcpStream::connect(addr)?, Player::O)?;
+    let mut line = String::new();
+    connection.reader.read_line(&mut line)?;
+    connection.player = line.trim().parse().map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("expected the player to play, got {:?}", line.trim()),
+        )
+    })?;
+    Ok(connection)
+}
+
+/// One end of a game's connection
+#[derive(Debug)]
+pub struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    player: Player,
+}
+
+impl Connection {
+    fn new(stream: TcpStream, player: Player) -> io::Result<Self> {
+        Ok(Connection {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+            player,
+        })
+    }
+
+    /// The player at this end
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    pub fn send_move(&mut self, (x, y): (usize, usize)) -> io::Result<()> {
+        writeln!(self.writer, "{},{}", x, y)?;
+        self.writer.flush()
+    }
+
+    /// Waits for the other end's next move on a `size` by `size` board, or
+    /// returns `None` if it has disconnected
+    pub fn receive_move(&mut self, size: usize) -> anyhow::Result<Option<(usize, usize)>> {
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => Ok(None),
+            Ok(_) => parse_coords(line.trim(), Indexing::ZeroBased, size)
+                .map(Some)
+                .map_err(|error| {
+                    anyhow_error!("the other player sent {:?}: {}", line.trim(), error)
+                }),
+            // A broken connection means the same as a closed one
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+/// Plays the local player's turns through `inner` and reads the other
+/// player's from `connection`. If the other player disconnects, they forfeit.
+///
+/// Restarting, loading, and choosing marks would leave the two boards out of
+/// step, so they are refused.
+pub struct NetGameIO<I> {
+    inner: I,
+    connection: Connection,
+    disconnected: bool,
+}
+
+impl<I: GameIO> NetGameIO<I> {
+    pub fn new(inner: I, connection: Connection) -> Self {
+        NetGameIO {
+            inner,
+            connection,
+            disconnected: false,
+        }
+    }
+
+    /// Takes back the local interface, hanging up the connection
+    pub fn into_inner(self) -> I {
+        self.inner
+    }
+
+    fn read_remote(&mut self, board: &BoardState) -> anyhow::Result<Command> {
+        let received = if self.disconnected {
+            None
+        } else {
+            self.inner
+                .show_message(&self.language().text(Message::WaitingForOpponent))?;
+            self.connection.receive_move(board.size())?
+        };
+
+        match received {
+            Some(coords) => {
+                board.clone().play(coords).map_err(|error| {
+                    anyhow_error!("the other player broke the rules: {}", error)
+                })?;
+                Ok(Command::Move(coords))
+            }
+            None => {
+                self.disconnected = true;
+                self.inner
+                    .show_message(&self.language().text(Message::OpponentDisconnected))?;
+                Ok(Command::Forfeit)
+            }
+        }
+    }
+}
+
+impl<I: GameIO> GameIO for NetGameIO<I> {
+    fn read_command(&mut self, board: &BoardState) -> anyhow::Result<Command> {
+        if board.next() != self.connection.player() {
+            return self.read_remote(board);
+        }
+
+        loop {
+            match self.inner.read_command(board)? {
+                Command::Move(coords) => {
+                    // Only legal moves are sent, so the other side never has
+                    // to reject one. If sending fails, the other player has
+                    // gone, which their next turn will discover.
+                    if board.clone().play(coords).is_ok()
+                        && self.connection.send_move(coords).is_err()
+                    {
+                        self.disconnected = true;
+                    }
+                    return Ok(Command::Move(coords));
+                }
+                Command::Restart | Command::Load(_) | Command::Place(..) => self
+                    .inner
+                    .show_message(&self.language().text(Message::NotOverNetwork))?,
+                command => return Ok(command),
+            }
+        }
+    }
+
+    fn show_board(&mut self, board: &BoardState) -> anyhow::Result<()> {
+        self.inner.show_board(board)
+    }
+
+    fn show_message(&mut self, message: &str) -> anyhow::Result<()> {
+        self.inner.show_message(message)
+    }
+
+    fn show_result(&mut self, message: &str) -> anyhow::Result<()> {
+        self.inner.show_result(message)
+    }
+
+    fn show_hint(&mut self, hint: Option<(usize, usize)>) -> anyhow::Result<()> {
+        self.inner.show_hint(hint)
+    }
+
+    fn show_coordinate_help(&mut self) -> anyhow::Result<()> {
+        self.inner.show_coordinate_help()
+    }
+
+    fn alert(&mut self) -> anyhow::Result<()> {
+        self.inner.alert()
+    }
+
+    fn language(&self) -> Language {
+        self.inner.language()
+    }
+}
+
+/// The name a [`GameServer`] gives one of its games
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GameId(u64);
+
+impl Display for GameId {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        write!(fmt, "{}", self.0)
+    }
+}
+
+/// Holds any number of independent games at once, so that several pairs of
+/// players can share one server. It can be shared between threads, such as
+/// one per connection, behind an `Arc`.
+#[derive(Debug, Default)]
+pub struct GameServer {
+    games: Mutex<Games>,
+}
+
+#[derive(Debug, Default)]
+struct Games {
+    boards: HashMap<GameId, BoardState>,
+    next_id: u64,
+}
+
+impl GameServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a standard game, returning its ID
+    pub fn create_game(&self) -> GameId {
+        let mut games = self.lock();
+        let id = GameId(games.next_id);
+        games.next_id += 1;
+        games.boards.insert(id, BoardState::new());
+        id
+    }
+
+    /// Plays `coords` for the next player in the game `id`, returning the
+    /// board after the move. Fails once the game is over.
+    pub fn play(&self, id: GameId, coords: (usize, usize)) -> anyhow::Result<BoardState> {
+        let mut games = self.lock();
+        let board = games
+            .boards
+            .get_mut(&id)
+            .ok_or_else(|| anyhow_error!("there is no game {}", id))?;
+        if board.status() != GameResult::InProgress {
+            return Err(GameError::GameOver.into());
+        }
+        board.play(coords)?;
+        Ok(board.clone())
+    }
+
+    /// A copy of the board of the game `id`
+    pub fn state(&self, id: GameId) -> anyhow::Result<BoardState> {
+        self.lock()
+            .boards
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| anyhow_error!("there is no game {}", id))
+    }
+
+    /// Forgets the game `id`, returning its board if there was such a game
+    pub fn remove_game(&self, id: GameId) -> Option<BoardState> {
+        self.lock().boards.remove(&id)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Games> {
+        // A thread that panicked while holding the lock can only have left
+        // a game between two complete moves, so the games are still sound
+        self.games
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game_io::ScriptedGameIO;
+
+    /// Both ends of a connection over the loopback interface
+    fn loopback() -> (Connection, Connection) {
+        let host = super::host("127.0.0.1:0").unwrap();
+        let address = host.local_addr().unwrap();
+        // Joining waits to hear which player to play, so it can't go first
+        let joining = thread::spawn(move || super::join(address).unwrap());
+        (host.accept().unwrap(), joining.join().unwrap())
+    }
+
+    #[test]
+    fn connection() {
+        let (mut host, mut joined) = loopback();
+        assert_eq!(host.player(), Player::X);
+        assert_eq!(joined.player(), Player::O);
+
+        host.send_move((2, 1)).unwrap();
+        assert_eq!(joined.receive_move(3).unwrap(), Some((2, 1)));
+        joined.send_move((0, 0)).unwrap();
+        assert_eq!(host.receive_move(3).unwrap(), Some((0, 0)));
+
+        writeln!(host.writer, "left").unwrap();
+        assert!(joined.receive_move(3).is_err());
+
+        // Someone who doesn't say which player to play isn't a host
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let impostor = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            writeln!(stream, "hello").unwrap();
+        });
+        assert_eq!(
+            super::join(address).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        impostor.join().unwrap();
+
+        drop(host);
+        assert_eq!(joined.receive_move(3).unwrap(), None);
+    }
+
+    #[test]
+    fn local_turn() {
+        let (host, mut joined) = loopback();
+        let board = BoardState::from_moves(&[(1, 1), (0, 0)]).unwrap();
+        let mut io = NetGameIO::new(
+            ScriptedGameIO::with_commands(vec![
+                Command::Restart,
+                Command::Move((1, 1)),
+                Command::Hint,
+                Command::Move((2, 2)),
+            ]),
+            host,
+        );
+
+        // The taken tile is handed back to be rejected, but not sent
+        assert_eq!(io.read_command(&board).unwrap(), Command::Move((1, 1)));
+        assert_eq!(
+            io.inner.messages,
+            vec![Language::default().text(Message::NotOverNetwork)]
+        );
+        assert_eq!(io.read_command(&board).unwrap(), Command::Hint);
+        assert_eq!(io.read_command(&board).unwrap(), Command::Move((2, 2)));
+        assert_eq!(joined.receive_move(3).unwrap(), Some((2, 2)));
+    }
+
+    #[test]
+    fn remote_turn() {
+        let (mut host, joined) = loopback();
+        let mut io = NetGameIO::new(ScriptedGameIO::default(), joined);
+        let board = BoardState::from_moves(&[(1, 1), (0, 0)]).unwrap();
+
+        // X's turns come from the connection, not the script
+        host.send_move((0, 2)).unwrap();
+        assert_eq!(
+            io.read_command(&BoardState::new()).unwrap(),
+            Command::Move((0, 2))
+        );
+        assert_eq!(
+            io.inner.messages,
+            vec![Language::default().text(Message::WaitingForOpponent)]
+        );
+
+        host.send_move((1, 1)).unwrap();
+        assert_eq!(
+            io.read_command(&board).unwrap_err().to_string(),
+            "the other player broke the rules: (1, 1) has already been played"
+        );
+
+        drop(host);
+        assert_eq!(
+            io.read_command(&BoardState::new()).unwrap(),
+            Command::Forfeit
+        );
+        assert_eq!(
+            io.inner.messages.last().unwrap(),
+            &Language::default().text(Message::OpponentDisconnected)
+        );
+    }
+
+    mod game_server {
+        use super::*;
+
+        #[test]
+        fn independent_games() {
+            let server = GameServer::new();
+            let first = server.create_game();
+            let second = server.create_game();
+            assert_ne!(first, second);
+
+            let board = server.play(first, (1, 1)).unwrap();
+            assert_eq!(board, BoardState::from_moves(&[(1, 1)]).unwrap());
+            assert_eq!(server.state(first).unwrap(), board);
+            assert_eq!(server.state(second).unwrap(), BoardState::new());
+
+            // The same tile is still free in the other game
+            server.play(second, (1, 1)).unwrap();
+            server.play(second, (0, 0)).unwrap();
+            assert_eq!(server.state(first).unwrap(), board);
+            assert_eq!(
+                server.play(first, (1, 1)).unwrap_err().to_string(),
+                "(1, 1) has already been played"
+            );
+            assert_eq!(server.state(second).unwrap().move_count(), 2);
+
+            assert_eq!(server.remove_game(second).unwrap().move_count(), 2);
+            assert!(server.state(second).is_err());
+            assert_eq!(server.remove_game(second), None);
+            assert_eq!(server.state(first).unwrap(), board);
+        }
+
+        #[test]
+        fn game_over() {
+            let server = GameServer::new();
+            let id = server.create_game();
+            for &coords in [(0, 0), (1, 0), (1, 1), (2, 0), (2, 2)].iter() {
+                server.play(id, coords).unwrap();
+            }
+            let error = server
+                .play(id, (0, 2))
+                .unwrap_err()
+                .downcast::<GameError>()
+                .unwrap();
+            assert_eq!(error, GameError::GameOver);
+            assert_eq!(server.state(id).unwrap().move_count(), 5);
+        }
+
+        #[test]
+        fn serve() {
+            let host = super::host("127.0.0.1:0").unwrap();
+            let address = host.local_addr().unwrap();
+            let server = Arc::new(GameServer::new());
+            thread::spawn(move || host.serve(&server));
+
+            let mut players = (0..4)
+                .map(|_| super::join(address).unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                players.iter().map(Connection::player).collect::<Vec<_>>(),
+                [Player::X, Player::O, Player::X, Player::O]
+            );
+
+            // Two pairs play at once, each seeing only its own moves
+            players[0].send_move((1, 1)).unwrap();
+            players[2].send_move((0, 0)).unwrap();
+            assert_eq!(players[1].receive_move(3).unwrap(), Some((1, 1)));
+            assert_eq!(players[3].receive_move(3).unwrap(), Some((0, 0)));
+            players[1].send_move((0, 0)).unwrap();
+            assert_eq!(players[0].receive_move(3).unwrap(), Some((0, 0)));
+
+            // Breaking the rules ends only that pair's game
+            players[3].send_move((0, 0)).unwrap();
+            assert_eq!(players[2].receive_move(3).unwrap(), None);
+            players[0].send_move((2, 2)).unwrap();
+            assert_eq!(players[1].receive_move(3).unwrap(), Some((2, 2)));
+
+            // So does leaving, which the server notices on the leaver's turn
+            drop(players.remove(0));
+            players[0].send_move((1, 0)).unwrap();
+            assert_eq!(players[0].receive_move(3).unwrap(), None);
+        }
+
+        #[test]
+        fn unknown_game() {
+            let server = GameServer::new();
+            server.create_game();
+            let missing = GameId(7);
+            assert_eq!(
+                server.state(missing).unwrap_err().to_string(),
+                "there is no game 7"
+            );
+            assert!(server.play(missing, (0, 0)).is_err());
+        }
+
+        #[test]
+        fn concurrent_games() {
+            let server = Arc::new(GameServer::new());
+            let moves = [(0, 0), (1, 0), (1, 1), (2, 0), (2, 2)];
+            let threads = (0..4)
+                .map(|_| {
+                    let server = Arc::clone(&server);
+                    thread::spawn(move || {
+                        let id = server.create_game();
+                        for &coords in moves.iter() {
+                            server.play(id, coords).unwrap();
+                        }
+                        id
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            for thread in threads {
+                let board = server.state(thread.join().unwrap()).unwrap();
+                assert_eq!(board.history(), &moves);
+                assert_eq!(board.won(), Some(Player::X));
+            }
+        }
+    }
+}
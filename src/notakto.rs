@@ -0,0 +1,158 @@
+//! Notakto played across several boards at once
+
+use crate::state::{BoardState, GameError, GameResult, Player, TileState};
+
+/// A game of notakto on several standard boards, on all of which both
+/// players place X. Completing a line kills a board, and whoever kills the
+/// last board still alive loses.
+///
+/// A single board plays the same as [`Variant::Notakto`].
+///
+/// [`Variant::Notakto`]: crate::game::Variant::Notakto
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notakto {
+    boards: Vec<BoardState>,
+    next: Player,
+}
+
+impl Notakto {
+    /// Creates a game on `boards` empty boards
+    ///
+    /// # Panics
+    ///
+    /// Panics if `boards` is 0.
+    pub fn new(boards: usize) -> Self {
+        assert!(boards > 0, "notakto needs at least one board");
+        Notakto {
+            boards: vec![BoardState::new(); boards],
+            next: Player::X,
+        }
+    }
+
+    /// Places an X for the next player at `coords` on the board numbered
+    /// `board`, failing if the game is over, there is no such board, the
+    /// board is dead, or the tile is taken
+    pub fn play(&mut self, board: usize, coords: (usize, usize)) -> Result<&mut Self, GameError> {
+        if self.status() != GameResult::InProgress {
+            return Err(GameError::GameOver);
+        }
+
+        let count = self.boards.len();
+        let target = self
+            .boards
+            .get_mut(board)
+            .ok_or(GameError::NoSuchBoard { board, count })?;
+        if target.won().is_some() {
+            return Err(GameError::BoardDead { board });
+        }
+
+        target.play_mark(coords, TileState::X)?;
+        self.next = self.next.opponent();
+        Ok(self)
+    }
+
+    pub fn boards(&self) -> &[BoardState] {
+        &self.boards
+    }
+
+    /// Whether the board numbered `board` holds a line, so that no more moves
+    /// can be played on it
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no such board.
+    pub fn is_dead(&self, board: usize) -> bool {
+        self.boards[board].won().is_some()
+    }
+
+    pub fn next(&self) -> Player {
+        self.next
+    }
+
+    /// Iterates over every legal move as a pair of the board's number and
+    /// the tile on it
+    pub fn available_moves(&self) -> impl Iterator<Item = (usize, (usize, usize))> + '_ {
+        self.boards
+            .iter()
+            .enumerate()
+            .filter(|(_, board)| board.won().is_none())
+            .flat_map(|(index, board)| board.available_moves().map(move |coords| (index, coords)))
+    }
+
+    /// Won by the player to move once every board is dead, since their
+    /// opponent killed the last one
+    pub fn status(&self) -> GameResult {
+        if self.boards.iter().all(|board| board.won().is_some()) {
+            GameResult::Win(self.next)
+        } else {
+            GameResult::InProgress
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn single_board() {
+        let mut game = Notakto::new(1);
+        for &coords in [(0, 0), (1, 1), (2, 1)].iter() {
+            game.play(0, coords).unwrap();
+            assert_eq!(game.status(), GameResult::InProgress);
+        }
+        assert_eq!(game.boards()[0][(2, 1)], TileState::X);
+        assert_eq!(game.next(), Player::O);
+
+        // O completes the diagonal, losing to X
+        game.play(0, (2, 2)).unwrap();
+        assert!(game.is_dead(0));
+        assert_eq!(game.status(), GameResult::Win(Player::X));
+        assert_eq!(game.available_moves().count(), 0);
+        assert_eq!(game.play(0, (2, 0)).unwrap_err(), GameError::GameOver);
+    }
+
+    #[test]
+    fn boards() {
+        let mut game = Notakto::new(2);
+        assert_eq!(game.available_moves().count(), 18);
+
+        // X kills board 0, which only moves the game on to board 1
+        for &coords in [(0, 0), (1, 0), (2, 0)].iter() {
+            game.play(0, coords).unwrap();
+        }
+        assert!(game.is_dead(0));
+        assert!(!game.is_dead(1));
+        assert_eq!(game.status(), GameResult::InProgress);
+        assert!(game.available_moves().all(|(board, _)| board == 1));
+        assert_eq!(
+            game.play(0, (1, 1)).unwrap_err(),
+            GameError::BoardDead { board: 0 }
+        );
+        assert_eq!(
+            game.play(2, (1, 1)).unwrap_err(),
+            GameError::NoSuchBoard { board: 2, count: 2 }
+        );
+        assert_eq!(
+            GameError::NoSuchBoard { board: 2, count: 2 }.to_string(),
+            "there is no board 2; there are 2"
+        );
+        assert_eq!(
+            game.play(1, (3, 0)).unwrap_err(),
+            GameError::OutOfBounds { x: 3, y: 0 }
+        );
+        assert_eq!(game.next(), Player::O);
+
+        // O kills the last board and loses
+        for &coords in [(0, 0), (1, 1), (2, 2)].iter() {
+            game.play(1, coords).unwrap();
+        }
+        assert_eq!(game.status(), GameResult::Win(Player::X));
+    }
+
+    #[test]
+    #[should_panic]
+    fn no_boards() {
+        Notakto::new(0);
+    }
+}
@@ -0,0 +1,83 @@
+//! Algebraic notation for tiles, as move lists and the prompt write them
+
+use anyhow::{self, anyhow as anyhow_error};
+
+/// Parses a cell in algebraic notation on a `size` by `size` board: a column
+/// letter counting from `a`, then a row number counting from 1, as in `b2`.
+/// Only the first [`ALGEBRAIC_COLUMNS`] columns have letters.
+pub fn parse_algebraic(input: &str, size: usize) -> anyhow::Result<(usize, usize)> {
+    let input = input.trim();
+    let mut chars = input.chars();
+    let letter = chars
+        .next()
+        .filter(char::is_ascii_alphabetic)
+        .ok_or_else(|| anyhow_error!("expected a column letter, got {:?}", input))?;
+    let row = chars
+        .as_str()
+        .parse::<usize>()
+        .map_err(|_| anyhow_error!("expected a row number after the column, got {:?}", input))?;
+
+    let x = usize::from(letter.to_ascii_lowercase() as u8 - b'a');
+    if x >= size {
+        return Err(anyhow_error!("column {} is off the board", letter));
+    }
+
+    if row == 0 || row > size {
+        return Err(anyhow_error!("row {} is off the board", row));
+    }
+
+    Ok((x, row - 1))
+}
+
+/// How many columns algebraic notation has letters for, `a` to `z`
+pub const ALGEBRAIC_COLUMNS: usize = 26;
+
+/// Writes `coords` in the algebraic notation read by [`parse_algebraic`],
+/// failing if the column has no letter
+pub fn format_algebraic((x, y): (usize, usize)) -> anyhow::Result<String> {
+    if x >= ALGEBRAIC_COLUMNS {
+        return Err(anyhow_error!(
+            "column {} has no letter in algebraic notation",
+            x
+        ));
+    }
+
+    Ok(format!("{}{}", char::from(b'a' + x as u8), y + 1))
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn parse_algebraic() {
+        assert_eq!(super::parse_algebraic("a1", 3).unwrap(), (0, 0));
+        assert_eq!(super::parse_algebraic("b2", 3).unwrap(), (1, 1));
+        assert_eq!(super::parse_algebraic("c3", 3).unwrap(), (2, 2));
+        assert_eq!(super::parse_algebraic("C1\n", 3).unwrap(), (2, 0));
+        assert_eq!(super::parse_algebraic("d12", 12).unwrap(), (3, 11));
+        assert_eq!(super::parse_algebraic("z30", 30).unwrap(), (25, 29));
+        assert!(super::parse_algebraic("d1", 3).is_err());
+        assert!(super::parse_algebraic("a4", 3).is_err());
+        assert!(super::parse_algebraic("a0", 3).is_err());
+        assert!(super::parse_algebraic("a", 3).is_err());
+        assert!(super::parse_algebraic("1a", 3).is_err());
+        assert!(super::parse_algebraic("éa", 3).is_err());
+        assert_eq!(
+            super::parse_algebraic("z1", 3).unwrap_err().to_string(),
+            "column z is off the board"
+        );
+    }
+
+    #[test]
+    fn format_algebraic() {
+        assert_eq!(super::format_algebraic((0, 0)).unwrap(), "a1");
+        assert_eq!(super::format_algebraic((2, 1)).unwrap(), "c2");
+        assert_eq!(super::format_algebraic((3, 11)).unwrap(), "d12");
+        assert_eq!(super::format_algebraic((25, 30)).unwrap(), "z31");
+        assert!(super::format_algebraic((26, 0)).is_err());
+        assert!(super::format_algebraic((200, 0)).is_err());
+        for &coords in [(0, 2), (1, 1), (2, 0)].iter() {
+            let formatted = super::format_algebraic(coords).unwrap();
+            assert_eq!(super::parse_algebraic(&formatted, 3).unwrap(), coords);
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! A JSON protocol through which front ends such as web pages and bots can
+//! drive a board over any transport, one message at a time.
+//!
+//! Each request is an object whose `type` is one of:
+//!
+//! - `move`, with the zero-based coordinates `x` and `y` of a tile to play
+//!   for the next player, answered with the board after the move, and
+//!   refused once the game is over
+//! - `board`, answered with the board
+//! - `result`, answered with the state of the game
+//!
+//! A `board` reply holds the board's `rows`, written as by
+//! [`BoardState::to_compact`], and the `next` player. A `result` reply holds
+//! a `status` of `in_progress`, `draw`, or `win`, and the `winner`, if any.
+
+use serde::{Deserialize, Serialize};
+
+use anyhow::{self, anyhow as anyhow_error};
+
+use crate::state::{BoardState, GameError, GameResult, Player};
+
+/// A message to the engine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    Move { x: usize, y: usize },
+    Board,
+    Result,
+}
+
+/// The engine's answer to a [`Request`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Reply {
+    Board {
+        rows: Vec<String>,
+        next: Player,
+    },
+    Result {
+        status: Status,
+        winner: Option<Player>,
+    },
+}
+
+/// How far along a game is, as a [`Reply::Result`] reports it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    InProgress,
+    Draw,
+    Win,
+}
+
+impl Reply {
+    /// The board reply describing `state`
+    pub fn board(state: &BoardState) -> Self {
+        let compact = state.to_compact();
+        let size = state.size();
+        Reply::Board {
+            rows: (0..size)
+                .map(|y| compact[y * size..(y + 1) * size].to_owned())
+                .collect(),
+            next: state.next(),
+        }
+    }
+
+    /// The result reply describing `state`
+    pub fn result(state: &BoardState) -> Self {
+        let (status, winner) = match state.status() {
+            GameResult::InProgress => (Status::InProgress, None),
+            GameResult::Draw => (Status::Draw, None),
+            GameResult::Win(player) => (Status::Win, Some(player)),
+        };
+        Reply::Result { status, winner }
+    }
+}
+
+/// Answers `request` about `state`, playing it first if it is a move
+pub fn apply_request(state: &mut BoardState, request: Request) -> anyhow::Result<Reply> {
+    match request {
+        Request::Move { .. } if state.status() != GameResult::InProgress => {
+            Err(GameError::GameOver.into())
+        }
+        Request::Move { x, y } => {
+            state.play((x, y))?;
+            Ok(Reply::board(state))
+        }
+        Request::Board => Ok(Reply::board(state)),
+        Request::Result => Ok(Reply::result(state)),
+    }
+}
+
+/// Parses the JSON request `msg`, applies it to `state`, and returns the
+/// JSON reply. Malformed requests and illegal moves leave `state` as it was.
+pub fn apply_message(state: &mut BoardState, msg: &str) -> anyhow::Result<String> {
+    let request = serde_json::from_str(msg)
+        .map_err(|error| anyhow_error!("invalid request {:?}: {}", msg, error))?;
+    let reply = apply_request(state, request)?;
+    Ok(serde_json::to_string(&reply)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn apply_message() {
+        let mut state = BoardState::new();
+        let reply = super::apply_message(&mut state, r#"{"type":"move","x":1,"y":1}"#).unwrap();
+        assert_eq!(
+            reply,
+            r#"{"type":"board","rows":["...",".X.","..."],"next":"O"}"#
+        );
+        assert_eq!(state, BoardState::from_moves(&[(1, 1)]).unwrap());
+
+        let reply: Reply = serde_json::from_str(
+            &super::apply_message(&mut state, r#"{"type":"move","x":2,"y":0}"#).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            reply,
+            Reply::Board {
+                rows: vec!["..O".to_owned(), ".X.".to_owned(), "...".to_owned()],
+                next: Player::X,
+            }
+        );
+        assert_eq!(
+            super::apply_message(&mut state, r#"{"type": "board"}"#).unwrap(),
+            r#"{"type":"board","rows":["..O",".X.","..."],"next":"X"}"#
+        );
+    }
+
+    #[test]
+    fn result() {
+        let mut state = BoardState::new();
+        assert_eq!(
+            super::apply_message(&mut state, r#"{"type":"result"}"#).unwrap(),
+            r#"{"type":"result","status":"in_progress","winner":null}"#
+        );
+
+        for &(x, y) in [(0, 0), (1, 0), (1, 1), (2, 0), (2, 2)].iter() {
+            let request = serde_json::to_string(&Request::Move { x, y }).unwrap();
+            super::apply_message(&mut state, &request).unwrap();
+        }
+        assert_eq!(
+            apply_request(&mut state, Request::Result).unwrap(),
+            Reply::Result {
+                status: Status::Win,
+                winner: Some(Player::X),
+            }
+        );
+        assert_eq!(
+            super::apply_message(&mut state, r#"{"type":"result"}"#).unwrap(),
+            r#"{"type":"result","status":"win","winner":"X"}"#
+        );
+
+        let before = state.clone();
+        let error = apply_request(&mut state, Request::Move { x: 0, y: 2 })
+            .unwrap_err()
+            .downcast::<GameError>()
+            .unwrap();
+        assert_eq!(error, GameError::GameOver);
+        assert_eq!(state, before);
+    }
+
+    #[test]
+    fn errors() {
+        let mut state = BoardState::from_moves(&[(1, 1)]).unwrap();
+        let before = state.clone();
+
+        let error = super::apply_message(&mut state, r#"{"type":"move","x":1,"y":1}"#)
+            .unwrap_err()
+            .downcast::<GameError>()
+            .unwrap();
+        assert_eq!(error, GameError::AlreadyPlayed { x: 1, y: 1 });
+        for msg in [
+            "",
+            "move 1 1",
+            r#"{"type":"undo"}"#,
+            r#"{"type":"move","x":1}"#,
+            r#"{"type":"move","x":-1,"y":0}"#,
+        ]
+        .iter()
+        {
+            let error = super::apply_message(&mut state, msg).unwrap_err();
+            assert!(
+                error.to_string().starts_with("invalid request"),
+                "{}",
+                error
+            );
+        }
+        assert_eq!(state, before);
+    }
+}
@@ -1,14 +1,21 @@
 use core::{
+    convert::TryFrom,
     fmt::{self, Display, Formatter},
+    hash::{Hash, Hasher},
     ops::{Index, IndexMut},
+    str::FromStr,
 };
 
 use anyhow::{self, anyhow as anyhow_error};
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+use crate::notation::{format_algebraic, parse_algebraic, ALGEBRAIC_COLUMNS};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TileState {
     X,
     O,
+    Z,
     Empty,
 }
 
@@ -17,6 +24,7 @@ impl From<Player> for TileState {
         match player {
             Player::X => Self::X,
             Player::O => Self::O,
+            Player::Z => Self::Z,
         }
     }
 }
@@ -29,23 +37,64 @@ impl Display for TileState {
             match self {
                 Self::X => "X",
                 Self::O => "O",
+                Self::Z => "Z",
                 Self::Empty => " ",
             }
         )
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+impl TryFrom<char> for TileState {
+    type Error = anyhow::Error;
+
+    /// Parses a tile as `Display` writes it, ignoring case, or as `.` for an
+    /// empty tile as in the compact encoding
+    fn try_from(c: char) -> anyhow::Result<Self> {
+        match c.to_ascii_uppercase() {
+            'X' => Ok(Self::X),
+            'O' => Ok(Self::O),
+            'Z' => Ok(Self::Z),
+            ' ' | '.' => Ok(Self::Empty),
+            _ => Err(anyhow_error!("unknown tile {:?}", c)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Player {
     X,
     O,
+    /// The third player, only in three-player games
+    Z,
 }
 
 impl Player {
+    /// Every player, in turn order
+    const ALL: [Player; 3] = [Self::X, Self::O, Self::Z];
+
+    /// The other player in a two-player game
     pub fn opponent(&self) -> Self {
+        self.next_player(2)
+    }
+
+    /// The player who moves after this one in a game between the first
+    /// `players` of X, O, and Z
+    pub fn next_player(&self, players: usize) -> Self {
+        Self::ALL[(self.index() + 1) % players]
+    }
+
+    /// The player who moves before this one in a game between the first
+    /// `players` of X, O, and Z
+    pub fn previous_player(&self, players: usize) -> Self {
+        Self::ALL[(self.index() + players - 1) % players]
+    }
+
+    fn index(&self) -> usize {
         match self {
-            Self::X => Self::O,
-            Self::O => Self::X,
+            Self::X => 0,
+            Self::O => 1,
+            Self::Z => 2,
         }
     }
 }
@@ -58,261 +107,3533 @@ impl Display for Player {
             match self {
                 Self::X => "X",
                 Self::O => "O",
+                Self::Z => "Z",
             }
         )
     }
 }
 
+impl FromStr for Player {
+    type Err = anyhow::Error;
+
+    /// Parses a player as `Display` writes it, ignoring case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "X" => Ok(Self::X),
+            "O" => Ok(Self::O),
+            "Z" => Ok(Self::Z),
+            _ => Err(anyhow_error!("unknown player {:?}", s)),
+        }
+    }
+}
+
 impl From<TileState> for Option<Player> {
     fn from(tile: TileState) -> Self {
         match tile {
             TileState::X => Some(Player::X),
             TileState::O => Some(Player::O),
+            TileState::Z => Some(Player::Z),
             TileState::Empty => None,
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameResult {
+    InProgress,
+    Win(Player),
+    Draw,
+}
+
+/// A move or history operation that the board rejected
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameError {
+    /// The coordinates are off the board
+    OutOfBounds {
+        x: usize,
+        y: usize,
+    },
+    /// The tile has already been played
+    AlreadyPlayed {
+        x: usize,
+        y: usize,
+    },
+    NothingToUndo,
+    NothingToRedo,
+    /// The game has already been won or drawn
+    GameOver,
+    /// The player to move may not place this mark
+    WrongMark {
+        mark: TileState,
+    },
+    /// In ultimate tic-tac-toe, the move must be played on the local board at
+    /// these coordinates
+    WrongBoard {
+        x: usize,
+        y: usize,
+    },
+    /// In ultimate tic-tac-toe, the local board at these coordinates has
+    /// already been won or drawn
+    BoardFinished {
+        x: usize,
+        y: usize,
+    },
+    /// In notakto, there is no board with this number among `count` boards
+    NoSuchBoard {
+        board: usize,
+        count: usize,
+    },
+    /// In notakto, the board with this number already holds a line
+    BoardDead {
+        board: usize,
+    },
+}
+
+impl Display for GameError {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Self::OutOfBounds { x, y } => write!(fmt, "({}, {}) is out of bounds", x, y),
+            Self::AlreadyPlayed { x, y } => write!(fmt, "({}, {}) has already been played", x, y),
+            Self::NothingToUndo => write!(fmt, "there are no moves to undo"),
+            Self::NothingToRedo => write!(fmt, "there are no moves to redo"),
+            Self::GameOver => write!(fmt, "the game is already over"),
+            Self::WrongMark {
+                mark: TileState::Empty,
+            } => write!(fmt, "an empty tile can't be placed"),
+            Self::WrongMark { mark } => write!(fmt, "{} can't be placed on this turn", mark),
+            Self::WrongBoard { x, y } => {
+                write!(fmt, "the move must be played on board ({}, {})", x, y)
+            }
+            Self::BoardFinished { x, y } => write!(fmt, "board ({}, {}) is already finished", x, y),
+            Self::NoSuchBoard { board, count } => {
+                write!(fmt, "there is no board {}; there are {}", board, count)
+            }
+            Self::BoardDead { board } => write!(fmt, "board {} is already dead", board),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+/// The side length of a standard board
 pub const BOARD_SIZE: usize = 3;
 
-#[derive(Debug, PartialEq)]
+/// The largest side length of a board read from outside the program, as
+/// many columns as algebraic notation has letters for
+pub const MAX_SIZE: usize = ALGEBRAIC_COLUMNS;
+
+/// A board of any size, with its move history, played by two or three
+/// players.
+///
+/// The size is chosen at run time, since saves, compact strings, the command
+/// line, and serde all learn it only when reading a board. For a board sized
+/// at compile time, see [`ArrayBoard`](crate::array_board::ArrayBoard).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "BoardData"))]
 pub struct BoardState {
+    size: usize,
+    /// How many identical tiles in a row win
+    win_length: usize,
+    tiles: Vec<TileState>,
+    /// How many players take turns, either 2 or 3
+    #[cfg_attr(feature = "serde", serde(default = "players_default"))]
+    players: usize,
+    /// Who moved, or will move, first
+    #[cfg_attr(feature = "serde", serde(default = "first_player_default"))]
+    first: Player,
+    next: Player,
+    /// Every move played so far, in order
+    history: Vec<(usize, usize)>,
+    /// Undone moves available to redo, with the marks they placed, most
+    /// recently undone last
+    undone: Vec<((usize, usize), TileState)>,
+    /// The position's Zobrist key, if known. Moves keep it up to date, but
+    /// `IndexMut` can't see what is written through it, so it clears the key
+    /// to be recomputed when next needed.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    zobrist: Option<u64>,
+    /// The tiles each player holds, as one bit per tile in row-major order,
+    /// if known. Boards of more than 64 tiles never have them, and like the
+    /// Zobrist key they are cleared by `IndexMut`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    masks: Option<[u64; 3]>,
+}
+
+/// Boards serialized before the number of players was recorded had two
+#[cfg(feature = "serde")]
+fn players_default() -> usize {
+    2
+}
+
+/// Boards serialized before the first player was recorded were started by X
+#[cfg(feature = "serde")]
+fn first_player_default() -> Player {
+    Player::X
+}
+
+/// A [`BoardState`] as serialized, checked before it becomes one
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct BoardData {
+    size: usize,
+    win_length: usize,
     tiles: Vec<TileState>,
+    #[serde(default = "players_default")]
+    players: usize,
+    #[serde(default = "first_player_default")]
+    first: Player,
     next: Player,
+    history: Vec<(usize, usize)>,
+    undone: Vec<((usize, usize), TileState)>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<BoardData> for BoardState {
+    type Error = anyhow::Error;
+
+    /// Accepts any board whose parts fit together, whether or not it could
+    /// arise from taking turns, since wild and notakto games can't
+    fn try_from(data: BoardData) -> anyhow::Result<Self> {
+        let BoardData {
+            size,
+            win_length,
+            tiles,
+            players,
+            first,
+            next,
+            history,
+            undone,
+        } = data;
+
+        if size == 0 || size > MAX_SIZE {
+            return Err(anyhow_error!(
+                "a board of size {} is not between 1 and {}",
+                size,
+                MAX_SIZE
+            ));
+        }
+        if size.checked_mul(size) != Some(tiles.len()) {
+            return Err(anyhow_error!(
+                "a board of size {} can't have {} tiles",
+                size,
+                tiles.len()
+            ));
+        }
+        if win_length == 0 || win_length > size {
+            return Err(anyhow_error!(
+                "win length {} doesn't fit a board of size {}",
+                win_length,
+                size
+            ));
+        }
+        if !(players == 2 || (players == 3 && size > BOARD_SIZE)) {
+            return Err(anyhow_error!(
+                "a board of size {} can't have {} players",
+                size,
+                players
+            ));
+        }
+        let active = &Player::ALL[..players];
+        if !active.contains(&first) || !active.contains(&next) {
+            return Err(anyhow_error!(
+                "{} or {} doesn't play in a {}-player game",
+                first,
+                next,
+                players
+            ));
+        }
+
+        let mut board = BoardState {
+            tiles,
+            first,
+            next,
+            ..BoardState::with_players(size, win_length, players)
+        };
+        let pieces = board.tiles.len() - board.count(TileState::Empty);
+        let mut played = std::collections::HashSet::new();
+        let history_fits = history.len() == pieces
+            && history.iter().all(|&(x, y)| {
+                x < size && y < size && board[(x, y)] != TileState::Empty && played.insert((x, y))
+            });
+        let undone_fits = undone
+            .iter()
+            .all(|&((x, y), _)| x < size && y < size && board[(x, y)] == TileState::Empty);
+        if !history_fits || !undone_fits {
+            return Err(anyhow_error!("the move history doesn't match the tiles"));
+        }
+
+        board.history = history;
+        board.undone = undone;
+        Ok(board)
+    }
 }
 
 impl BoardState {
     pub fn new() -> Self {
+        Self::with_size(BOARD_SIZE)
+    }
+
+    /// Creates an empty `size` by `size` board, won by filling a whole line
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    pub fn with_size(size: usize) -> Self {
+        Self::with_win_length(size, size)
+    }
+
+    /// Creates an empty `size` by `size` board, won by `win_length` in a row
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0, or if `win_length` is 0 or greater than `size`.
+    pub fn with_win_length(size: usize, win_length: usize) -> Self {
+        Self::with_players(size, win_length, 2)
+    }
+
+    /// Creates an empty `size` by `size` board, won by `win_length` in a row,
+    /// on which `players` players take turns from X to O to Z
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0, if `win_length` is 0 or greater than `size`, or
+    /// if `players` is not 2 or 3. Three players need a board bigger than 3 by
+    /// 3.
+    pub fn with_players(size: usize, win_length: usize, players: usize) -> Self {
+        assert!(size > 0, "board size must be positive");
+        assert!(
+            win_length > 0 && win_length <= size,
+            "win length must be between 1 and the board size"
+        );
+        assert!(
+            players == 2 || (players == 3 && size > BOARD_SIZE),
+            "there must be two players, or three on a board bigger than 3 by 3"
+        );
+
         BoardState {
-            tiles: vec![TileState::Empty; BOARD_SIZE * BOARD_SIZE],
+            size,
+            win_length,
+            tiles: vec![TileState::Empty; size * size],
+            players,
+            first: Player::X,
             next: Player::X,
+            history: Vec::new(),
+            undone: Vec::new(),
+            zobrist: None,
+            masks: None,
         }
     }
 
-    pub fn play(&mut self, (x, y): (usize, usize)) -> anyhow::Result<&mut Self> {
-        if x > BOARD_SIZE || y > BOARD_SIZE {
-            return Err(anyhow_error!("({}, {}) is out of bounds", x, y));
+    pub fn play(&mut self, coords: (usize, usize)) -> Result<&mut Self, GameError> {
+        self.play_mark(coords, self.next.into())
+    }
+
+    /// Places `mark`, which may be any player's, at `coords` and passes the
+    /// turn as [`play`](Self::play) does, as in wild tic-tac-toe. A line still
+    /// counts for the player whose mark fills it; [`Game`] can credit
+    /// whoever completed it instead.
+    ///
+    /// [`Game`]: crate::game::Game
+    pub fn play_mark(
+        &mut self,
+        (x, y): (usize, usize),
+        mark: TileState,
+    ) -> Result<&mut Self, GameError> {
+        if x >= self.size || y >= self.size {
+            return Err(GameError::OutOfBounds { x, y });
+        }
+
+        let playing =
+            Option::<Player>::from(mark).is_some_and(|player| player.index() < self.players);
+        if !playing {
+            return Err(GameError::WrongMark { mark });
         }
 
         match self[(x, y)] {
             TileState::Empty => {
-                self[(x, y)] = self.next.into();
-                self.next = self.next.opponent();
+                self.place((x, y), mark);
+                self.undone.clear();
                 Ok(self)
             }
-            _ => Err(anyhow_error!("({}, {}) has already been played", x, y)),
+            _ => Err(GameError::AlreadyPlayed { x, y }),
         }
     }
 
-    pub fn next(&self) -> Player {
-        self.next
+    /// Replays `moves` on an empty board, alternating players from X, and
+    /// fails if any move is illegal or comes after the game has been won
+    pub fn from_moves(moves: &[(usize, usize)]) -> anyhow::Result<Self> {
+        BoardState::new().replay(moves)
     }
 
-    pub fn iter_row(&self, row: usize) -> impl Iterator<Item = TileState> + '_ {
-        (0..BOARD_SIZE).map(move |x| self[(x, row)])
-    }
+    /// Plays `moves` in order, failing if any is illegal or comes after the
+    /// game has been won
+    pub(crate) fn replay(mut self, moves: &[(usize, usize)]) -> anyhow::Result<Self> {
+        for &coords in moves {
+            self.replay_mark(coords, None)?;
+        }
 
-    pub fn iter_col(&self, col: usize) -> impl Iterator<Item = TileState> + '_ {
-        (0..BOARD_SIZE).map(move |y| self[(col, y)])
+        Ok(self)
     }
 
-    pub fn iter_diag(&self, sinister: bool) -> impl Iterator<Item = TileState> + '_ {
-        (0..BOARD_SIZE).map(move |i| self[(if sinister { BOARD_SIZE - 1 - i } else { i }, i)])
+    /// Places `mark` at `coords`, or the next player's own mark if none is
+    /// given, failing if the move is illegal or comes after the game has been
+    /// won
+    pub(crate) fn replay_mark(
+        &mut self,
+        (x, y): (usize, usize),
+        mark: Option<TileState>,
+    ) -> anyhow::Result<()> {
+        if let Some(winner) = self.won() {
+            return Err(anyhow_error!(
+                "({}, {}) was played after {} had already won",
+                x,
+                y,
+                winner
+            ));
+        }
+
+        self.play_mark((x, y), mark.unwrap_or_else(|| self.next.into()))?;
+        Ok(())
     }
 
-    pub fn won(&self) -> Option<Player> {
-        (0..BOARD_SIZE)
-            .map(|row| all_eq(self.iter_row(row)))
-            .chain((0..BOARD_SIZE).map(|col| all_eq(self.iter_col(col))))
-            .chain(
-                [false, true]
-                    .iter()
-                    .map(|&sinister| all_eq(self.iter_diag(sinister))),
-            )
-            .find_map(|opt_tile| opt_tile.and_then(|tile| tile.into()))
+    /// Encodes the board's rules and move history as text to save to a file,
+    /// one `setting value` pair per line, as in
+    ///
+    /// ```text
+    /// size 3
+    /// win-length 3
+    /// players 2
+    /// first X
+    /// move 1 1
+    /// move 0 2
+    /// ```
+    ///
+    /// A move that placed someone else's mark, as in a wild game, ends with
+    /// that mark, as in `move 0 2 X`.
+    pub fn to_save(&self) -> String {
+        let mut save = format!(
+            "size {}\nwin-length {}\nplayers {}\nfirst {}\n",
+            self.size, self.win_length, self.players, self.first
+        );
+
+        for (player, (x, y), mark) in self.moves() {
+            if mark == player.into() {
+                save.push_str(&format!("move {} {}\n", x, y));
+            } else {
+                save.push_str(&format!("move {} {} {}\n", x, y, mark));
+            }
+        }
+
+        save
     }
 
-    pub fn drawn(&self) -> bool {
-        self.tiles.iter().all(|&tile| tile != TileState::Empty)
+    /// Parses the encoding produced by [`to_save`](Self::to_save) and replays
+    /// its moves. Only the size is required; the other settings default as in
+    /// [`with_size`](Self::with_size). Fails if any setting is malformed or any
+    /// move is illegal.
+    pub fn from_save(save: &str) -> anyhow::Result<Self> {
+        let mut size = None;
+        let mut win_length = None;
+        let mut players = 2;
+        let mut first = Player::X;
+        let mut moves = Vec::new();
+
+        for (i, line) in save.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (setting, value) = line
+                .split_once(' ')
+                .ok_or_else(|| anyhow_error!("expected a setting and a value on line {}", i + 1))?;
+            let number = |value: &str| {
+                value.trim().parse::<usize>().map_err(|_| {
+                    anyhow_error!("expected a number for {} on line {}", setting, i + 1)
+                })
+            };
+
+            match setting {
+                "size" => size = Some(number(value)?),
+                "win-length" => win_length = Some(number(value)?),
+                "players" => players = number(value)?,
+                "first" => first = value.trim().parse()?,
+                "move" => match value.split_whitespace().collect::<Vec<_>>()[..] {
+                    [x, y] => moves.push(((number(x)?, number(y)?), None)),
+                    [x, y, mark] => moves.push(((number(x)?, number(y)?), Some(parse_mark(mark)?))),
+                    _ => return Err(anyhow_error!("expected x and y on line {}", i + 1)),
+                },
+                _ => {
+                    return Err(anyhow_error!(
+                        "unknown setting {:?} on line {}",
+                        setting,
+                        i + 1
+                    ))
+                }
+            }
+        }
+
+        let size = size.ok_or_else(|| anyhow_error!("the save has no size"))?;
+        let mut board = BoardState::checked(size, win_length.unwrap_or(size), players)?;
+        if !board.turn_order(Player::X).contains(&first) {
+            return Err(anyhow_error!("{} isn't playing", first));
+        }
+
+        board.set_first_player(first);
+        for (coords, mark) in moves {
+            board.replay_mark(coords, mark)?;
+        }
+
+        Ok(board)
     }
-}
 
-/// If `iter` is nonempty and all its items are equal, returns an item
-fn all_eq<T, I>(mut iter: I) -> Option<T>
-where
-    I: Iterator<Item = T>,
-    T: PartialEq,
-{
-    iter.next().and_then(|first| {
-        if iter.all(|item| item == first) {
-            Some(first)
-        } else {
-            None
+    /// An empty board as made by [`with_players`](Self::with_players), failing
+    /// rather than panicking if the settings don't fit together or the size
+    /// is past [`MAX_SIZE`]
+    pub(crate) fn checked(size: usize, win_length: usize, players: usize) -> anyhow::Result<Self> {
+        if size > MAX_SIZE {
+            return Err(anyhow_error!(
+                "a {} by {} board is larger than the limit of {}",
+                size,
+                size,
+                MAX_SIZE
+            ));
         }
-    })
-}
 
-impl Index<(usize, usize)> for BoardState {
-    type Output = TileState;
+        if size == 0 || win_length == 0 || win_length > size {
+            return Err(anyhow_error!(
+                "a win length of {} doesn't fit a {} by {} board",
+                win_length,
+                size,
+                size
+            ));
+        }
 
-    fn index(&self, (x, y): (usize, usize)) -> &<Self as Index<(usize, usize)>>::Output {
-        &self.tiles[x + y * BOARD_SIZE]
+        if !(players == 2 || (players == 3 && size > BOARD_SIZE)) {
+            return Err(anyhow_error!(
+                "{} players can't play on a {} by {} board",
+                players,
+                size,
+                size
+            ));
+        }
+
+        Ok(BoardState::with_players(size, win_length, players))
     }
-}
 
-impl IndexMut<(usize, usize)> for BoardState {
-    fn index_mut(
-        &mut self,
-        (x, y): (usize, usize),
-    ) -> &mut <Self as Index<(usize, usize)>>::Output {
-        &mut self.tiles[x + y * BOARD_SIZE]
+    /// Returns a copy of the board rotated a quarter turn clockwise
+    pub fn rotate90(&self) -> Self {
+        let size = self.size;
+        self.transform(|(x, y)| (size - 1 - y, x))
     }
-}
 
-impl Display for BoardState {
-    fn fmt(&self, fmt: &mut Formatter) -> Result<(), fmt::Error> {
-        write!(fmt, "  ")?;
+    /// Returns a copy of the board mirrored left to right
+    pub fn reflect_horizontal(&self) -> Self {
+        let size = self.size;
+        self.transform(|(x, y)| (size - 1 - x, y))
+    }
+
+    /// Returns a copy of the board mirrored across its main diagonal
+    pub fn transpose(&self) -> Self {
+        self.transform(|(x, y)| (y, x))
+    }
 
-        for x in 0..BOARD_SIZE {
-            write!(fmt, "{}", x)?;
+    /// Returns the one of the board's eight rotations and reflections whose
+    /// [compact encoding](Self::to_compact) sorts first, so that boards
+    /// equivalent under symmetry have the same canonical form
+    pub fn canonical(&self) -> Self {
+        let mut rotation = self.clone();
+        let mut best = self.clone();
+        let mut best_compact = self.to_compact();
 
-            if x != BOARD_SIZE - 1 {
-                write!(fmt, " ")?;
+        for _ in 0..4 {
+            for candidate in [rotation.reflect_horizontal(), rotation.clone()].iter() {
+                let compact = candidate.to_compact();
+                if compact < best_compact {
+                    best = candidate.clone();
+                    best_compact = compact;
+                }
             }
+            rotation = rotation.rotate90();
         }
 
-        writeln!(fmt, "")?;
-        writeln!(fmt, "")?;
-
-        for y in 0..BOARD_SIZE {
-            write!(fmt, "{} ", y)?;
+        best
+    }
 
-            for x in 0..BOARD_SIZE {
-                write!(fmt, "{}", self[(x, y)])?;
+    /// Returns a copy of the board with each tile, and each move in its
+    /// history, moved to `map(coords)`, which must be a symmetry of the board
+    fn transform(&self, map: impl Fn((usize, usize)) -> (usize, usize)) -> Self {
+        let mut board = BoardState {
+            history: self.history.iter().copied().map(&map).collect(),
+            undone: self
+                .undone
+                .iter()
+                .map(|&(coords, mark)| (map(coords), mark))
+                .collect(),
+            ..self.clone()
+        };
 
-                if x != BOARD_SIZE - 1 {
-                    write!(fmt, "|")?;
-                }
+        for y in 0..self.size {
+            for x in 0..self.size {
+                board[map((x, y))] = self[(x, y)];
             }
+        }
 
-            if y != BOARD_SIZE - 1 {
-                writeln!(fmt, "")?;
+        board
+    }
 
-                write!(fmt, "  ")?;
+    /// Encodes the board row-major as `X`, `O`, `Z`, and `.` for empty tiles
+    pub fn to_compact(&self) -> String {
+        self.tiles
+            .iter()
+            .map(|tile| match tile {
+                TileState::X => 'X',
+                TileState::O => 'O',
+                TileState::Z => 'Z',
+                TileState::Empty => '.',
+            })
+            .collect()
+    }
 
-                for x in 0..BOARD_SIZE {
-                    write!(fmt, "-")?;
+    /// Parses the encoding produced by [`to_compact`](Self::to_compact),
+    /// inferring the board size from its length, the number of players from
+    /// whether Z has played, and the next player from the number of pieces
+    /// each has played
+    pub fn from_compact(s: &str) -> anyhow::Result<Self> {
+        let tiles = s
+            .chars()
+            .map(|c| match c {
+                'X' => Ok(TileState::X),
+                'O' => Ok(TileState::O),
+                'Z' => Ok(TileState::Z),
+                '.' => Ok(TileState::Empty),
+                _ => Err(anyhow_error!("unexpected character {:?}", c)),
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
 
-                    if x != BOARD_SIZE - 1 {
-                        write!(fmt, "+")?;
-                    }
-                }
+        let size = Self::square_size(tiles.len())?;
+        let players = Self::infer_players(&tiles, size, None)?;
+        let mut board = BoardState {
+            tiles,
+            ..BoardState::with_players(size, size, players)
+        };
+        board.next = board.infer_next()?;
+        Ok(board)
+    }
 
-                writeln!(fmt, "")?;
-            }
-        }
+    /// Sets up a position from its tiles, row-major, with `next` to move, such
+    /// as a puzzle. The board size is inferred from the number of tiles, the
+    /// number of players from whether Z is involved, and the first player from
+    /// the number of pieces each has played. Fails if the position could not
+    /// arise from play.
+    pub fn from_tiles(tiles: Vec<TileState>, next: Player) -> anyhow::Result<Self> {
+        let size = Self::square_size(tiles.len())?;
+        let players = Self::infer_players(&tiles, size, Some(next))?;
+        let mut board = BoardState {
+            tiles,
+            ..BoardState::with_players(size, size, players)
+        };
+        // The first player is the earliest, counting round from `next`, of
+        // those who have played the most
+        let order = board.turn_order(next);
+        let most = order.iter().map(|&player| board.count(player.into())).max();
+        board.first = order
+            .into_iter()
+            .find(|&player| Some(board.count(player.into())) == most)
+            .unwrap_or(next);
+        board.next = next;
 
-        Ok(())
+        if board.is_valid() {
+            Ok(board)
+        } else {
+            Err(anyhow_error!(
+                "{:?} with {} to move could not arise from play",
+                board.to_compact(),
+                next
+            ))
+        }
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
+    /// Three players are involved if Z has played or is `next`, which needs a
+    /// board bigger than 3 by 3
+    fn infer_players(
+        tiles: &[TileState],
+        size: usize,
+        next: Option<Player>,
+    ) -> anyhow::Result<usize> {
+        if !tiles.contains(&TileState::Z) && next != Some(Player::Z) {
+            Ok(2)
+        } else if size > BOARD_SIZE {
+            Ok(3)
+        } else {
+            Err(anyhow_error!(
+                "three players need a board bigger than {} by {}",
+                BOARD_SIZE,
+                BOARD_SIZE
+            ))
+        }
+    }
 
-    mod tile_state {
-        use super::*;
+    /// The active players in turn order, starting from `player`
+    fn turn_order(&self, player: Player) -> Vec<Player> {
+        core::iter::successors(Some(player), |player| {
+            Some(player.next_player(self.players))
+        })
+        .take(self.players)
+        .collect()
+    }
 
-        #[test]
-        fn display() {
-            assert_eq!(format!("{}", TileState::X), "X");
-            assert_eq!(format!("{}", TileState::O), "O");
-            assert_eq!(format!("{}", TileState::Empty), " ");
+    /// The side length of a board with `len` tiles, failing unless `len` is a
+    /// positive square number
+    fn square_size(len: usize) -> anyhow::Result<usize> {
+        match (1..=len).find(|&size| size * size >= len) {
+            Some(size) if size * size == len => Ok(size),
+            _ => Err(anyhow_error!(
+                "expected a positive square number of tiles, got {}",
+                len
+            )),
         }
     }
 
-    mod player {
-        use super::*;
+    /// Works out whose turn it is from the number of pieces each player has
+    /// played, failing if taking turns from the first player could not have
+    /// produced them
+    pub fn infer_next(&self) -> anyhow::Result<Player> {
+        if self.players == 2 && self.count(TileState::Z) > 0 {
+            return Err(anyhow_error!("Z cannot play in a two-player game"));
+        }
 
-        #[test]
-        fn opponent() {
-            assert_eq!(Player::X.opponent(), Player::O);
-            assert_eq!(Player::X.opponent().opponent(), Player::X);
+        // Each player in turn order has played as many pieces as the first, up
+        // to the next player, and everyone from them on has played one fewer
+        let order = self.turn_order(self.first);
+        let counts = order
+            .iter()
+            .map(|&player| self.count(player.into()))
+            .collect::<Vec<_>>();
+        let taken = counts
+            .iter()
+            .take_while(|&&count| count == counts[0])
+            .count();
+
+        if counts[taken..].iter().all(|&count| count + 1 == counts[0]) {
+            Ok(order.get(taken).copied().unwrap_or(self.first))
+        } else {
+            let mut described = Player::ALL[..self.players]
+                .iter()
+                .map(|&player| format!("{} {}", self.count(player.into()), player))
+                .collect::<Vec<_>>();
+            let last = described.pop().unwrap_or_default();
+            Err(anyhow_error!(
+                "{} and {} cannot arise from alternating play",
+                described.join(", "),
+                last
+            ))
         }
     }
 
-    mod board_state {
-        use super::*;
+    /// Counts the tiles in the state `target`
+    fn count(&self, target: TileState) -> usize {
+        self.tiles.iter().filter(|&&tile| tile == target).count()
+    }
 
-        #[test]
-        fn display() {
-            insta::assert_snapshot!(format!("{}", BoardState::new().play((1, 1)).unwrap()), @r###"
-              0 1 2
+    /// Takes back the most recent move
+    pub fn undo(&mut self) -> Result<(), GameError> {
+        let coords = self.history.pop().ok_or(GameError::NothingToUndo)?;
+        self.undone.push((coords, self[coords]));
+        self.put(coords, TileState::Empty);
+        self.pass_turn(self.next.previous_player(self.players));
+        Ok(())
+    }
 
-            0  | | 
-              -+-+-
-            1  |X| 
-              -+-+-
-            2  | | 
-            "###);
+    /// Replays the most recently undone move
+    pub fn redo(&mut self) -> Result<(), GameError> {
+        let (coords, mark) = self.undone.pop().ok_or(GameError::NothingToRedo)?;
+        self.place(coords, mark);
+        Ok(())
+    }
+
+    /// Winds the board back or forward to how it was after the first `n` moves,
+    /// counting both the moves played and any undone since. Fails if there are
+    /// fewer than `n`.
+    pub fn goto_move(&mut self, n: usize) -> anyhow::Result<()> {
+        let moves = self.history.len() + self.undone.len();
+        if n > moves {
+            return Err(anyhow_error!(
+                "can't go to move {} of a game with {} moves",
+                n,
+                moves
+            ));
         }
 
-        #[test]
-        fn next() {
-            assert_eq!(BoardState::new().next(), Player::X);
-            assert_eq!(BoardState::new().play((0, 0)).unwrap().next(), Player::O);
+        while self.history.len() > n {
+            self.undo()?;
         }
 
-        #[test]
-        fn iter_row() {
-            use TileState::*;
-            let board = BoardState {
-                tiles: vec![X, X, Empty, O, X, Empty, Empty, Empty, O],
-                next: Player::O,
-            };
-            assert_eq!(board.iter_row(0).collect::<Vec<_>>(), vec![X, X, Empty]);
-            assert_eq!(board.iter_row(1).collect::<Vec<_>>(), vec![O, X, Empty]);
+        while self.history.len() < n {
+            self.redo()?;
         }
 
-        #[test]
-        fn iter_col() {
-            use TileState::*;
-            let board = BoardState {
-                tiles: vec![X, X, Empty, O, X, Empty, Empty, Empty, O],
-                next: Player::O,
-            };
-            assert_eq!(board.iter_col(0).collect::<Vec<_>>(), vec![X, O, Empty]);
-            assert_eq!(board.iter_col(1).collect::<Vec<_>>(), vec![X, X, Empty]);
+        Ok(())
+    }
+
+    /// Gives the first move to `player` instead of X
+    pub(crate) fn set_first_player(&mut self, player: Player) {
+        assert!(self.history.is_empty(), "the game has already started");
+        self.first = player;
+        self.pass_turn(player);
+    }
+
+    /// Before the first move of a recorded game is replayed, gives the first
+    /// move to its author, if they are playing, since records don't say
+    /// separately who started
+    pub(crate) fn start_with(&mut self, player: Player) {
+        if self.turn_order(Player::X).contains(&player) {
+            self.set_first_player(player);
         }
+    }
 
-        #[test]
-        fn iter_diag() {
-            use TileState::*;
-            let board = BoardState {
-                tiles: vec![X, X, Empty, O, X, Empty, Empty, Empty, O],
-                next: Player::O,
-            };
-            assert_eq!(board.iter_diag(false).collect::<Vec<_>>(), vec![X, X, O]);
-            assert_eq!(
-                board.iter_diag(true).collect::<Vec<_>>(),
-                vec![Empty, X, Empty]
-            );
+    /// Who moved, or will move, first
+    pub fn first_player(&self) -> Player {
+        self.first
+    }
+
+    /// Puts `tile` at `coords` regardless of whose turn it is, for setting up
+    /// puzzles. Unlike [`play`](Self::play), this doesn't change the next
+    /// player or the history, so the board may no longer be
+    /// [valid](Self::is_valid).
+    pub fn set(&mut self, (x, y): (usize, usize), tile: TileState) -> anyhow::Result<()> {
+        if x >= self.size || y >= self.size {
+            return Err(GameError::OutOfBounds { x, y }.into());
         }
 
-        #[test]
-        fn won() {
-            use TileState::*;
-            assert_eq!(BoardState::new().won(), None);
-            let board = BoardState {
-                tiles: vec![X, O, X, O, X, X, O, X, O],
-                next: Player::O,
-            };
-            assert_eq!(board.won(), None);
-            let board = BoardState {
-                tiles: vec![X, O, X, O, X, O, X, Empty, Empty],
-                next: Player::O,
-            };
-            assert_eq!(board.won(), Some(Player::X));
+        self.put((x, y), tile);
+        Ok(())
+    }
+
+    /// Plays `mark` at `coords` for the next player without any validation
+    fn place(&mut self, coords: (usize, usize), mark: TileState) {
+        self.put(coords, mark);
+        self.pass_turn(self.next.next_player(self.players));
+        self.history.push(coords);
+    }
+
+    /// Writes `tile` at `coords`, keeping the Zobrist key and masks up to date
+    fn put(&mut self, (x, y): (usize, usize), tile: TileState) {
+        let index = x + y * self.size;
+        let key = self.zobrist() ^ tile_key(index, self.tiles[index]) ^ tile_key(index, tile);
+        let masks = self.masks().map(|mut masks| {
+            for mask in masks.iter_mut() {
+                *mask &= !(1 << index);
+            }
+            if let Some(player) = Option::<Player>::from(tile) {
+                masks[player.index()] |= 1 << index;
+            }
+            masks
+        });
+        self.tiles[index] = tile;
+        self.zobrist = Some(key);
+        self.masks = masks;
+    }
+
+    /// Makes `player` the next to move, keeping the Zobrist key up to date
+    fn pass_turn(&mut self, player: Player) {
+        let key = self.zobrist() ^ player_key(self.next) ^ player_key(player);
+        self.next = player;
+        self.zobrist = Some(key);
+    }
+
+    /// A Zobrist key for the tiles and the player to move: equal positions
+    /// share a key and distinct positions almost never do. Moves update it
+    /// rather than rehashing the board, making it a cheap key for
+    /// transposition tables.
+    pub fn zobrist(&self) -> u64 {
+        self.zobrist.unwrap_or_else(|| {
+            self.tiles
+                .iter()
+                .enumerate()
+                .fold(player_key(self.next), |key, (index, &tile)| {
+                    key ^ tile_key(index, tile)
+                })
+        })
+    }
+
+    /// The tiles each player holds as bitmasks, indexed like
+    /// [`Player::index`], or `None` if the board has too many tiles
+    fn masks(&self) -> Option<[u64; 3]> {
+        if self.tiles.len() > u64::BITS as usize {
+            return None;
+        }
+
+        self.masks.or_else(|| {
+            let mut masks = [0; 3];
+            for (index, &tile) in self.tiles.iter().enumerate() {
+                if let Some(player) = Option::<Player>::from(tile) {
+                    masks[player.index()] |= 1 << index;
+                }
+            }
+            Some(masks)
+        })
+    }
+
+    /// Whether the tiles of `mask` include a winning run in any direction
+    fn has_run(&self, mask: u64) -> bool {
+        let (size, length) = (self.size, self.win_length);
+        // The tiles in columns `xs` of every row
+        let columns = |xs: core::ops::Range<usize>| {
+            let row = xs.fold(0u64, |row, x| row | 1 << x);
+            (0..size).fold(0, |columns, y| columns | row << (y * size))
+        };
+
+        // Each direction steps by a fixed number of bits, from starting tiles
+        // far enough from the edge that every step stays on the board
+        [
+            (1, columns(0..size + 1 - length)),
+            (size, columns(0..size)),
+            (size + 1, columns(0..size + 1 - length)),
+            (size - 1, columns(length - 1..size)),
+        ]
+        .iter()
+        .any(|&(step, starts)| {
+            (1..length).fold(mask & starts, |run, i| run & mask >> (step * i)) != 0
+        })
+    }
+
+    pub fn next(&self) -> Player {
+        self.next
+    }
+
+    /// How many players take turns, either 2 or 3
+    pub fn players(&self) -> usize {
+        self.players
+    }
+
+    /// The side length of the board
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// How many identical tiles in a row win
+    pub fn win_length(&self) -> usize {
+        self.win_length
+    }
+
+    /// The moves played so far, oldest first
+    pub fn history(&self) -> &[(usize, usize)] {
+        &self.history
+    }
+
+    /// The moves played so far, oldest first, each with the player who made
+    /// it and the mark they placed, which differ only in wild games
+    pub fn moves(&self) -> impl Iterator<Item = (Player, (usize, usize), TileState)> + '_ {
+        let mut player = self.first;
+        self.history.iter().map(move |&coords| {
+            let mover = player;
+            player = player.next_player(self.players);
+            (mover, coords, self[coords])
+        })
+    }
+
+    /// Writes the board's settings and then the moves played so far in
+    /// algebraic notation, numbering each round of turns, as in
+    /// `size=3 win-length=3 players=2 1. X:b2 O:a1 2. X:c3`. A move that
+    /// placed someone else's mark, as in a wild game, ends with that mark, as
+    /// in `O:a1=X`. Fails if a move is past the columns that have letters.
+    pub fn to_notation(&self) -> anyhow::Result<String> {
+        let mut notation = vec![
+            format!("size={}", self.size),
+            format!("win-length={}", self.win_length),
+            format!("players={}", self.players),
+        ];
+
+        for (i, (player, coords, mark)) in self.moves().enumerate() {
+            if i.is_multiple_of(self.players) {
+                notation.push(format!("{}.", i / self.players + 1));
+            }
+
+            let mut token = format!("{}:{}", player, format_algebraic(coords)?);
+            if mark != player.into() {
+                token.push_str(&format!("={}", mark));
+            }
+            notation.push(token);
+        }
+
+        Ok(notation.join(" "))
+    }
+
+    /// Reads back what [`to_notation`](Self::to_notation) writes. Settings
+    /// left out default as in [`with_size`](Self::with_size), from a size of
+    /// 3. Whoever made the first move is taken to have started. Fails if a
+    /// setting is unknown or doesn't fit, the rounds are misnumbered, a cell is
+    /// unknown, or a move is illegal or out of turn.
+    pub fn from_notation(notation: &str) -> anyhow::Result<Self> {
+        let mut tokens = notation.split_whitespace().peekable();
+        let (mut size, mut win_length, mut players) = (BOARD_SIZE, None, 2);
+        while let Some((setting, value)) = tokens.peek().and_then(|token| token.split_once('=')) {
+            let number = value
+                .parse::<usize>()
+                .map_err(|_| anyhow_error!("expected a number for {}, got {:?}", setting, value))?;
+            match setting {
+                "size" => size = number,
+                "win-length" => win_length = Some(number),
+                "players" => players = number,
+                _ => return Err(anyhow_error!("unknown setting {:?}", setting)),
+            }
+            tokens.next();
+        }
+
+        let mut board = BoardState::checked(size, win_length.unwrap_or(size), players)?;
+        let mut numbered = false;
+
+        for token in tokens {
+            let played = board.history.len();
+            let round = played / board.players + 1;
+            let round_start = played.is_multiple_of(board.players);
+
+            if let Some(number) = token.strip_suffix('.') {
+                if !round_start || numbered || number.parse() != Ok(round) {
+                    return Err(anyhow_error!(
+                        "expected a move, or move number {}, got {:?}",
+                        round,
+                        token
+                    ));
+                }
+
+                numbered = true;
+                continue;
+            }
+
+            if round_start && !numbered {
+                return Err(anyhow_error!(
+                    "expected move number {} before {:?}",
+                    round,
+                    token
+                ));
+            }
+            numbered = false;
+
+            let (player, cell) = token
+                .split_once(':')
+                .ok_or_else(|| anyhow_error!("expected a move like X:b2, got {:?}", token))?;
+            let player = player.parse::<Player>()?;
+            let (cell, mark) = match cell.split_once('=') {
+                Some((cell, mark)) => (cell, Some(parse_mark(mark)?)),
+                None => (cell, None),
+            };
+            let coords = parse_algebraic(cell, board.size)?;
+
+            if played == 0 {
+                board.start_with(player);
+            }
+
+            if player != board.next {
+                return Err(anyhow_error!("{} moved out of turn in {:?}", player, token));
+            }
+
+            board.replay_mark(coords, mark)?;
+        }
+
+        if numbered {
+            return Err(anyhow_error!("the last move number has no moves"));
+        }
+
+        Ok(board)
+    }
+
+    /// Iterates over the coordinates of every empty tile in row-major order
+    pub fn available_moves(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.size)
+            .flat_map(move |y| (0..self.size).map(move |x| (x, y)))
+            .filter(move |&coords| self[coords] == TileState::Empty)
+    }
+
+    /// The number of empty tiles
+    pub fn remaining(&self) -> usize {
+        self.tiles
+            .iter()
+            .filter(|&&tile| tile == TileState::Empty)
+            .count()
+    }
+
+    /// The number of occupied tiles, whether played or set by hand
+    pub fn move_count(&self) -> usize {
+        self.tiles.len() - self.remaining()
+    }
+
+    /// Whether every tile is empty
+    pub fn is_empty(&self) -> bool {
+        self.move_count() == 0
+    }
+
+    pub fn iter_row(&self, row: usize) -> impl Iterator<Item = TileState> + '_ {
+        (0..self.size).map(move |x| self[(x, row)])
+    }
+
+    pub fn iter_col(&self, col: usize) -> impl Iterator<Item = TileState> + '_ {
+        (0..self.size).map(move |y| self[(col, y)])
+    }
+
+    pub fn iter_diag(&self, sinister: bool) -> impl Iterator<Item = TileState> + '_ {
+        (0..self.size).map(move |i| self[(if sinister { self.size - 1 - i } else { i }, i)])
+    }
+
+    pub fn status(&self) -> GameResult {
+        // The masks rule out a win in a few operations, leaving the scan to
+        // find which line comes first on boards that have one
+        let maybe_won = self
+            .masks()
+            .is_none_or(|masks| masks.iter().any(|&mask| self.has_run(mask)));
+        if let Some((player, _)) = maybe_won.then(|| self.winning_line()).flatten() {
+            GameResult::Win(player)
+        } else if self.tiles.iter().all(|&tile| tile != TileState::Empty) {
+            GameResult::Draw
+        } else {
+            GameResult::InProgress
+        }
+    }
+
+    pub fn won(&self) -> Option<Player> {
+        match self.status() {
+            GameResult::Win(player) => Some(player),
+            _ => None,
+        }
+    }
+
+    /// Returns the winner along with the coordinates of their winning run.
+    ///
+    /// Lines are checked rows first, then columns, then the dexter and sinister
+    /// diagonals, and each line from its start, so a board with several
+    /// winning runs always reports the first in that order.
+    pub fn winning_line(&self) -> Option<(Player, Vec<(usize, usize)>)> {
+        self.iter_lines().find_map(|line| {
+            line.windows(self.win_length)
+                .find_map(|run| self.run_winner(run).map(|player| (player, run.to_vec())))
+        })
+    }
+
+    /// The player holding every tile of `run`, if any
+    fn run_winner(&self, run: &[(usize, usize)]) -> Option<Player> {
+        all_eq(run.iter().map(|&coord| self[coord])).and_then(Option::<Player>::from)
+    }
+
+    /// Checks that the board could have arisen from alternating play starting
+    /// with the board's first player: its dimensions are consistent, the piece
+    /// counts agree with whose turn it is, and at most one player has a
+    /// winning line.
+    ///
+    /// Boards built through `play` are always valid, but deserialized or
+    /// hand-edited boards may not be.
+    pub fn is_valid(&self) -> bool {
+        if self.size == 0
+            || self.win_length == 0
+            || self.win_length > self.size
+            || self.tiles.len() != self.size * self.size
+        {
+            return false;
+        }
+
+        if self.infer_next().ok() != Some(self.next) {
+            return false;
+        }
+
+        let winners = self
+            .iter_lines()
+            .flat_map(|line| {
+                line.windows(self.win_length)
+                    .filter_map(|run| self.run_winner(run))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        winners.iter().all(|&player| player == winners[0])
+    }
+
+    pub fn drawn(&self) -> bool {
+        self.status() == GameResult::Draw
+    }
+
+    /// Whether the game can only end in a draw because every run that could
+    /// win already holds pieces of two different players, even if tiles are
+    /// still empty. A full board that nobody has won is a dead draw too.
+    pub fn is_dead_draw(&self) -> bool {
+        self.won().is_none()
+            && self.iter_lines().all(|line| {
+                line.windows(self.win_length)
+                    .all(|run| self.run_blocked(run))
+            })
+    }
+
+    /// Whether `run` holds pieces of more than one player
+    fn run_blocked(&self, run: &[(usize, usize)]) -> bool {
+        let mut owners = run
+            .iter()
+            .filter_map(|&coords| Option::<Player>::from(self[coords]));
+        match owners.next() {
+            Some(first) => owners.any(|player| player != first),
+            None => false,
+        }
+    }
+
+    /// Iterates over the coordinates of every row, then every column, then the
+    /// dexter and sinister diagonals long enough to hold a winning run, main
+    /// diagonals first.
+    ///
+    /// When the whole line must be filled to win, these are just the rows,
+    /// columns, and two main diagonals.
+    pub fn iter_lines(&self) -> impl Iterator<Item = Vec<(usize, usize)>> {
+        let size = self.size;
+        // How far off the main diagonals a diagonal may start
+        let spare = size - self.win_length;
+
+        let rows = (0..size).map(move |y| (0..size).map(|x| (x, y)).collect());
+        let cols = (0..size).map(move |x| (0..size).map(|y| (x, y)).collect());
+        let dexter = (0..=spare)
+            .map(|x| (x, 0))
+            .chain((1..=spare).map(|y| (0, y)))
+            .map(move |(x, y)| (0..size - x.max(y)).map(|i| (x + i, y + i)).collect());
+        let sinister = (0..=spare)
+            .map(move |d| (size - 1 - d, 0))
+            .chain((1..=spare).map(move |y| (size - 1, y)))
+            .map(move |(x, y)| (0..(x + 1).min(size - y)).map(|i| (x - i, y + i)).collect());
+
+        rows.chain(cols).chain(dexter).chain(sinister)
+    }
+}
+
+impl BoardState {
+    /// Renders the board like `Display`, with options to change its appearance
+    pub fn render(&self) -> Render<'_> {
+        Render {
+            board: self,
+            labels: true,
+            unicode: false,
+            highlight: false,
+            symbols: Symbols::default(),
+            #[cfg(feature = "color")]
+            color: false,
+        }
+    }
+
+    /// A view of the board that can look but not play
+    pub fn view(&self) -> GameView<'_> {
+        GameView { board: self }
+    }
+
+    /// Displays the board in a grid of box-drawing characters
+    pub fn display_unicode(&self) -> Render<'_> {
+        self.render().with_unicode(true)
+    }
+
+    /// Displays the board like `Display`, but with X in red and O in blue,
+    /// unless the `NO_COLOR` environment variable is set
+    #[cfg(feature = "color")]
+    pub fn display_colored(&self) -> Render<'_> {
+        self.render()
+            .with_color(color_allowed(std::env::var_os("NO_COLOR").as_deref()))
+    }
+}
+
+/// Whether `no_color`, the value of the `NO_COLOR` environment variable,
+/// allows color, which it does unless set to something non-empty
+#[cfg(feature = "color")]
+fn color_allowed(no_color: Option<&std::ffi::OsStr>) -> bool {
+    no_color.is_none_or(|value| value.is_empty())
+}
+
+impl Default for BoardState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Boards are equal if they hold the same position, however it was reached
+impl PartialEq for BoardState {
+    fn eq(&self, other: &Self) -> bool {
+        self.size == other.size
+            && self.win_length == other.win_length
+            && self.tiles == other.tiles
+            && self.players == other.players
+            && self.next == other.next
+    }
+}
+
+impl Eq for BoardState {}
+
+/// Hashes exactly the fields compared by `PartialEq`
+impl Hash for BoardState {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.size.hash(state);
+        self.win_length.hash(state);
+        self.tiles.hash(state);
+        self.players.hash(state);
+        self.next.hash(state);
+    }
+}
+
+/// The Zobrist key of `tile` at tile `index`. Keys are hashed from the tile
+/// and index rather than drawn from a table, so boards of any size have them.
+fn tile_key(index: usize, tile: TileState) -> u64 {
+    match Option::<Player>::from(tile) {
+        Some(player) => splitmix((index * Player::ALL.len() + player.index()) as u64),
+        None => 0,
+    }
+}
+
+/// The Zobrist key of `player` being next to move
+fn player_key(player: Player) -> u64 {
+    splitmix(u64::MAX - player.index() as u64)
+}
+
+/// The SplitMix64 finalizer, which scatters distinct seeds across all 64 bits
+fn splitmix(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Parses a mark as saves and notation write it, a single letter
+pub(crate) fn parse_mark(mark: &str) -> anyhow::Result<TileState> {
+    let mut chars = mark.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => TileState::try_from(c),
+        _ => Err(anyhow_error!("expected a mark, got {:?}", mark)),
+    }
+}
+
+/// If `iter` is nonempty and all its items are equal, returns an item
+pub(crate) fn all_eq<T, I>(mut iter: I) -> Option<T>
+where
+    I: Iterator<Item = T>,
+    T: PartialEq,
+{
+    iter.next().and_then(|first| {
+        if iter.all(|item| item == first) {
+            Some(first)
+        } else {
+            None
+        }
+    })
+}
+
+impl Index<(usize, usize)> for BoardState {
+    type Output = TileState;
+
+    fn index(&self, (x, y): (usize, usize)) -> &<Self as Index<(usize, usize)>>::Output {
+        &self.tiles[x + y * self.size]
+    }
+}
+
+impl IndexMut<(usize, usize)> for BoardState {
+    fn index_mut(
+        &mut self,
+        (x, y): (usize, usize),
+    ) -> &mut <Self as Index<(usize, usize)>>::Output {
+        self.zobrist = None;
+        self.masks = None;
+        &mut self.tiles[x + y * self.size]
+    }
+}
+
+impl Display for BoardState {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), fmt::Error> {
+        self.render().fmt(fmt)
+    }
+}
+
+impl FromStr for BoardState {
+    type Err = anyhow::Error;
+
+    /// Parses the grid drawn by `Display`, with its labels, inferring the
+    /// board size from the column labels and the next player as
+    /// [`from_compact`](Self::from_compact) does. The win length is always
+    /// the board size.
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let header = s.lines().next().unwrap_or("");
+        let size = header.split_whitespace().count();
+        if size == 0 {
+            return Err(anyhow_error!("expected a header of column labels"));
+        }
+
+        // Compare everything but the tiles to an empty board of the same size
+        let empty = BoardState::with_size(size).to_string();
+        let expected = empty.lines().collect::<Vec<_>>();
+        let lines = s.lines().collect::<Vec<_>>();
+        if lines.len() != expected.len() {
+            return Err(anyhow_error!(
+                "expected {} lines for a {} by {} board, got {}",
+                expected.len(),
+                size,
+                size,
+                lines.len()
+            ));
+        }
+
+        let label_width = (size - 1).to_string().len();
+        let mut compact = String::with_capacity(size * size);
+
+        for (i, (line, expected)) in lines.iter().zip(expected).enumerate() {
+            // Rows follow the header and a blank line, alternating with
+            // separators
+            if i < 2 || i % 2 == 1 {
+                if *line != expected {
+                    return Err(anyhow_error!("expected {:?}, got {:?}", expected, line));
+                }
+                continue;
+            }
+
+            let y = (i - 2) / 2;
+            let tiles = line
+                .strip_prefix(&format!("{:>1$} ", y, label_width))
+                .ok_or_else(|| anyhow_error!("expected row {} to start with its label", y))?
+                .split('|')
+                .collect::<Vec<_>>();
+            if tiles.len() != size {
+                return Err(anyhow_error!(
+                    "expected {} tiles in row {}, got {}",
+                    size,
+                    y,
+                    tiles.len()
+                ));
+            }
+
+            for tile in tiles {
+                // Tiles are right-aligned under their labels
+                match tile.strip_prefix(&" ".repeat(label_width - 1)) {
+                    Some("X") => compact.push('X'),
+                    Some("O") => compact.push('O'),
+                    Some("Z") => compact.push('Z'),
+                    Some(" ") => compact.push('.'),
+                    _ => return Err(anyhow_error!("unexpected tile {:?} in row {}", tile, y)),
+                }
+            }
+        }
+
+        BoardState::from_compact(&compact)
+    }
+}
+
+#[cfg(feature = "color")]
+const RED: &str = "\x1b[31m";
+#[cfg(feature = "color")]
+const BLUE: &str = "\x1b[34m";
+#[cfg(feature = "color")]
+const GREEN: &str = "\x1b[32m";
+#[cfg(feature = "color")]
+const RESET: &str = "\x1b[0m";
+
+/// The characters a board is drawn with, by default `X`, `O`, and a space,
+/// with the third player always drawn as `Z`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symbols {
+    x: char,
+    o: char,
+    empty: char,
+}
+
+impl Symbols {
+    /// Fails unless the three characters are distinct from each other, and
+    /// from `Z` if a game of `players` has a third player, so that every tile
+    /// can be told apart
+    pub fn new(x: char, o: char, empty: char, players: usize) -> anyhow::Result<Self> {
+        let symbols = [x, o, empty, 'Z'];
+        let symbols = &symbols[..if players > 2 { 4 } else { 3 }];
+        if (1..symbols.len()).any(|i| symbols[..i].contains(&symbols[i])) {
+            return Err(anyhow_error!(
+                "symbols must be distinct from each other{}, got {:?}, {:?}, and {:?}",
+                if players > 2 { " and from 'Z'" } else { "" },
+                x,
+                o,
+                empty
+            ));
+        }
+
+        Ok(Symbols { x, o, empty })
+    }
+
+    /// The character to draw `tile` with
+    pub fn get(&self, tile: TileState) -> char {
+        match tile {
+            TileState::X => self.x,
+            TileState::O => self.o,
+            TileState::Z => 'Z',
+            TileState::Empty => self.empty,
+        }
+    }
+}
+
+impl Default for Symbols {
+    fn default() -> Self {
+        Symbols {
+            x: 'X',
+            o: 'O',
+            empty: ' ',
+        }
+    }
+}
+
+/// Displays a board with configurable options; see [`BoardState::render`]
+#[derive(Debug, Clone, Copy)]
+pub struct Render<'a> {
+    board: &'a BoardState,
+    labels: bool,
+    unicode: bool,
+    highlight: bool,
+    symbols: Symbols,
+    #[cfg(feature = "color")]
+    color: bool,
+}
+
+impl Render<'_> {
+    /// Whether to label the rows and columns with their indices, as is the
+    /// default
+    pub fn with_labels(mut self, labels: bool) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// Whether to draw the grid with box-drawing characters instead of ASCII
+    pub fn with_unicode(mut self, unicode: bool) -> Self {
+        self.unicode = unicode;
+        self
+    }
+
+    /// Whether to bracket the tiles of the winning line, if there is one
+    pub fn with_highlight(mut self, highlight: bool) -> Self {
+        self.highlight = highlight;
+        self
+    }
+
+    /// Draws the tiles with `symbols` in place of `X`, `O`, and a space
+    pub fn with_symbols(mut self, symbols: Symbols) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Whether to draw X in red, O in blue, and Z in green using ANSI escape
+    /// codes
+    #[cfg(feature = "color")]
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// The width of the widest label, or 0 without labels
+    fn label_width(&self) -> usize {
+        if self.labels {
+            (self.board.size - 1).to_string().len()
+        } else {
+            0
+        }
+    }
+
+    /// The tiles to highlight, if highlighting is on and the game has been won
+    fn highlighted(&self) -> Option<Vec<(usize, usize)>> {
+        if self.highlight {
+            self.board.winning_line().map(|(_, line)| line)
+        } else {
+            None
+        }
+    }
+
+    /// Writes `width` spaces
+    fn fmt_padding(fmt: &mut Formatter, width: usize) -> Result<(), fmt::Error> {
+        write!(fmt, "{:1$}", "", width)
+    }
+
+    fn fmt_tile(&self, fmt: &mut Formatter, coords: (usize, usize)) -> Result<(), fmt::Error> {
+        let tile = self.board[coords];
+        let symbol = self.symbols.get(tile);
+
+        #[cfg(feature = "color")]
+        if self.color {
+            match tile {
+                TileState::X => return write!(fmt, "{}{}{}", RED, symbol, RESET),
+                TileState::O => return write!(fmt, "{}{}{}", BLUE, symbol, RESET),
+                TileState::Z => return write!(fmt, "{}{}{}", GREEN, symbol, RESET),
+                TileState::Empty => {}
+            }
+        }
+
+        write!(fmt, "{}", symbol)
+    }
+
+    /// Writes a tile between brackets if it is in `line`, or between spaces
+    /// otherwise
+    fn fmt_bracketed(
+        &self,
+        fmt: &mut Formatter,
+        coords: (usize, usize),
+        line: &[(usize, usize)],
+    ) -> Result<(), fmt::Error> {
+        let (open, close) = if line.contains(&coords) {
+            ('[', ']')
+        } else {
+            (' ', ' ')
+        };
+        write!(fmt, "{}", open)?;
+        self.fmt_tile(fmt, coords)?;
+        write!(fmt, "{}", close)
+    }
+
+    fn fmt_ascii(&self, fmt: &mut Formatter) -> Result<(), fmt::Error> {
+        let size = self.board.size;
+        let label_width = self.label_width();
+        let highlighted = self.highlighted();
+        // Widen the tiles to fit their labels above them, and any brackets
+        let tile_width = if highlighted.is_some() {
+            label_width.max(3)
+        } else {
+            label_width.max(1)
+        };
+        let indent = if self.labels { label_width + 1 } else { 0 };
+
+        if self.labels {
+            Self::fmt_padding(fmt, indent)?;
+
+            for x in 0..size {
+                if highlighted.is_some() {
+                    write!(fmt, "{:^1$}", x, tile_width)?;
+                } else {
+                    write!(fmt, "{:>1$}", x, tile_width)?;
+                }
+
+                if x != size - 1 {
+                    write!(fmt, " ")?;
+                }
+            }
+
+            writeln!(fmt)?;
+            writeln!(fmt)?;
+        }
+
+        for y in 0..size {
+            if self.labels {
+                write!(fmt, "{:>1$} ", y, label_width)?;
+            }
+
+            for x in 0..size {
+                match &highlighted {
+                    Some(line) => {
+                        Self::fmt_padding(fmt, tile_width - 3)?;
+                        self.fmt_bracketed(fmt, (x, y), line)?;
+                    }
+                    None => {
+                        Self::fmt_padding(fmt, tile_width - 1)?;
+                        self.fmt_tile(fmt, (x, y))?;
+                    }
+                }
+
+                if x != size - 1 {
+                    write!(fmt, "|")?;
+                }
+            }
+
+            if y != size - 1 {
+                writeln!(fmt)?;
+
+                Self::fmt_padding(fmt, indent)?;
+
+                for x in 0..size {
+                    write!(fmt, "{}", "-".repeat(tile_width))?;
+
+                    if x != size - 1 {
+                        write!(fmt, "+")?;
+                    }
+                }
+
+                writeln!(fmt)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn fmt_unicode(&self, fmt: &mut Formatter) -> Result<(), fmt::Error> {
+        let size = self.board.size;
+        let label_width = self.label_width();
+        let indent = if self.labels { label_width + 1 } else { 0 };
+        let highlighted = self.highlighted().unwrap_or_default();
+
+        if self.labels {
+            let header = (0..size).map(|x| format!(" {:^3}", x)).collect::<String>();
+            Self::fmt_padding(fmt, indent)?;
+            writeln!(fmt, "{}", header.trim_end())?;
+        }
+
+        self.fmt_border(fmt, indent, ['┌', '┬', '┐'])?;
+        writeln!(fmt)?;
+
+        for y in 0..size {
+            if self.labels {
+                write!(fmt, "{:>1$} ", y, label_width)?;
+            }
+
+            write!(fmt, "│")?;
+
+            for x in 0..size {
+                self.fmt_bracketed(fmt, (x, y), &highlighted)?;
+                write!(fmt, "│")?;
+            }
+
+            writeln!(fmt)?;
+
+            if y != size - 1 {
+                self.fmt_border(fmt, indent, ['├', '┼', '┤'])?;
+                writeln!(fmt)?;
+            }
+        }
+
+        self.fmt_border(fmt, indent, ['└', '┴', '┘'])
+    }
+
+    /// Draws a horizontal box-drawing border from `left` to `right`, crossing
+    /// each inner vertical line with `junction`
+    fn fmt_border(
+        &self,
+        fmt: &mut Formatter,
+        indent: usize,
+        [left, junction, right]: [char; 3],
+    ) -> Result<(), fmt::Error> {
+        Self::fmt_padding(fmt, indent)?;
+        write!(fmt, "{}", left)?;
+
+        for x in 0..self.board.size {
+            write!(fmt, "───")?;
+
+            if x != self.board.size - 1 {
+                write!(fmt, "{}", junction)?;
+            }
+        }
+
+        write!(fmt, "{}", right)
+    }
+}
+
+impl Display for Render<'_> {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), fmt::Error> {
+        if self.unicode {
+            self.fmt_unicode(fmt)
+        } else {
+            self.fmt_ascii(fmt)
+        }
+    }
+}
+
+/// A read-only view of a board, for spectators and interfaces that must not
+/// change the game they show; see [`BoardState::view`]
+///
+/// There is no way to play on or edit a board through its view:
+///
+/// ```compile_fail
+/// let board = tic_tac_toe::state::BoardState::new();
+/// board.view().play((0, 0));
+/// ```
+///
+/// ```compile_fail
+/// use tic_tac_toe::state::{BoardState, TileState};
+/// let board = BoardState::new();
+/// board.view().set((0, 0), TileState::X);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GameView<'a> {
+    board: &'a BoardState,
+}
+
+impl<'a> GameView<'a> {
+    pub fn render(&self) -> Render<'a> {
+        self.board.render()
+    }
+
+    pub fn tile(&self, coords: (usize, usize)) -> TileState {
+        self.board[coords]
+    }
+
+    pub fn size(&self) -> usize {
+        self.board.size()
+    }
+
+    /// The player whose turn it is
+    pub fn next(&self) -> Player {
+        self.board.next()
+    }
+
+    pub fn status(&self) -> GameResult {
+        self.board.status()
+    }
+
+    /// The winner and the tiles of their line, if anyone has won
+    pub fn winning_line(&self) -> Option<(Player, Vec<(usize, usize)>)> {
+        self.board.winning_line()
+    }
+
+    pub fn move_count(&self) -> usize {
+        self.board.move_count()
+    }
+
+    pub fn history(&self) -> &'a [(usize, usize)] {
+        self.board.history()
+    }
+}
+
+impl Display for GameView<'_> {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), fmt::Error> {
+        self.board.fmt(fmt)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    mod tile_state {
+        use super::*;
+
+        #[test]
+        fn display() {
+            assert_eq!(format!("{}", TileState::X), "X");
+            assert_eq!(format!("{}", TileState::O), "O");
+            assert_eq!(format!("{}", TileState::Z), "Z");
+            assert_eq!(format!("{}", TileState::Empty), " ");
+        }
+
+        #[test]
+        fn try_from() {
+            use TileState::*;
+            for &tile in [X, O, Z, Empty].iter() {
+                let c = tile.to_string().chars().next().unwrap();
+                assert_eq!(TileState::try_from(c).unwrap(), tile);
+            }
+            assert_eq!(TileState::try_from('x').unwrap(), X);
+            assert_eq!(TileState::try_from('o').unwrap(), O);
+            assert_eq!(TileState::try_from('.').unwrap(), Empty);
+            for &c in ['0', '-', '_', 'Y', '\n'].iter() {
+                assert!(TileState::try_from(c).is_err());
+            }
+            assert_eq!(
+                TileState::try_from('?').unwrap_err().to_string(),
+                "unknown tile '?'"
+            );
+        }
+    }
+
+    mod player {
+        use super::*;
+
+        #[test]
+        fn from_str() {
+            for &player in Player::ALL.iter() {
+                assert_eq!(player.to_string().parse::<Player>().unwrap(), player);
+            }
+            assert_eq!("x".parse::<Player>().unwrap(), Player::X);
+            assert_eq!("o".parse::<Player>().unwrap(), Player::O);
+            for s in ["", "XO", " X", "Y", "0", "player"].iter() {
+                assert!(s.parse::<Player>().is_err());
+            }
+            assert_eq!(
+                "Q".parse::<Player>().unwrap_err().to_string(),
+                "unknown player \"Q\""
+            );
+        }
+
+        #[test]
+        fn opponent() {
+            assert_eq!(Player::X.opponent(), Player::O);
+            assert_eq!(Player::X.opponent().opponent(), Player::X);
+        }
+
+        #[test]
+        fn next_player() {
+            assert_eq!(Player::X.next_player(2), Player::O);
+            assert_eq!(Player::O.next_player(2), Player::X);
+            assert_eq!(Player::X.next_player(3), Player::O);
+            assert_eq!(Player::O.next_player(3), Player::Z);
+            assert_eq!(Player::Z.next_player(3), Player::X);
+            for &player in Player::ALL.iter() {
+                assert_eq!(player.next_player(3).previous_player(3), player);
+            }
+            assert_eq!(Player::X.previous_player(2), Player::O);
+        }
+    }
+
+    mod symbols {
+        use super::*;
+
+        #[test]
+        fn new() {
+            let symbols = Symbols::new('☆', '♛', '.', 2).unwrap();
+            assert_eq!(symbols.get(TileState::X), '☆');
+            assert_eq!(symbols.get(TileState::O), '♛');
+            assert_eq!(symbols.get(TileState::Empty), '.');
+            assert!(Symbols::new('X', 'X', ' ', 2).is_err());
+            assert!(Symbols::new('X', 'O', 'O', 2).is_err());
+            assert!(Symbols::new('X', 'O', 'X', 2).is_err());
+            assert_eq!(
+                Symbols::new('Z', 'O', ' ', 2).unwrap().get(TileState::X),
+                'Z'
+            );
+            assert!(Symbols::new('Z', 'O', ' ', 3).is_err());
+            assert!(Symbols::new('X', 'O', 'Z', 3).is_err());
+        }
+
+        #[test]
+        fn default() {
+            let symbols = Symbols::default();
+            assert_eq!(symbols, Symbols::new('X', 'O', ' ', 3).unwrap());
+            for &tile in [TileState::X, TileState::O, TileState::Empty].iter() {
+                assert_eq!(symbols.get(tile).to_string(), tile.to_string());
+            }
+        }
+    }
+
+    mod board_state {
+        use std::collections::HashSet;
+
+        use proptest::{collection, prelude::*};
+
+        use super::*;
+
+        #[test]
+        fn display() {
+            insta::assert_snapshot!(format!("{}", BoardState::new().play((1, 1)).unwrap()), @r###"
+              0 1 2
+
+            0  | | 
+              -+-+-
+            1  |X| 
+              -+-+-
+            2  | | 
+            "###);
+        }
+
+        #[test]
+        fn display_unicode() {
+            let mut board = BoardState::new();
+            board.play((1, 1)).unwrap().play((2, 0)).unwrap();
+            insta::assert_snapshot!(format!("{}", board.display_unicode()), @r###"
+                0   1   2
+              ┌───┬───┬───┐
+            0 │   │   │ O │
+              ├───┼───┼───┤
+            1 │   │ X │   │
+              ├───┼───┼───┤
+            2 │   │   │   │
+              └───┴───┴───┘
+            "###);
+        }
+
+        #[test]
+        fn display_unicode_sizes() {
+            assert_eq!(
+                BoardState::with_size(1).display_unicode().to_string(),
+                "    0\n  ┌───┐\n0 │   │\n  └───┘"
+            );
+            let board = BoardState::with_size(4).display_unicode().to_string();
+            assert_eq!(board.lines().count(), 10);
+            assert_eq!(board.matches('┼').count(), 9);
+            assert_eq!(board.matches('┬').count(), 3);
+            assert_eq!(board.matches('├').count(), 3);
+        }
+
+        /// Asserts that every line of `render` has the same width
+        fn assert_aligned(render: &str) {
+            let widths = render
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(|line| line.chars().count())
+                .collect::<Vec<_>>();
+            assert!(widths.iter().all(|&width| width == widths[0]), "{}", render);
+        }
+
+        #[test]
+        fn render() {
+            let mut board = BoardState::new();
+            board.play((1, 1)).unwrap().play((0, 2)).unwrap();
+            assert_eq!(board.render().to_string(), board.to_string());
+            assert_eq!(
+                board.render().with_labels(true).to_string(),
+                board.to_string()
+            );
+            assert_aligned(&board.to_string());
+            insta::assert_snapshot!(board.render().with_labels(false).to_string(), @r###"
+             | | 
+            -+-+-
+             |X| 
+            -+-+-
+            O| | 
+            "###);
+            insta::assert_snapshot!(board.render().with_labels(false).with_unicode(true).to_string(), @r###"
+            ┌───┬───┬───┐
+            │   │   │   │
+            ├───┼───┼───┤
+            │   │ X │   │
+            ├───┼───┼───┤
+            │ O │   │   │
+            └───┴───┴───┘
+            "###);
+        }
+
+        #[test]
+        fn render_highlight() {
+            let mut board = BoardState::new();
+            for &coords in [(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)].iter() {
+                board.play(coords).unwrap();
+            }
+            insta::assert_snapshot!(board.render().with_highlight(true).to_string(), @r###"
+               0   1   2 
+
+            0 [X]|[X]|[X]
+              ---+---+---
+            1  O | O |   
+              ---+---+---
+            2    |   |   
+            "###);
+            insta::assert_snapshot!(board.display_unicode().with_highlight(true).to_string(), @r###"
+                0   1   2
+              ┌───┬───┬───┐
+            0 │[X]│[X]│[X]│
+              ├───┼───┼───┤
+            1 │ O │ O │   │
+              ├───┼───┼───┤
+            2 │   │   │   │
+              └───┴───┴───┘
+            "###);
+            assert_eq!(board.render().to_string(), board.to_string());
+
+            // Nothing is highlighted until someone wins
+            board.undo().unwrap();
+            assert_eq!(
+                board.render().with_highlight(true).to_string(),
+                board.to_string()
+            );
+        }
+
+        #[test]
+        fn render_symbols() {
+            let board = BoardState::from_moves(&[(0, 0), (1, 1), (2, 0)]).unwrap();
+            let symbols = Symbols::new('●', '○', '·', 2).unwrap();
+            insta::assert_snapshot!(board.render().with_symbols(symbols).to_string(), @r###"
+              0 1 2
+
+            0 ●|·|●
+              -+-+-
+            1 ·|○|·
+              -+-+-
+            2 ·|·|·
+            "###);
+            insta::assert_snapshot!(board.display_unicode().with_symbols(symbols).to_string(), @r###"
+                0   1   2
+              ┌───┬───┬───┐
+            0 │ ● │ · │ ● │
+              ├───┼───┼───┤
+            1 │ · │ ○ │ · │
+              ├───┼───┼───┤
+            2 │ · │ · │ · │
+              └───┴───┴───┘
+            "###);
+            assert_eq!(
+                board.render().with_symbols(Symbols::default()).to_string(),
+                board.to_string()
+            );
+        }
+
+        #[test]
+        fn render_large() {
+            let mut board = BoardState::with_size(10);
+            board.play((9, 9)).unwrap();
+            let render = board.to_string();
+            assert_aligned(&render);
+            assert!(render.starts_with("  0 1 2 3 4 5 6 7 8 9\n"));
+            assert!(render.ends_with("\n9  | | | | | | | | |X"));
+            let render = board.display_unicode().to_string();
+            // The header is trimmed, so only the grid lines up
+            assert_aligned(render.split_once('\n').unwrap().1);
+
+            let mut board = BoardState::with_size(12);
+            board.play((11, 10)).unwrap();
+            let render = board.to_string();
+            assert_aligned(&render);
+            assert!(render.starts_with("    0  1  2  3  4  5  6  7  8  9 10 11\n"));
+            assert!(render.contains("\n 9   |"));
+            assert!(render.contains("\n10   |  |  |  |  |  |  |  |  |  |  | X\n"));
+            assert!(render.contains("\n   --+--+"));
+            let render = board.display_unicode().to_string();
+            assert!(render.starts_with("     0   1   2"));
+            assert_aligned(render.split_once('\n').unwrap().1);
+            assert_aligned(&board.render().with_labels(false).to_string());
+        }
+
+        #[cfg(feature = "color")]
+        #[test]
+        fn display_colored() {
+            let mut board = BoardState::new();
+            board.play((1, 1)).unwrap().play((0, 2)).unwrap();
+
+            let colored = board.render().with_color(true).to_string();
+            assert!(colored.contains("|\x1b[31mX\x1b[0m|"));
+            assert!(colored.contains("2 \x1b[34mO\x1b[0m|"));
+            assert_eq!(colored.matches('\x1b').count(), 4);
+            assert_eq!(
+                board.render().with_color(false).to_string(),
+                board.to_string()
+            );
+
+            assert!(color_allowed(None));
+            assert!(color_allowed(Some("".as_ref())));
+            assert!(!color_allowed(Some("1".as_ref())));
+        }
+
+        #[test]
+        fn play() {
+            assert!(BoardState::new().play((3, 0)).is_err());
+            assert!(BoardState::new().play((0, 3)).is_err());
+            assert!(BoardState::new().play((2, 2)).is_ok());
+            assert!(BoardState::new()
+                .play((1, 1))
+                .unwrap()
+                .play((1, 1))
+                .is_err());
+        }
+
+        #[test]
+        fn play_errors() {
+            let mut board = BoardState::new();
+            assert_eq!(
+                board.play((3, 1)).unwrap_err(),
+                GameError::OutOfBounds { x: 3, y: 1 }
+            );
+            board.play((1, 1)).unwrap();
+            assert!(matches!(
+                board.play((1, 1)),
+                Err(GameError::AlreadyPlayed { x: 1, y: 1 })
+            ));
+            assert_eq!(
+                board.play((1, 1)).unwrap_err().to_string(),
+                "(1, 1) has already been played"
+            );
+
+            let mut board = BoardState::new();
+            assert_eq!(board.undo(), Err(GameError::NothingToUndo));
+            assert_eq!(board.redo(), Err(GameError::NothingToRedo));
+        }
+
+        #[test]
+        fn from_moves() {
+            let moves = [(1, 1), (0, 0), (2, 0), (0, 2)];
+            let board = BoardState::from_moves(&moves).unwrap();
+            assert_eq!(board.to_compact(), "O.X.X.O..");
+            assert_eq!(board.next(), Player::X);
+            assert_eq!(board.history(), &moves);
+            assert_eq!(BoardState::from_moves(&[]).unwrap(), BoardState::new());
+
+            let won = [(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)];
+            assert_eq!(BoardState::from_moves(&won).unwrap().won(), Some(Player::X));
+        }
+
+        #[test]
+        fn from_moves_errors() {
+            let error = BoardState::from_moves(&[(1, 1), (0, 0), (1, 1)]).unwrap_err();
+            assert_eq!(
+                error.downcast_ref::<GameError>(),
+                Some(&GameError::AlreadyPlayed { x: 1, y: 1 })
+            );
+
+            let error = BoardState::from_moves(&[(0, 3)]).unwrap_err();
+            assert_eq!(
+                error.downcast_ref::<GameError>(),
+                Some(&GameError::OutOfBounds { x: 0, y: 3 })
+            );
+
+            let error = BoardState::from_moves(&[(0, 0), (0, 1), (1, 0), (1, 1), (2, 0), (2, 1)])
+                .unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                "(2, 1) was played after X had already won"
+            );
+        }
+
+        #[test]
+        fn infer_next() {
+            let mut board = BoardState::new();
+            assert_eq!(board.infer_next().unwrap(), Player::X);
+            board[(1, 1)] = TileState::X;
+            assert_eq!(board.infer_next().unwrap(), Player::O);
+            board[(0, 0)] = TileState::O;
+            assert_eq!(board.infer_next().unwrap(), Player::X);
+            board[(2, 2)] = TileState::X;
+            board[(2, 0)] = TileState::X;
+            assert_eq!(
+                board.infer_next().unwrap_err().to_string(),
+                "3 X and 1 O cannot arise from alternating play"
+            );
+
+            let mut board = BoardState::new();
+            board[(0, 0)] = TileState::O;
+            assert!(board.infer_next().is_err());
+        }
+
+        #[test]
+        fn is_valid() {
+            assert!(BoardState::new().is_valid());
+            let mut board = BoardState::from_moves(&[(1, 1), (0, 0), (2, 0)]).unwrap();
+            assert!(board.is_valid());
+            assert!(
+                BoardState::from_moves(&[(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)])
+                    .unwrap()
+                    .is_valid()
+            );
+
+            board[(2, 2)] = TileState::X;
+            assert!(!board.is_valid());
+
+            // X's top row and O's middle row could not both have been played
+            let board = BoardState::from_compact("XXXOOO...").unwrap();
+            assert!(!board.is_valid());
+
+            let board = BoardState {
+                next: Player::O,
+                ..BoardState::new()
+            };
+            assert!(!board.is_valid());
+        }
+
+        #[cfg(feature = "serde")]
+        #[test]
+        fn deserialize_checked() {
+            let board = BoardState::from_moves(&[(1, 1), (0, 0)]).unwrap();
+            let json = serde_json::to_value(&board).unwrap();
+            let parse = |edit: &dyn Fn(&mut serde_json::Value)| {
+                let mut json = json.clone();
+                edit(&mut json);
+                serde_json::from_value::<BoardState>(json)
+            };
+            assert_eq!(parse(&|_| {}).unwrap(), board);
+
+            assert!(parse(&|json| {
+                json["tiles"].as_array_mut().unwrap().pop();
+            })
+            .is_err());
+            assert!(parse(&|json| json["size"] = 0.into()).is_err());
+            // Squaring this size overflows, so it must be refused before then
+            let error = parse(&|json| {
+                json["size"] = 4_294_967_296_u64.into();
+                json["tiles"] = serde_json::json!([]);
+            })
+            .unwrap_err();
+            assert!(
+                error.to_string().contains("not between 1 and 26"),
+                "{}",
+                error
+            );
+            let error = parse(&|json| {
+                json["size"] = 27.into();
+                json["tiles"] = serde_json::json!(vec!["Empty"; 27 * 27]);
+            })
+            .unwrap_err();
+            assert!(error.to_string().contains("size 27"), "{}", error);
+            assert!(parse(&|json| json["win_length"] = 4.into()).is_err());
+            assert!(parse(&|json| json["players"] = 3.into()).is_err());
+            assert!(parse(&|json| json["next"] = "Z".into()).is_err());
+            assert!(parse(&|json| json["history"][1] = serde_json::json!([5, 0])).is_err());
+            assert!(parse(&|json| json["history"][1] = serde_json::json!([2, 2])).is_err());
+            assert!(parse(&|json| {
+                json["history"].as_array_mut().unwrap().pop();
+            })
+            .is_err());
+        }
+
+        #[test]
+        fn next() {
+            assert_eq!(BoardState::new().next(), Player::X);
+            assert_eq!(BoardState::new().play((0, 0)).unwrap().next(), Player::O);
+        }
+
+        #[test]
+        fn goto_move() {
+            let moves = [(1, 1), (0, 0), (2, 2), (0, 2), (0, 1)];
+            let mut board = BoardState::from_moves(&moves).unwrap();
+            let end = board.clone();
+
+            board.goto_move(0).unwrap();
+            assert_eq!(board, BoardState::new());
+            assert_eq!(board.history(), &[]);
+
+            board.goto_move(2).unwrap();
+            assert_eq!(board, BoardState::from_moves(&moves[..2]).unwrap());
+            assert_eq!(board.next(), Player::X);
+
+            board.goto_move(5).unwrap();
+            assert_eq!(board, end);
+            assert_eq!(board.history(), end.history());
+
+            board.goto_move(3).unwrap();
+            assert_eq!(board.history(), &moves[..3]);
+            board.goto_move(3).unwrap();
+            assert_eq!(board.history(), &moves[..3]);
+
+            assert_eq!(
+                board.goto_move(6).unwrap_err().to_string(),
+                "can't go to move 6 of a game with 5 moves"
+            );
+            assert_eq!(board.history(), &moves[..3]);
+
+            // Playing a new move discards the undone ones
+            board.play((2, 0)).unwrap();
+            assert!(board.goto_move(5).is_err());
+            board.goto_move(4).unwrap();
+            assert_eq!(board.history().last(), Some(&(2, 0)));
+        }
+
+        #[test]
+        fn first_player() {
+            let mut board = BoardState::new();
+            assert_eq!(board.first_player(), Player::X);
+            board.set_first_player(Player::O);
+            assert_eq!(board.first_player(), Player::O);
+            assert_eq!(board.next(), Player::O);
+            assert!(board.is_valid());
+
+            board.play((0, 0)).unwrap();
+            assert_eq!(board[(0, 0)], TileState::O);
+            assert_eq!(board.next(), Player::X);
+            assert!(board.is_valid());
+            board.undo().unwrap();
+            assert_eq!(board.next(), Player::O);
+
+            for &coords in [(1, 1), (0, 0), (1, 0), (0, 1), (1, 2)].iter() {
+                board.play(coords).unwrap();
+            }
+            assert_eq!(board.first_player(), Player::O);
+            assert_eq!(board.status(), GameResult::Win(Player::O));
+            assert!(board.is_valid());
+            assert_eq!(board.infer_next().unwrap(), Player::X);
+
+            // Two more O than X is impossible whoever started
+            board[(2, 2)] = TileState::O;
+            assert!(board.infer_next().is_err());
+        }
+
+        #[test]
+        fn three_players() {
+            let mut board = BoardState::with_players(4, 3, 3);
+            assert_eq!(board.players(), 3);
+            let mut turns = Vec::new();
+            for &coords in [(0, 0), (1, 0), (2, 1), (3, 0)].iter() {
+                turns.push(board.next());
+                board.play(coords).unwrap();
+            }
+            assert_eq!(turns, vec![Player::X, Player::O, Player::Z, Player::X]);
+            assert_eq!(board[(2, 1)], TileState::Z);
+            assert_eq!(board.next(), Player::O);
+            assert!(board.is_valid());
+
+            board.undo().unwrap();
+            assert_eq!(board.next(), Player::X);
+            board.redo().unwrap();
+
+            for &coords in [(0, 1), (2, 2), (0, 3), (3, 2)].iter() {
+                board.play(coords).unwrap();
+            }
+            assert_eq!(board.status(), GameResult::InProgress);
+            board.play((2, 3)).unwrap();
+            assert_eq!(board.status(), GameResult::Win(Player::Z));
+            assert_eq!(
+                board.winning_line(),
+                Some((Player::Z, vec![(2, 1), (2, 2), (2, 3)]))
+            );
+            assert!(board.is_valid());
+
+            // The compact encoding doesn't record the win length
+            let parsed = BoardState::from_compact(&board.to_compact()).unwrap();
+            assert_eq!(parsed.players(), 3);
+            assert_eq!(parsed.next(), Player::X);
+        }
+
+        #[test]
+        fn three_players_infer_next() {
+            use TileState::*;
+            let mut tiles = vec![Empty; 16];
+            tiles[0] = X;
+            tiles[1] = O;
+            let board = BoardState::from_tiles(tiles.clone(), Player::Z).unwrap();
+            assert_eq!(board.players(), 3);
+            assert_eq!(board.first_player(), Player::X);
+
+            tiles[2] = Z;
+            let board = BoardState::from_tiles(tiles.clone(), Player::X).unwrap();
+            assert_eq!(board.infer_next().unwrap(), Player::X);
+            let board = BoardState::from_tiles(tiles.clone(), Player::O).unwrap();
+            assert_eq!(board.first_player(), Player::O);
+
+            tiles[3] = Z;
+            let board = BoardState {
+                tiles,
+                ..BoardState::with_players(4, 4, 3)
+            };
+            assert_eq!(
+                board.infer_next().unwrap_err().to_string(),
+                "1 X, 1 O and 2 Z cannot arise from alternating play"
+            );
+            assert!(BoardState::from_compact("XOZ......").is_err());
+            assert!(BoardState::from_tiles(vec![Empty; 9], Player::Z).is_err());
+        }
+
+        #[test]
+        #[should_panic]
+        fn three_players_too_small() {
+            BoardState::with_players(3, 3, 3);
+        }
+
+        #[test]
+        fn set() {
+            let mut board = BoardState::new();
+            board.set((0, 0), TileState::O).unwrap();
+            board.set((2, 1), TileState::O).unwrap();
+            board.set((1, 1), TileState::X).unwrap();
+            assert_eq!(board.to_compact(), "O...XO...");
+            assert_eq!(board.next(), Player::X);
+            assert_eq!(board.history(), &[]);
+
+            board.set((0, 0), TileState::Empty).unwrap();
+            assert_eq!(board[(0, 0)], TileState::Empty);
+            assert_eq!(
+                board.set((3, 0), TileState::X).unwrap_err().to_string(),
+                "(3, 0) is out of bounds"
+            );
+            assert!(board.set((0, 3), TileState::X).is_err());
+            assert_eq!(board.to_compact(), "....XO...");
+        }
+
+        #[test]
+        fn undo() {
+            let mut board = BoardState::new();
+            assert!(board.undo().is_err());
+
+            board.play((1, 1)).unwrap();
+            board.undo().unwrap();
+            assert_eq!(board, BoardState::new());
+            assert_eq!(board.history(), &[]);
+            assert!(board.undo().is_err());
+
+            for &coords in [(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)].iter() {
+                board.play(coords).unwrap();
+            }
+            assert_eq!(board.won(), Some(Player::X));
+            board.undo().unwrap();
+            assert_eq!(board.won(), None);
+            assert_eq!(board.next(), Player::X);
+            assert_eq!(board[(2, 0)], TileState::Empty);
+            assert_eq!(board.history(), &[(0, 0), (0, 1), (1, 0), (1, 1)]);
+            board.play((2, 0)).unwrap();
+            assert_eq!(board.won(), Some(Player::X));
+        }
+
+        #[test]
+        fn redo() {
+            let mut board = BoardState::new();
+            assert!(board.redo().is_err());
+
+            board.play((0, 0)).unwrap().play((1, 1)).unwrap();
+            let played = board.clone();
+            board.undo().unwrap();
+            board.redo().unwrap();
+            assert_eq!(board, played);
+            assert_eq!(board.history(), &[(0, 0), (1, 1)]);
+            assert!(board.redo().is_err());
+
+            board.undo().unwrap();
+            board.undo().unwrap();
+            assert_eq!(board, BoardState::new());
+            board.redo().unwrap();
+            assert_eq!(board[(0, 0)], TileState::X);
+            assert_eq!(board.next(), Player::O);
+            board.undo().unwrap();
+            board.redo().unwrap();
+            board.redo().unwrap();
+            assert_eq!(board, played);
+        }
+
+        #[test]
+        fn play_mark() {
+            let mut board = BoardState::new();
+            board.play_mark((0, 0), TileState::O).unwrap();
+            assert_eq!(board[(0, 0)], TileState::O);
+            assert_eq!(board.next(), Player::O);
+            board.play_mark((1, 1), TileState::O).unwrap();
+            assert_eq!(board.next(), Player::X);
+            assert_eq!(board.history(), &[(0, 0), (1, 1)]);
+
+            // Redoing a move places the mark it placed, not the mover's own
+            let played = board.clone();
+            board.undo().unwrap();
+            board.undo().unwrap();
+            board.redo().unwrap();
+            board.redo().unwrap();
+            assert_eq!(board, played);
+
+            assert_eq!(
+                board.play_mark((2, 2), TileState::Z).unwrap_err(),
+                GameError::WrongMark { mark: TileState::Z }
+            );
+            assert_eq!(
+                board
+                    .play_mark((2, 2), TileState::Empty)
+                    .unwrap_err()
+                    .to_string(),
+                "an empty tile can't be placed"
+            );
+            assert_eq!(
+                board.play_mark((3, 0), TileState::X).unwrap_err(),
+                GameError::OutOfBounds { x: 3, y: 0 }
+            );
+            assert_eq!(board, played);
+
+            let mut board = BoardState::with_players(4, 3, 3);
+            board.play_mark((0, 0), TileState::Z).unwrap();
+            assert_eq!(board[(0, 0)], TileState::Z);
+        }
+
+        #[test]
+        fn play_clears_redo() {
+            let mut board = BoardState::new();
+            board.play((0, 0)).unwrap().play((1, 1)).unwrap();
+            board.undo().unwrap();
+            board.play((2, 2)).unwrap();
+            assert!(board.redo().is_err());
+            assert_eq!(board[(1, 1)], TileState::Empty);
+            assert_eq!(board.history(), &[(0, 0), (2, 2)]);
+        }
+
+        #[test]
+        fn history() {
+            let mut board = BoardState::new();
+            board.play((2, 1)).unwrap().play((0, 0)).unwrap();
+            assert!(board.play((0, 0)).is_err());
+            assert_eq!(board.history(), &[(2, 1), (0, 0)]);
+        }
+
+        #[cfg(feature = "serde")]
+        #[test]
+        fn serde() {
+            let mut board = BoardState::new();
+            board
+                .play((1, 1))
+                .unwrap()
+                .play((0, 2))
+                .unwrap()
+                .play((2, 0))
+                .unwrap();
+            let json = serde_json::to_string(&board).unwrap();
+            let parsed: BoardState = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed, board);
+            assert_eq!(parsed.next(), Player::O);
+            assert_eq!(parsed.history(), board.history());
+        }
+
+        #[test]
+        fn default() {
+            assert_eq!(BoardState::default(), BoardState::new());
+            assert_eq!(BoardState::default().size(), BOARD_SIZE);
+            assert_eq!(BoardState::default().history(), &[]);
+        }
+
+        #[test]
+        fn clone() {
+            let mut board = BoardState::from_moves(&[(1, 1), (0, 0)]).unwrap();
+            let original = board.clone();
+            let mut clone = board.clone();
+            assert_eq!(clone, board);
+            assert_eq!(clone.history(), board.history());
+
+            clone.play((2, 2)).unwrap();
+            clone.undo().unwrap();
+            clone.undo().unwrap();
+            assert_eq!(board, original);
+            assert_eq!(board.history(), &[(1, 1), (0, 0)]);
+            assert!(board.redo().is_err());
+
+            board.play((0, 2)).unwrap();
+            assert_eq!(clone.to_compact(), "....X....");
+            assert_eq!(clone[(0, 2)], TileState::Empty);
+        }
+
+        #[test]
+        fn hash() {
+            let boards = [
+                BoardState::new(),
+                BoardState::from_moves(&[(1, 1)]).unwrap(),
+                BoardState::from_moves(&[(0, 0)]).unwrap(),
+                BoardState::from_moves(&[(1, 1), (0, 0)]).unwrap(),
+                BoardState::with_size(4),
+                BoardState::with_win_length(4, 3),
+                // An empty board, but with O to move
+                BoardState {
+                    next: Player::O,
+                    ..BoardState::new()
+                },
+            ];
+            let set = boards.iter().cloned().collect::<HashSet<_>>();
+            assert_eq!(set.len(), boards.len());
+
+            let mut undone = BoardState::from_moves(&[(2, 2)]).unwrap();
+            undone.undo().unwrap();
+            let transposed = BoardState::from_moves(&[(0, 0), (1, 1)]).unwrap();
+            let mut set = HashSet::new();
+            set.insert(BoardState::new());
+            set.insert(undone);
+            set.insert(BoardState::from_moves(&[(1, 1), (0, 0)]).unwrap());
+            set.insert(BoardState::from_compact("....X...O").unwrap());
+            assert_eq!(set.len(), 3);
+            assert!(!set.contains(&transposed));
+        }
+
+        #[test]
+        fn with_size() {
+            use TileState::*;
+            let mut board = BoardState::with_size(4);
+            assert_eq!(board.size(), 4);
+            assert_eq!(board.iter_row(3).count(), 4);
+            for &coords in [(0, 0), (1, 0), (2, 1), (3, 3)].iter() {
+                board.play(coords).unwrap();
+            }
+            assert!(board.play((4, 0)).is_err());
+            assert!(board.play((0, 4)).is_err());
+            assert_eq!(
+                board.iter_row(0).collect::<Vec<_>>(),
+                vec![X, O, Empty, Empty]
+            );
+            assert_eq!(
+                board.iter_col(3).collect::<Vec<_>>(),
+                vec![Empty, Empty, Empty, O]
+            );
+            assert_eq!(
+                board.iter_diag(false).collect::<Vec<_>>(),
+                vec![X, Empty, Empty, O]
+            );
+            assert_eq!(
+                board.iter_diag(true).collect::<Vec<_>>(),
+                vec![Empty, X, Empty, Empty]
+            );
+            assert!(!board.drawn());
+        }
+
+        #[test]
+        #[should_panic]
+        fn with_size_zero() {
+            BoardState::with_size(0);
+        }
+
+        #[test]
+        fn won_4x4() {
+            let mut board = BoardState::with_size(4);
+            for &coords in [(0, 1), (0, 0), (1, 1), (1, 0), (2, 1), (2, 0)].iter() {
+                board.play(coords).unwrap();
+            }
+            assert_eq!(board.won(), None);
+            board.play((3, 1)).unwrap();
+            assert_eq!(board.won(), Some(Player::X));
+
+            let mut board = BoardState::with_size(4);
+            for &coords in [(3, 0), (0, 0), (2, 1), (0, 1), (1, 2), (0, 2)].iter() {
+                board.play(coords).unwrap();
+            }
+            assert_eq!(board.won(), None);
+            board.play((0, 3)).unwrap();
+            assert_eq!(board.won(), Some(Player::X));
+            assert_eq!(
+                board.winning_line(),
+                Some((Player::X, vec![(3, 0), (2, 1), (1, 2), (0, 3)]))
+            );
+        }
+
+        #[test]
+        fn with_win_length() {
+            let board = BoardState::with_win_length(5, 4);
+            assert_eq!(board.size(), 5);
+            assert_eq!(board.win_length(), 4);
+            assert_eq!(BoardState::with_size(5).win_length(), 5);
+        }
+
+        #[test]
+        #[should_panic]
+        fn with_win_length_too_long() {
+            BoardState::with_win_length(3, 4);
+        }
+
+        #[test]
+        fn won_win_length() {
+            let runs: [&[(usize, usize)]; 4] = [
+                &[(1, 2), (2, 2), (3, 2), (4, 2)],
+                &[(4, 0), (4, 1), (4, 2), (4, 3)],
+                &[(1, 0), (2, 1), (3, 2), (4, 3)],
+                &[(3, 1), (2, 2), (1, 3), (0, 4)],
+            ];
+            for run in runs.iter() {
+                let mut board = BoardState::with_win_length(5, 4);
+                for &coords in run[..3].iter() {
+                    board[coords] = TileState::X;
+                }
+                assert_eq!(board.won(), None);
+                board[run[3]] = TileState::X;
+                assert_eq!(board.winning_line(), Some((Player::X, run.to_vec())));
+            }
+
+            let mut board = BoardState::with_win_length(5, 4);
+            for &coords in [(0, 0), (1, 1), (2, 2), (4, 4)].iter() {
+                board[coords] = TileState::O;
+            }
+            assert_eq!(board.won(), None);
+        }
+
+        #[test]
+        fn rotate90() {
+            let board = BoardState::from_moves(&[(0, 0), (1, 0), (2, 2)]).unwrap();
+            let rotated = board.rotate90();
+            assert_eq!(rotated.to_compact(), "..X..OX..");
+            assert_eq!(rotated.history(), &[(2, 0), (2, 1), (0, 2)]);
+            assert_eq!(rotated.next(), board.next());
+
+            assert_ne!(rotated, board);
+            assert_eq!(rotated.rotate90().rotate90().rotate90(), board);
+
+            let mut board = BoardState::with_size(4);
+            board
+                .play((0, 0))
+                .unwrap()
+                .play((3, 1))
+                .unwrap()
+                .play((1, 2))
+                .unwrap();
+            let mut rotated = board.clone();
+            for _ in 0..4 {
+                rotated = rotated.rotate90();
+            }
+            assert_eq!(rotated, board);
+            assert_eq!(rotated.history(), board.history());
+        }
+
+        #[test]
+        fn reflect_horizontal() {
+            let mut board = BoardState::from_moves(&[(0, 0), (1, 0), (2, 1)]).unwrap();
+            board.undo().unwrap();
+            let reflected = board.reflect_horizontal();
+            assert_eq!(reflected.to_compact(), ".OX......");
+            assert_eq!(reflected.reflect_horizontal(), board);
+
+            let mut reflected = reflected;
+            reflected.redo().unwrap();
+            assert_eq!(reflected[(0, 1)], TileState::X);
+        }
+
+        #[test]
+        fn transpose() {
+            let board = BoardState::from_moves(&[(0, 0), (1, 0), (2, 1)]).unwrap();
+            let transposed = board.transpose();
+            assert_eq!(transposed.to_compact(), "X..O...X.");
+            assert_eq!(transposed.transpose(), board);
+            assert_eq!(
+                transposed,
+                board.reflect_horizontal().rotate90().rotate90().rotate90()
+            );
+            assert_eq!(transposed.won(), None);
+        }
+
+        #[test]
+        fn canonical() {
+            let corners = [(0, 0), (2, 0), (2, 2), (0, 2)]
+                .iter()
+                .map(|&coords| BoardState::from_moves(&[coords]).unwrap().canonical())
+                .collect::<Vec<_>>();
+            assert!(corners.iter().all(|board| *board == corners[0]));
+            assert_eq!(corners[0].to_compact(), "........X");
+
+            let board = BoardState::from_moves(&[(0, 0), (1, 0), (2, 2)]).unwrap();
+            let canonical = board.canonical();
+            for equivalent in [
+                board.rotate90(),
+                board.rotate90().rotate90(),
+                board.transpose(),
+                board.reflect_horizontal(),
+                canonical.clone(),
+            ]
+            .iter()
+            {
+                assert_eq!(equivalent.canonical(), canonical);
+            }
+
+            let other = BoardState::from_moves(&[(0, 0), (1, 0), (2, 1)]).unwrap();
+            assert_ne!(other.canonical(), canonical);
+            assert_eq!(BoardState::new().canonical(), BoardState::new());
+        }
+
+        #[test]
+        fn to_compact() {
+            assert_eq!(BoardState::new().to_compact(), ".........");
+            let mut board = BoardState::new();
+            board.play((1, 1)).unwrap().play((2, 0)).unwrap();
+            assert_eq!(board.to_compact(), "..O.X....");
+        }
+
+        #[test]
+        fn from_compact() {
+            let mut board = BoardState::new();
+            assert_eq!(
+                BoardState::from_compact(&board.to_compact()).unwrap(),
+                board
+            );
+            board.play((1, 1)).unwrap();
+            assert_eq!(
+                BoardState::from_compact(&board.to_compact()).unwrap(),
+                board
+            );
+            board.play((2, 0)).unwrap().play((0, 2)).unwrap();
+            let parsed = BoardState::from_compact(&board.to_compact()).unwrap();
+            assert_eq!(parsed, board);
+            assert_eq!(parsed.next(), Player::O);
+
+            let mut board = BoardState::with_size(4);
+            board.play((3, 3)).unwrap();
+            let parsed = BoardState::from_compact("...............X").unwrap();
+            assert_eq!(parsed.size(), 4);
+            assert_eq!(parsed, board);
+        }
+
+        #[test]
+        fn to_notation() {
+            assert_eq!(
+                BoardState::new().to_notation().unwrap(),
+                "size=3 win-length=3 players=2"
+            );
+            let board = BoardState::from_moves(&[(1, 1), (0, 0), (2, 2)]).unwrap();
+            assert_eq!(
+                board.to_notation().unwrap(),
+                "size=3 win-length=3 players=2 1. X:b2 O:a1 2. X:c3"
+            );
+
+            let mut board = BoardState::with_players(4, 3, 3);
+            board.set_first_player(Player::O);
+            for &coords in [(0, 0), (1, 0), (3, 3), (0, 1)].iter() {
+                board.play(coords).unwrap();
+            }
+            assert_eq!(
+                board.to_notation().unwrap(),
+                "size=4 win-length=3 players=3 1. O:a1 Z:b1 X:d4 2. O:a2"
+            );
+
+            let mut board = BoardState::with_win_length(30, 3);
+            board.play((25, 0)).unwrap();
+            assert!(board.to_notation().unwrap().ends_with(" 1. X:z1"));
+            board.play((26, 0)).unwrap();
+            assert!(board.to_notation().is_err());
+
+            let mut board = BoardState::new();
+            board.play((1, 1)).unwrap();
+            board.play_mark((0, 0), TileState::X).unwrap();
+            assert_eq!(
+                board.to_notation().unwrap(),
+                "size=3 win-length=3 players=2 1. X:b2 O:a1=X"
+            );
+        }
+
+        #[test]
+        fn from_notation() {
+            let board = BoardState::from_moves(&[(1, 1), (0, 0), (2, 2), (0, 2)]).unwrap();
+            let parsed = BoardState::from_notation(&board.to_notation().unwrap()).unwrap();
+            assert_eq!(parsed, board);
+            assert_eq!(parsed.history(), board.history());
+            assert_eq!(BoardState::from_notation("").unwrap(), BoardState::new());
+            assert_eq!(
+                BoardState::from_notation(" 1.  X:B2\nO:a1 2. X:c3 ").unwrap(),
+                BoardState::from_moves(&[(1, 1), (0, 0), (2, 2)]).unwrap()
+            );
+
+            let parsed = BoardState::from_notation("1. O:b2 X:a1").unwrap();
+            assert_eq!(parsed.first_player(), Player::O);
+            assert_eq!(parsed.next(), Player::O);
+            assert_eq!(parsed[(1, 1)], TileState::O);
+
+            let mut board = BoardState::with_players(5, 4, 3);
+            for &coords in [(0, 0), (4, 4), (2, 2), (1, 0)].iter() {
+                board.play(coords).unwrap();
+            }
+            let parsed = BoardState::from_notation(&board.to_notation().unwrap()).unwrap();
+            assert_eq!(parsed, board);
+            let parsed = BoardState::from_notation("size=4 players=3 1. Z:d4").unwrap();
+            assert_eq!(parsed.win_length(), 4);
+            assert_eq!(parsed.first_player(), Player::Z);
+
+            let parsed = BoardState::from_notation("1. X:b2=O O:a1").unwrap();
+            assert_eq!(parsed[(1, 1)], TileState::O);
+            assert_eq!(parsed[(0, 0)], TileState::O);
+            assert_eq!(parsed.next(), Player::X);
+        }
+
+        #[test]
+        fn from_notation_errors() {
+            for notation in [
+                // Misnumbered
+                "X:b2",
+                "2. X:b2",
+                "1. X:b2 2. O:a1",
+                "1. 1. X:b2",
+                "1. X:b2 O:a1 3. X:c3",
+                "one. X:b2",
+                "1. X:b2 O:a1 2.",
+                // Unknown cells and players
+                "1. X:d1",
+                "1. X:b0",
+                "1. X:2b",
+                "1. Xb2",
+                "1. Y:b2",
+                "1. Z:b2",
+                "1. X:b2=",
+                "1. X:b2=Z",
+                "1. X:b2=OX",
+                // Illegal
+                "1. X:b2 X:a1",
+                "1. X:b2 O:b2",
+                "1. X:a1 O:a2 2. X:b1 O:b2 3. X:c1 O:c2",
+                // Bad settings
+                "size=0",
+                "size=3 win-length=4",
+                "size=3 players=3",
+                "size=three",
+                "size=27",
+                "size=5000000000",
+                "colors=2",
+                "1. X:b2 size=4",
+            ]
+            .iter()
+            {
+                assert!(
+                    BoardState::from_notation(notation).is_err(),
+                    "{:?}",
+                    notation
+                );
+            }
+            assert_eq!(
+                BoardState::from_notation("1. X:b2 O:a1 3. X:c3")
+                    .unwrap_err()
+                    .to_string(),
+                "expected a move, or move number 2, got \"3.\""
+            );
+            assert_eq!(
+                BoardState::from_notation("1. X:b2 X:a1")
+                    .unwrap_err()
+                    .to_string(),
+                "X moved out of turn in \"X:a1\""
+            );
+        }
+
+        #[test]
+        fn to_save() {
+            let board = BoardState::from_moves(&[(1, 1), (0, 2)]).unwrap();
+            assert_eq!(
+                board.to_save(),
+                "size 3\nwin-length 3\nplayers 2\nfirst X\nmove 1 1\nmove 0 2\n"
+            );
+            let parsed = BoardState::from_save(&board.to_save()).unwrap();
+            assert_eq!(parsed, board);
+            assert_eq!(parsed.history(), board.history());
+
+            let mut board = BoardState::with_players(5, 4, 3);
+            board.set_first_player(Player::Z);
+            board.play((4, 4)).unwrap().play((0, 0)).unwrap();
+            let parsed = BoardState::from_save(&board.to_save()).unwrap();
+            assert_eq!(parsed, board);
+            assert_eq!(parsed.win_length(), 4);
+            assert_eq!(parsed.first_player(), Player::Z);
+            assert_eq!(parsed.history(), board.history());
+
+            // Wild games note the marks that aren't the mover's own
+            let mut board = BoardState::new();
+            board.play_mark((1, 1), TileState::O).unwrap();
+            board.play_mark((0, 2), TileState::O).unwrap();
+            assert!(board.to_save().ends_with("move 1 1 O\nmove 0 2\n"));
+            let parsed = BoardState::from_save(&board.to_save()).unwrap();
+            assert_eq!(parsed, board);
+            assert_eq!(parsed[(1, 1)], TileState::O);
+        }
+
+        #[test]
+        fn from_save() {
+            let board = BoardState::from_save("\nsize 4\n  move 3 0\n").unwrap();
+            assert_eq!(board, *BoardState::with_size(4).play((3, 0)).unwrap());
+            assert_eq!(board.win_length(), 4);
+
+            for save in [
+                "",
+                "win-length 3\n",
+                "size\n",
+                "size three\n",
+                "size 0\n",
+                "size 3\nwin-length 4\n",
+                "size 3\nplayers 3\n",
+                "size 4\nplayers 4\n",
+                "size 3\nfirst Z\n",
+                "size 3\nfirst Y\n",
+                "size 3\nmove 1\n",
+                "size 3\nmove 1 1 Y\n",
+                "size 3\nmove 1 1 XO\n",
+                "size 3\nmove 1 1 Z\n",
+                "size 3\nmove 1 1 .\n",
+                "size 3\nmove 3 3\n",
+                "size 3\nmove 1 1\nmove 1 1\n",
+                "size 3\nlength 3\n",
+                "size 27\n",
+                "size 5000000000\n",
+            ]
+            .iter()
+            {
+                assert!(BoardState::from_save(save).is_err(), "{:?}", save);
+            }
+            assert_eq!(
+                BoardState::from_save("size 3\nmoves 1 1\n")
+                    .unwrap_err()
+                    .to_string(),
+                "unknown setting \"moves\" on line 2"
+            );
+        }
+
+        #[test]
+        fn from_str() {
+            let mut board = BoardState::new();
+            assert_eq!(board.to_string().parse::<BoardState>().unwrap(), board);
+            for &coords in [(1, 1), (0, 2), (2, 0), (0, 0)].iter() {
+                board.play(coords).unwrap();
+                assert_eq!(board.to_string().parse::<BoardState>().unwrap(), board);
+            }
+            assert_eq!(format!("{}\n", board).parse::<BoardState>().unwrap(), board);
+
+            for &size in [1, 4, 10, 12].iter() {
+                let mut board = BoardState::with_size(size);
+                board.play((0, size - 1)).unwrap();
+                assert_eq!(board.to_string().parse::<BoardState>().unwrap(), board);
+                if size > 1 {
+                    board.play((size - 1, 0)).unwrap();
+                    assert_eq!(board.to_string().parse::<BoardState>().unwrap(), board);
+                }
+            }
+        }
+
+        #[test]
+        fn from_str_errors() {
+            let grid = BoardState::new().to_string();
+            assert!("".parse::<BoardState>().is_err());
+            assert!(grid.replacen(" ", "", 1).parse::<BoardState>().is_err());
+            assert!(grid.replacen("|", "", 1).parse::<BoardState>().is_err());
+            assert!(grid.replacen("+", "|", 1).parse::<BoardState>().is_err());
+            assert!(grid.replacen("1 ", "3 ", 1).parse::<BoardState>().is_err());
+            assert_eq!(
+                grid.replacen("0  ", "0 x", 1)
+                    .parse::<BoardState>()
+                    .unwrap_err()
+                    .to_string(),
+                "unexpected tile \"x\" in row 0"
+            );
+            assert_eq!(
+                grid.replacen("0  |", "0  | |", 1)
+                    .parse::<BoardState>()
+                    .unwrap_err()
+                    .to_string(),
+                "expected 3 tiles in row 0, got 4"
+            );
+            assert_eq!(
+                grid.lines()
+                    .take(5)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+                    .parse::<BoardState>()
+                    .unwrap_err()
+                    .to_string(),
+                "expected 7 lines for a 3 by 3 board, got 5"
+            );
+            assert!(grid.replace("0  |", "0 O|").parse::<BoardState>().is_err());
+        }
+
+        #[test]
+        fn from_compact_errors() {
+            assert!(BoardState::from_compact("........").is_err());
+            assert!(BoardState::from_compact("..........").is_err());
+            assert!(BoardState::from_compact("").is_err());
+            assert!(BoardState::from_compact("....x....").is_err());
+            assert!(BoardState::from_compact("... .....").is_err());
+            assert!(BoardState::from_compact("XXXXX....").is_err());
+            assert!(BoardState::from_compact("XX.......").is_err());
+            assert!(BoardState::from_compact("O........").is_err());
+        }
+
+        #[test]
+        fn from_tiles() {
+            use TileState::*;
+            let puzzle = vec![X, Empty, Empty, Empty, O, Empty, Empty, Empty, X];
+            let board = BoardState::from_tiles(puzzle, Player::O).unwrap();
+            assert_eq!(board.to_compact(), "X...O...X");
+            assert_eq!(board.next(), Player::O);
+            assert_eq!(board.status(), GameResult::InProgress);
+            assert!(board.is_valid());
+
+            let board = BoardState::from_tiles(vec![Empty; 16], Player::O).unwrap();
+            assert_eq!(board.size(), 4);
+            assert_eq!(board.first_player(), Player::O);
+            assert_eq!(board.next(), Player::O);
+
+            let board = BoardState::from_tiles(vec![O, Empty, Empty, Empty], Player::X).unwrap();
+            assert_eq!(board.first_player(), Player::O);
+            assert_eq!(board.next(), Player::X);
+        }
+
+        #[test]
+        fn from_tiles_errors() {
+            use TileState::*;
+            assert!(BoardState::from_tiles(vec![Empty; 8], Player::X).is_err());
+            assert!(BoardState::from_tiles(Vec::new(), Player::X).is_err());
+            assert_eq!(
+                BoardState::from_tiles(vec![X, X, Empty, Empty], Player::O)
+                    .unwrap_err()
+                    .to_string(),
+                "\"XX..\" with O to move could not arise from play"
+            );
+            // X has played more, so it can't be X's turn
+            assert!(BoardState::from_tiles(vec![X, Empty, Empty, Empty], Player::X).is_err());
+            // O's middle row could not follow X's top row
+            let tiles = vec![X, X, X, O, O, O, Empty, Empty, Empty];
+            assert!(BoardState::from_tiles(tiles, Player::X).is_err());
+        }
+
+        #[test]
+        fn move_count() {
+            let mut board = BoardState::new();
+            assert!(board.is_empty());
+            assert_eq!(board.move_count(), 0);
+
+            board.play((1, 1)).unwrap();
+            assert!(!board.is_empty());
+            assert_eq!(board.move_count(), 1);
+            assert_eq!(board.move_count() + board.remaining(), 9);
+
+            let full = BoardState::from_compact("XOXXOOOXX").unwrap();
+            assert!(!full.is_empty());
+            assert_eq!(full.move_count(), 9);
+
+            let mut board = BoardState::with_size(4);
+            board[(3, 3)] = TileState::O;
+            assert_eq!(board.move_count(), 1);
+            board[(3, 3)] = TileState::Empty;
+            assert!(board.is_empty());
+        }
+
+        #[test]
+        fn available_moves() {
+            use TileState::*;
+            let board = BoardState::new();
+            assert_eq!(board.available_moves().count(), 9);
+            assert_eq!(board.remaining(), 9);
+            assert_eq!(
+                board.available_moves().take(4).collect::<Vec<_>>(),
+                vec![(0, 0), (1, 0), (2, 0), (0, 1)]
+            );
+
+            let board = BoardState {
+                tiles: vec![X, O, X, X, O, O, O, Empty, X],
+                next: Player::X,
+                ..BoardState::new()
+            };
+            assert_eq!(board.available_moves().collect::<Vec<_>>(), vec![(1, 2)]);
+            assert_eq!(board.remaining(), 1);
+
+            let mut board = BoardState::with_size(4);
+            board.play((1, 1)).unwrap().play((3, 2)).unwrap();
+            assert_eq!(board.remaining(), 14);
+            for coords in board.available_moves() {
+                assert_eq!(board[coords], Empty);
+                assert!(board.clone().play(coords).is_ok());
+            }
+        }
+
+        #[test]
+        fn iter_row() {
+            use TileState::*;
+            let board = BoardState {
+                tiles: vec![X, X, Empty, O, X, Empty, Empty, Empty, O],
+                next: Player::O,
+                ..BoardState::new()
+            };
+            assert_eq!(board.iter_row(0).collect::<Vec<_>>(), vec![X, X, Empty]);
+            assert_eq!(board.iter_row(1).collect::<Vec<_>>(), vec![O, X, Empty]);
+        }
+
+        #[test]
+        fn iter_col() {
+            use TileState::*;
+            let board = BoardState {
+                tiles: vec![X, X, Empty, O, X, Empty, Empty, Empty, O],
+                next: Player::O,
+                ..BoardState::new()
+            };
+            assert_eq!(board.iter_col(0).collect::<Vec<_>>(), vec![X, O, Empty]);
+            assert_eq!(board.iter_col(1).collect::<Vec<_>>(), vec![X, X, Empty]);
+        }
+
+        #[test]
+        fn iter_diag() {
+            use TileState::*;
+            let board = BoardState {
+                tiles: vec![X, X, Empty, O, X, Empty, Empty, Empty, O],
+                next: Player::O,
+                ..BoardState::new()
+            };
+            assert_eq!(board.iter_diag(false).collect::<Vec<_>>(), vec![X, X, O]);
+            assert_eq!(
+                board.iter_diag(true).collect::<Vec<_>>(),
+                vec![Empty, X, Empty]
+            );
+        }
+
+        #[test]
+        fn won() {
+            use TileState::*;
+            assert_eq!(BoardState::new().won(), None);
+            let board = BoardState {
+                tiles: vec![X, O, X, O, X, X, O, X, O],
+                next: Player::O,
+                ..BoardState::new()
+            };
+            assert_eq!(board.won(), None);
+            let board = BoardState {
+                tiles: vec![X, O, X, O, X, O, X, Empty, Empty],
+                next: Player::O,
+                ..BoardState::new()
+            };
+            assert_eq!(board.won(), Some(Player::X));
+        }
+
+        #[test]
+        fn iter_lines() {
+            for size in 1..6 {
+                let board = BoardState::with_size(size);
+                let lines = board.iter_lines().collect::<Vec<_>>();
+                assert_eq!(lines.len(), 2 * size + 2);
+                for line in lines.iter() {
+                    assert_eq!(line.len(), size);
+                    let mut distinct = line.clone();
+                    distinct.sort_unstable();
+                    distinct.dedup();
+                    assert_eq!(distinct.len(), size);
+                    assert!(line.iter().all(|&(x, y)| x < size && y < size));
+                }
+            }
+
+            let lines = BoardState::new().iter_lines().collect::<Vec<_>>();
+            assert_eq!(lines[0], vec![(0, 0), (1, 0), (2, 0)]);
+            assert_eq!(lines[5], vec![(2, 0), (2, 1), (2, 2)]);
+            assert_eq!(lines[7], vec![(2, 0), (1, 1), (0, 2)]);
+
+            let board = BoardState::with_win_length(4, 3);
+            assert_eq!(board.iter_lines().count(), 4 + 4 + 3 + 3);
+            assert!(board
+                .iter_lines()
+                .any(|line| line == vec![(1, 0), (2, 1), (3, 2)]));
+            assert!(board
+                .iter_lines()
+                .any(|line| line == vec![(3, 1), (2, 2), (1, 3)]));
+        }
+
+        #[test]
+        fn status() {
+            use TileState::*;
+            assert_eq!(BoardState::new().status(), GameResult::InProgress);
+            let board = BoardState {
+                tiles: vec![X, O, X, Empty, O, Empty, Empty, Empty, Empty],
+                next: Player::X,
+                ..BoardState::new()
+            };
+            assert_eq!(board.status(), GameResult::InProgress);
+            assert!(!board.drawn());
+            let board = BoardState {
+                tiles: vec![X, O, X, O, X, X, O, X, O],
+                next: Player::O,
+                ..BoardState::new()
+            };
+            assert_eq!(board.status(), GameResult::Draw);
+            assert!(board.drawn());
+            let board = BoardState {
+                tiles: vec![X, O, X, O, X, O, X, Empty, Empty],
+                next: Player::O,
+                ..BoardState::new()
+            };
+            assert_eq!(board.status(), GameResult::Win(Player::X));
+            assert!(!board.drawn());
+            let board = BoardState {
+                tiles: vec![X, X, X, O, O, X, X, O, O],
+                next: Player::O,
+                ..BoardState::new()
+            };
+            assert_eq!(board.status(), GameResult::Win(Player::X));
+            assert!(!board.drawn());
+        }
+
+        #[test]
+        fn is_dead_draw() {
+            let dead = BoardState::from_compact("XOXXOOOX.").unwrap();
+            assert_eq!(dead.status(), GameResult::InProgress);
+            assert!(dead.is_dead_draw());
+            assert!(BoardState::from_compact("XOXXOOOXX")
+                .unwrap()
+                .is_dead_draw());
+
+            // X can still complete the left column
+            let open = BoardState::from_compact("XOXXOO...").unwrap();
+            assert!(!open.is_dead_draw());
+            assert!(!BoardState::new().is_dead_draw());
+
+            let won = BoardState::from_compact("XXXOO....").unwrap();
+            assert!(!won.is_dead_draw());
+
+            // Only runs of the winning length matter
+            let mut board = BoardState::with_win_length(4, 3);
+            for &coords in [(0, 0), (1, 0), (3, 0), (2, 0)].iter() {
+                board.play(coords).unwrap();
+            }
+            assert!(!board.is_dead_draw());
+        }
+
+        #[test]
+        fn winning_line() {
+            use TileState::*;
+            assert_eq!(BoardState::new().winning_line(), None);
+
+            let lines = [
+                [(0, 0), (1, 0), (2, 0)],
+                [(0, 1), (1, 1), (2, 1)],
+                [(0, 2), (1, 2), (2, 2)],
+                [(0, 0), (0, 1), (0, 2)],
+                [(1, 0), (1, 1), (1, 2)],
+                [(2, 0), (2, 1), (2, 2)],
+                [(0, 0), (1, 1), (2, 2)],
+                [(2, 0), (1, 1), (0, 2)],
+            ];
+            for &line in lines.iter() {
+                let mut board = BoardState::new();
+                for &coord in line.iter() {
+                    board[coord] = O;
+                }
+                assert_eq!(board.winning_line(), Some((Player::O, line.to_vec())));
+            }
+
+            let board = BoardState {
+                tiles: vec![X, X, X, X, O, O, X, O, O],
+                next: Player::O,
+                ..BoardState::new()
+            };
+            assert_eq!(
+                board.winning_line(),
+                Some((Player::X, vec![(0, 0), (1, 0), (2, 0)]))
+            );
+        }
+
+        #[test]
+        fn gomoku() {
+            // The start of each run of five and the step along it: rows,
+            // columns, and diagonals both ways, at the edges, in the middle,
+            // and off the main diagonals
+            let runs = [
+                ((0, 0), (1, 0)),
+                ((10, 14), (1, 0)),
+                ((4, 7), (1, 0)),
+                ((14, 10), (0, 1)),
+                ((0, 0), (1, 1)),
+                ((10, 10), (1, 1)),
+                ((3, 0), (1, 1)),
+                ((0, 9), (1, 1)),
+                ((4, 0), (-1, 1)),
+                ((14, 6), (-1, 1)),
+                ((12, 3), (-1, 1)),
+            ];
+            for &((x, y), (dx, dy)) in runs.iter() {
+                let run = (0..5)
+                    .map(|i| ((x as isize + dx * i) as usize, y + dy as usize * i as usize))
+                    .collect::<Vec<_>>();
+
+                let mut board = BoardState::with_win_length(15, 5);
+                for &coords in run[..4].iter() {
+                    board[coords] = TileState::X;
+                }
+                assert_eq!(board.won(), None, "{:?}", run);
+                board[run[4]] = TileState::X;
+                assert_eq!(board.winning_line(), Some((Player::X, run.clone())));
+
+                // Five in a row wins even as part of a longer line
+                let mut board = BoardState::with_win_length(15, 5);
+                for &coords in run.iter() {
+                    board[coords] = TileState::O;
+                }
+                let (before_x, before_y) = (x as isize - dx, y as isize - dy);
+                if (0..15).contains(&before_x) && (0..15).contains(&before_y) {
+                    board[(before_x as usize, before_y as usize)] = TileState::O;
+                }
+                assert_eq!(board.won(), Some(Player::O), "{:?}", run);
+            }
+        }
+
+        #[test]
+        fn zobrist() {
+            let board = BoardState::from_moves(&[(0, 0), (1, 1), (2, 2), (0, 1)]).unwrap();
+            let transposed = BoardState::from_moves(&[(2, 2), (0, 1), (0, 0), (1, 1)]).unwrap();
+            assert_eq!(board, transposed);
+            assert_eq!(board.zobrist(), transposed.zobrist());
+            assert_ne!(board.zobrist(), BoardState::new().zobrist());
+            assert_ne!(
+                BoardState::new().zobrist(),
+                BoardState {
+                    next: Player::O,
+                    ..BoardState::new()
+                }
+                .zobrist()
+            );
+            assert_ne!(
+                BoardState::from_moves(&[(0, 0), (1, 1)]).unwrap().zobrist(),
+                BoardState::from_moves(&[(1, 1), (0, 0)]).unwrap().zobrist()
+            );
+
+            let mut board = BoardState::from_moves(&[(1, 1)]).unwrap();
+            let key = board.zobrist();
+            board.play((0, 0)).unwrap();
+            let played = board.zobrist();
+            assert_ne!(played, key);
+            board.undo().unwrap();
+            assert_eq!(board.zobrist(), key);
+            board.redo().unwrap();
+            assert_eq!(board.zobrist(), played);
+            board.goto_move(0).unwrap();
+            assert_eq!(board.zobrist(), BoardState::new().zobrist());
+        }
+
+        #[test]
+        fn zobrist_edited() {
+            let mut board = BoardState::from_moves(&[(1, 1), (0, 0)]).unwrap();
+            let key = board.zobrist();
+            board[(0, 0)] = TileState::Empty;
+            assert_ne!(board.zobrist(), key);
+            board[(0, 0)] = TileState::O;
+            assert_eq!(board.zobrist(), key);
+
+            board.play((2, 2)).unwrap();
+            let played = BoardState::from_moves(&[(1, 1), (0, 0), (2, 2)]).unwrap();
+            assert_eq!(board.zobrist(), played.zobrist());
+
+            board.set((2, 2), TileState::Empty).unwrap();
+            board.set((2, 2), TileState::X).unwrap();
+            assert_eq!(board.zobrist(), played.zobrist());
+            board.set((2, 1), TileState::Z).unwrap();
+            assert_ne!(board.zobrist(), played.zobrist());
+        }
+
+        /// Plays a random game, choosing each move by index into the moves
+        /// still available, until it ends or the choices run out
+        fn play_out(size: usize, win_length: usize, choices: &[usize]) -> BoardState {
+            let mut board = BoardState::with_win_length(size, win_length);
+            for &choice in choices {
+                let moves = board.available_moves().collect::<Vec<_>>();
+                if moves.is_empty() || board.won().is_some() {
+                    break;
+                }
+                board.play(moves[choice % moves.len()]).unwrap();
+            }
+            board
+        }
+
+        proptest! {
+            #[test]
+            fn win_detection(
+                (size, win_length) in (1..=9usize).prop_flat_map(|size| (Just(size), 1..=size)),
+                choices in collection::vec(0..81usize, 0..81),
+            ) {
+                let board = play_out(size, win_length, &choices);
+                prop_assert!(board.is_valid());
+
+                // Writing through IndexMut recomputes the key and masks from
+                // scratch
+                let mut rehashed = board.clone();
+                rehashed[(0, 0)] = board[(0, 0)];
+                prop_assert_eq!(rehashed.zobrist(), board.zobrist());
+                prop_assert_eq!(rehashed.masks(), board.masks());
+                prop_assert_eq!(board.masks().is_some(), size <= 8);
+
+                let winners = board
+                    .iter_lines()
+                    .flat_map(|line| {
+                        line.windows(win_length)
+                            .filter_map(|run| board.run_winner(run))
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<HashSet<_>>();
+                prop_assert!(winners.len() <= 1);
+
+                match board.winning_line() {
+                    Some((player, line)) => {
+                        prop_assert_eq!(board.won(), Some(player));
+                        prop_assert!(winners.contains(&player));
+                        prop_assert_eq!(line.len(), win_length);
+                        for &coords in line.iter() {
+                            prop_assert_eq!(Option::<Player>::from(board[coords]), Some(player));
+                        }
+                    }
+                    None => prop_assert_eq!(board.won(), None),
+                }
+
+                if board.drawn() {
+                    prop_assert_eq!(board.won(), None);
+                    prop_assert_eq!(board.remaining(), 0);
+                    prop_assert!(board.is_dead_draw());
+                }
+                if board.is_dead_draw() {
+                    prop_assert_eq!(board.won(), None);
+                    for coords in board.available_moves() {
+                        let mut next = board.clone();
+                        next.play(coords).unwrap();
+                        prop_assert_eq!(next.won(), None);
+                    }
+                }
+                if board.won().is_none() && board.remaining() == 0 {
+                    prop_assert!(board.drawn());
+                    prop_assert_eq!(board.status(), GameResult::Draw);
+                }
+            }
+        }
+    }
+
+    mod game_view {
+        use super::*;
+
+        #[test]
+        fn view() {
+            let mut board = BoardState::new();
+            board.play((1, 1)).unwrap();
+            let view = board.view();
+            assert_eq!(view.tile((1, 1)), TileState::X);
+            assert_eq!(view.size(), 3);
+            assert_eq!(view.next(), Player::O);
+            assert_eq!(view.status(), GameResult::InProgress);
+            assert_eq!(view.winning_line(), None);
+            assert_eq!(view.move_count(), 1);
+            assert_eq!(view.to_string(), board.to_string());
+
+            // A new view shows the moves played since the last
+            for &coords in [(0, 0), (0, 1), (2, 0), (2, 1)].iter() {
+                board.play(coords).unwrap();
+            }
+            let view = board.view();
+            assert_eq!(view.history(), &[(1, 1), (0, 0), (0, 1), (2, 0), (2, 1)]);
+            assert_eq!(view.status(), GameResult::Win(Player::X));
+            assert_eq!(
+                view.winning_line(),
+                Some((Player::X, vec![(0, 1), (1, 1), (2, 1)]))
+            );
+            assert_eq!(
+                view.render().with_unicode(true).to_string(),
+                board.display_unicode().to_string()
+            );
         }
     }
 }
@@ -0,0 +1,511 @@
+//! Per-move time limits for games played at a terminal
+
+use core::{cell::Cell, fmt, str::FromStr, time::Duration};
+use std::{
+    io::{self, BufRead, Read, Write},
+    rc::Rc,
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::Instant,
+};
+
+use anyhow::{self, anyhow as anyhow_error};
+use rand::{rngs::StdRng, seq::IteratorRandom, SeedableRng};
+
+use crate::{
+    game_io::GameIO,
+    input::Command,
+    messages::{Language, Message},
+    state::BoardState,
+};
+
+/// The time limit for a limit of `seconds`, where 0 means there is no limit
+pub fn time_limit(seconds: u64) -> Option<Duration> {
+    match seconds {
+        0 => None,
+        seconds => Some(Duration::from_secs(seconds)),
+    }
+}
+
+/// When a move started at `started` must be made by to stay within `limit`
+pub fn deadline(started: Instant, limit: Option<Duration>) -> Option<Instant> {
+    limit.map(|limit| started + limit)
+}
+
+/// Whether `deadline` has passed by `now`; there is no hurry without one
+pub fn expired(deadline: Option<Instant>, now: Instant) -> bool {
+    matches!(deadline, Some(deadline) if now >= deadline)
+}
+
+/// How many of the last seconds before a deadline are each counted down;
+/// before them, only every tenth is
+const COUNTDOWN: u64 = 5;
+
+/// The next moment after `now` at which to say how many whole seconds are
+/// left before `deadline`, and that number, if any are still to be said
+pub fn next_reminder(deadline: Instant, now: Instant) -> Option<(Instant, u64)> {
+    // The most seconds that are still to come, not counting one now
+    let remaining = deadline.checked_duration_since(now)?;
+    let most = remaining.as_secs() - u64::from(remaining.subsec_nanos() == 0);
+    let seconds = if most <= COUNTDOWN {
+        most
+    } else {
+        (most / 10 * 10).max(COUNTDOWN)
+    };
+    match seconds {
+        0 => None,
+        seconds => Some((deadline - Duration::from_secs(seconds), seconds)),
+    }
+}
+
+/// What happens to a player who runs out of time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnTimeout {
+    /// They lose the game
+    #[default]
+    ForfeitGame,
+    /// They lose the choice of move, and a random one is played for them,
+    /// since a board's history can't record a passed turn
+    ForfeitTurn,
+}
+
+impl FromStr for OnTimeout {
+    type Err = anyhow::Error;
+
+    /// Parses `game` or `turn`, ignoring case
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "game" => Ok(Self::ForfeitGame),
+            "turn" => Ok(Self::ForfeitTurn),
+            _ => Err(anyhow_error!(
+                "unknown forfeit {:?}; expected game or turn",
+                s
+            )),
+        }
+    }
+}
+
+/// A shared handle on the time by which a [`TimedInput`] must be given a
+/// line, if any
+#[derive(Debug, Clone, Default)]
+pub struct Deadline(Rc<Cell<Option<Instant>>>);
+
+impl Deadline {
+    pub fn get(&self) -> Option<Instant> {
+        self.0.get()
+    }
+
+    pub fn set(&self, deadline: Option<Instant>) {
+        self.0.set(deadline)
+    }
+}
+
+/// Input that reads lines on a background thread, so that waiting for one
+/// can give up at a deadline with an [`io::ErrorKind::TimedOut`] error.
+///
+/// Lines entered after a read has timed out but before the next read starts
+/// were meant for the turn that ran out, so they are thrown away.
+#[derive(Debug)]
+pub struct TimedInput {
+    lines: Receiver<(Instant, io::Result<String>)>,
+    line: Vec<u8>,
+    position: usize,
+    deadline: Deadline,
+    countdown: Option<Countdown>,
+    /// Whether the last read timed out
+    timed_out: bool,
+    finished: bool,
+}
+
+impl TimedInput {
+    pub fn new<R: BufRead + Send + 'static>(mut input: R) -> Self {
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || loop {
+            let mut line = String::new();
+            let result = input.read_line(&mut line).map(|_| line);
+            let last = !matches!(&result, Ok(line) if !line.is_empty());
+            if sender.send((Instant::now(), result)).is_err() || last {
+                break;
+            }
+        });
+
+        TimedInput {
+            lines,
+            line: Vec::new(),
+            position: 0,
+            deadline: Deadline::default(),
+            countdown: None,
+            timed_out: false,
+            finished: false,
+        }
+    }
+
+    /// Counts down the seconds left before a deadline on `output`, in
+    /// `language`, while waiting for a line
+    pub fn with_countdown<W: Write + 'static>(mut self, output: W, language: Language) -> Self {
+        self.countdown = Some(Countdown {
+            output: Box::new(output),
+            language,
+        });
+        self
+    }
+
+    /// The handle through which to set a deadline for reads
+    pub fn deadline(&self) -> Deadline {
+        self.deadline.clone()
+    }
+
+    /// Waits for the next line, or for the deadline if there is one
+    fn receive(&mut self) -> io::Result<Option<String>> {
+        let since = Instant::now();
+        let stale = self.timed_out;
+        self.timed_out = false;
+
+        loop {
+            let deadline = self.deadline.get();
+            let now = Instant::now();
+            if expired(deadline, now) {
+                self.timed_out = true;
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "time ran out"));
+            }
+
+            let received = match deadline {
+                Some(deadline) => {
+                    let reminder = self
+                        .countdown
+                        .as_ref()
+                        .and_then(|_| next_reminder(deadline, now));
+                    let wake = reminder.map_or(deadline, |(at, _)| at);
+                    match self.lines.recv_timeout(wake - now) {
+                        Ok(received) => Some(received),
+                        Err(RecvTimeoutError::Timeout) => {
+                            if let (Some(countdown), Some((at, seconds))) =
+                                (&mut self.countdown, reminder)
+                            {
+                                // Waking early would only say the same again
+                                if Instant::now() >= at {
+                                    countdown.remind(seconds)?;
+                                }
+                            }
+                            continue;
+                        }
+                        Err(RecvTimeoutError::Disconnected) => None,
+                    }
+                }
+                None => self.lines.recv().ok(),
+            };
+
+            match received {
+                Some((sent, _)) if stale && sent < since => continue,
+                Some((_, result)) => return result.map(Some),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// Where and how a [`TimedInput`] counts down
+struct Countdown {
+    output: Box<dyn Write>,
+    language: Language,
+}
+
+impl Countdown {
+    /// Says that `seconds` are left, on a line of its own after the prompt
+    /// or the last reminder, so that typing can carry on after it
+    fn remind(&mut self, seconds: u64) -> io::Result<()> {
+        write!(
+            self.output,
+            "\n{}",
+            self.language.text(Message::SecondsLeft(seconds))
+        )?;
+        self.output.flush()
+    }
+}
+
+impl fmt::Debug for Countdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Countdown")
+            .field("language", &self.language)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Read for TimedInput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let count = available.len().min(buf.len());
+        buf[..count].copy_from_slice(&available[..count]);
+        self.consume(count);
+        Ok(count)
+    }
+}
+
+impl BufRead for TimedInput {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.position == self.line.len() && !self.finished {
+            match self.receive()? {
+                Some(line) if !line.is_empty() => {
+                    self.line = line.into_bytes();
+                    self.position = 0;
+                }
+                _ => self.finished = true,
+            }
+        }
+        Ok(&self.line[self.position..])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.position = (self.position + amount).min(self.line.len());
+    }
+}
+
+/// Gives each player `limit` to choose a move through `inner`, which must
+/// read from the [`TimedInput`] that `deadline` came from. A player who runs
+/// out of time forfeits as [`OnTimeout`] says.
+pub struct TimedGameIO<I> {
+    inner: I,
+    deadline: Deadline,
+    limit: Duration,
+    on_timeout: OnTimeout,
+    rng: StdRng,
+}
+
+impl<I: GameIO> TimedGameIO<I> {
+    pub fn new(inner: I, deadline: Deadline, limit: Duration) -> Self {
+        TimedGameIO {
+            inner,
+            deadline,
+            limit,
+            on_timeout: OnTimeout::default(),
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    pub fn with_on_timeout(mut self, on_timeout: OnTimeout) -> Self {
+        self.on_timeout = on_timeout;
+        self
+    }
+
+    /// Draws the moves played for players who run out of time from `rng`
+    pub fn with_rng(mut self, rng: StdRng) -> Self {
+        self.rng = rng;
+        self
+    }
+}
+
+impl<I: GameIO> GameIO for TimedGameIO<I> {
+    fn read_command(&mut self, board: &BoardState) -> anyhow::Result<Command> {
+        let language = self.inner.language();
+        let seconds = self.limit.as_secs_f64().ceil() as u64;
+        self.inner
+            .show_message(&language.text(Message::SecondsToMove(seconds)))?;
+        self.deadline
+            .set(deadline(Instant::now(), Some(self.limit)));
+        let command = self.inner.read_command(board);
+        self.deadline.set(None);
+
+        match command {
+            Err(error)
+                if matches!(
+                    error.downcast_ref::<io::Error>(),
+                    Some(error) if error.kind() == io::ErrorKind::TimedOut
+                ) =>
+            {
+                // Finish the line of the prompt that went unanswered
+                self.inner
+                    .show_message(&format!("\n{}", language.text(Message::TimeUp)))?;
+                match self.on_timeout {
+                    OnTimeout::ForfeitGame => Ok(Command::Forfeit),
+                    OnTimeout::ForfeitTurn => Ok(board
+                        .available_moves()
+                        .choose(&mut self.rng)
+                        .map_or(Command::Forfeit, Command::Move)),
+                }
+            }
+            command => command,
+        }
+    }
+
+    fn show_board(&mut self, board: &BoardState) -> anyhow::Result<()> {
+        self.inner.show_board(board)
+    }
+
+    fn show_message(&mut self, message: &str) -> anyhow::Result<()> {
+        self.inner.show_message(message)
+    }
+
+    fn show_result(&mut self, message: &str) -> anyhow::Result<()> {
+        self.inner.show_result(message)
+    }
+
+    fn show_hint(&mut self, hint: Option<(usize, usize)>) -> anyhow::Result<()> {
+        self.inner.show_hint(hint)
+    }
+
+    fn show_coordinate_help(&mut self) -> anyhow::Result<()> {
+        self.inner.show_coordinate_help()
+    }
+
+    fn alert(&mut self) -> anyhow::Result<()> {
+        self.inner.alert()
+    }
+
+    fn language(&self) -> Language {
+        self.inner.language()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game_io::StdioGameIO;
+
+    /// Input that never arrives
+    struct Stalled;
+
+    impl Read for Stalled {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            thread::park();
+            Ok(0)
+        }
+    }
+
+    /// Output that can still be read after being handed over
+    #[derive(Clone, Default)]
+    struct Shared(Rc<Cell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let mut written = self.0.take();
+            written.extend_from_slice(buf);
+            self.0.set(written);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn time_limit() {
+        assert_eq!(super::time_limit(0), None);
+        assert_eq!(super::time_limit(10), Some(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn expired() {
+        let started = Instant::now();
+        let limit = super::time_limit(5);
+        let deadline = super::deadline(started, limit);
+        assert_eq!(deadline, Some(started + Duration::from_secs(5)));
+        assert!(!super::expired(deadline, started));
+        assert!(!super::expired(
+            deadline,
+            started + Duration::from_millis(4999)
+        ));
+        assert!(super::expired(deadline, started + Duration::from_secs(5)));
+        assert!(super::expired(deadline, started + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn unlimited() {
+        let started = Instant::now();
+        let deadline = super::deadline(started, super::time_limit(0));
+        assert_eq!(deadline, None);
+        assert!(!super::expired(
+            deadline,
+            started + Duration::from_secs(1 << 30)
+        ));
+    }
+
+    #[test]
+    fn next_reminder() {
+        let now = Instant::now();
+        let reminder = |seconds| super::next_reminder(now + seconds, now);
+        let at = |seconds| now + Duration::from_millis(seconds);
+        assert_eq!(reminder(Duration::from_secs(60)), Some((at(10_000), 50)));
+        assert_eq!(reminder(Duration::from_millis(10_500)), Some((at(500), 10)));
+        assert_eq!(reminder(Duration::from_secs(10)), Some((at(5000), 5)));
+        assert_eq!(reminder(Duration::from_secs(5)), Some((at(1000), 4)));
+        assert_eq!(reminder(Duration::from_millis(1500)), Some((at(500), 1)));
+        assert_eq!(reminder(Duration::from_secs(1)), None);
+        assert_eq!(
+            super::next_reminder(now, now + Duration::from_secs(1)),
+            None
+        );
+    }
+
+    #[test]
+    fn on_timeout() {
+        assert_eq!("game".parse::<OnTimeout>().unwrap(), OnTimeout::ForfeitGame);
+        assert_eq!("Turn".parse::<OnTimeout>().unwrap(), OnTimeout::ForfeitTurn);
+        assert!("pass".parse::<OnTimeout>().is_err());
+    }
+
+    #[test]
+    fn timed_input() {
+        let mut input = TimedInput::new("1, 1\n2, 2".as_bytes());
+        let mut line = String::new();
+        input.read_line(&mut line).unwrap();
+        assert_eq!(line, "1, 1\n");
+        input.read_line(&mut line).unwrap();
+        assert_eq!(line, "1, 1\n2, 2");
+        assert_eq!(input.read_line(&mut line).unwrap(), 0);
+
+        let mut input = TimedInput::new(io::BufReader::new(Stalled));
+        input
+            .deadline()
+            .set(deadline(Instant::now(), Some(Duration::from_millis(10))));
+        let error = input.read_line(&mut line).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn countdown() {
+        let output = Shared::default();
+        let mut input = TimedInput::new(io::BufReader::new(Stalled))
+            .with_countdown(output.clone(), Language::English);
+        input
+            .deadline()
+            .set(deadline(Instant::now(), Some(Duration::from_millis(1050))));
+        let error = input.read_line(&mut String::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(
+            String::from_utf8(output.0.take()).unwrap(),
+            "\n1 second left"
+        );
+    }
+
+    #[test]
+    fn timed_game_io() {
+        let board = BoardState::from_moves(&[(1, 1)]).unwrap();
+        for &on_timeout in [OnTimeout::ForfeitGame, OnTimeout::ForfeitTurn].iter() {
+            let input = TimedInput::new(io::BufReader::new(Stalled));
+            let deadline = input.deadline();
+            let mut output = Vec::new();
+            let mut io = TimedGameIO::new(
+                StdioGameIO::new(input, &mut output),
+                deadline.clone(),
+                Duration::from_millis(10),
+            )
+            .with_on_timeout(on_timeout)
+            .with_rng(StdRng::seed_from_u64(0));
+            match io.read_command(&board).unwrap() {
+                Command::Forfeit => assert_eq!(on_timeout, OnTimeout::ForfeitGame),
+                Command::Move(coords) => {
+                    assert_eq!(on_timeout, OnTimeout::ForfeitTurn);
+                    assert!(board.available_moves().any(|empty| empty == coords));
+                }
+                command => panic!("unexpected {:?}", command),
+            }
+            assert_eq!(deadline.get(), None);
+            drop(io);
+            assert_eq!(
+                String::from_utf8(output).unwrap(),
+                "1 second to move\nMove 2 — O > \nTime's up!\n"
+            );
+        }
+    }
+}
@@ -0,0 +1,394 @@
+//! A full-screen terminal interface in which players steer a cursor over the
+//! board with the arrow keys and place their piece with Enter
+
+use std::io::{self, Write};
+
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+use crate::{
+    game::Names,
+    game_io::GameIO,
+    input::Command,
+    messages::{Language, Message},
+    state::{BoardState, GameResult},
+};
+
+/// How many of the most recent messages stay on screen
+const MESSAGES: usize = 3;
+
+/// A direction the cursor can step in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// The tile a player is pointing at
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Cursor {
+    x: usize,
+    y: usize,
+}
+
+impl Cursor {
+    /// Creates a cursor at the center of a `size` by `size` board
+    pub fn new(size: usize) -> Self {
+        Cursor {
+            x: size / 2,
+            y: size / 2,
+        }
+    }
+
+    pub fn coords(self) -> (usize, usize) {
+        (self.x, self.y)
+    }
+
+    /// Moves one tile in `direction`, or stays put at the edge of a `size` by
+    /// `size` board
+    pub fn step(&mut self, direction: Direction, size: usize) {
+        match direction {
+            Direction::Up => self.y = self.y.saturating_sub(1),
+            Direction::Down => self.y = (self.y + 1).min(size - 1),
+            Direction::Left => self.x = self.x.saturating_sub(1),
+            Direction::Right => self.x = (self.x + 1).min(size - 1),
+        }
+    }
+
+    /// Moves to `coords`, or as close as a `size` by `size` board allows
+    pub fn jump(&mut self, (x, y): (usize, usize), size: usize) {
+        self.x = x.min(size - 1);
+        self.y = y.min(size - 1);
+    }
+}
+
+/// Applies a key press to `cursor` on a `size` by `size` board, returning the
+/// command it completes, if any. Arrow keys move the cursor, Enter or Space
+/// places a piece under it, and H, R, and Q or Escape ask for a hint, a
+/// restart, or to quit.
+pub fn key_command(key: KeyEvent, cursor: &mut Cursor, size: usize) -> Option<Command> {
+    if key.kind == KeyEventKind::Release {
+        return None;
+    }
+
+    let direction = match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            return Some(Command::Quit)
+        }
+        KeyCode::Up => Direction::Up,
+        KeyCode::Down => Direction::Down,
+        KeyCode::Left => Direction::Left,
+        KeyCode::Right => Direction::Right,
+        KeyCode::Enter | KeyCode::Char(' ') => return Some(Command::Move(cursor.coords())),
+        KeyCode::Char('h') | KeyCode::Char('H') => return Some(Command::Hint),
+        KeyCode::Char('r') | KeyCode::Char('R') => return Some(Command::Restart),
+        KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => return Some(Command::Quit),
+        _ => return None,
+    };
+
+    cursor.step(direction, size);
+    None
+}
+
+/// Plays in the terminal's alternate screen, redrawing the board in place.
+///
+/// The terminal is put back as it was when this is dropped, and the last
+/// board and messages are printed so that the result stays visible.
+pub struct TuiGameIO<W: Write> {
+    output: W,
+    names: Names,
+    cursor: Cursor,
+    /// The board last shown, kept to redraw after each key or resize
+    board: BoardState,
+    messages: Vec<String>,
+    language: Language,
+}
+
+impl<W: Write> TuiGameIO<W> {
+    /// Takes over the terminal that `output` writes to
+    pub fn new(mut output: W) -> anyhow::Result<Self> {
+        terminal::enable_raw_mode()?;
+        if let Err(error) = execute!(output, EnterAlternateScreen, Hide) {
+            // Without a TuiGameIO to drop, nothing else will restore the
+            // terminal, and there is nowhere to report failing to
+            let _ = execute!(output, Show, LeaveAlternateScreen);
+            let _ = terminal::disable_raw_mode();
+            return Err(error.into());
+        }
+        Ok(TuiGameIO {
+            output,
+            names: Names::default(),
+            cursor: Cursor::new(BoardState::new().size()),
+            board: BoardState::new(),
+            messages: Vec::new(),
+            language: Language::default(),
+        })
+    }
+
+    /// Addresses the players by `names`
+    pub fn with_names(mut self, names: Names) -> Self {
+        self.names = names;
+        self
+    }
+
+    /// Shows the game's messages in `language`
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+
+    fn draw(&mut self) -> io::Result<()> {
+        let size = self.board.size();
+        let status = match self.board.status() {
+            GameResult::InProgress => self
+                .language
+                .text(Message::ToMove(&self.names.label(self.board.next()))),
+            _ => self.language.text(Message::GameOver),
+        };
+        let lines = 2 * size + 3 + self.messages.len();
+        let (columns, rows) = terminal::size()?;
+
+        queue!(self.output, Clear(ClearType::All), MoveTo(0, 0))?;
+        if usize::from(columns) < 2 * size + 2 || usize::from(rows) < lines {
+            queue!(
+                self.output,
+                Print(self.language.text(Message::EnlargeTerminal))
+            )?;
+            return self.output.flush();
+        }
+
+        queue!(self.output, Print(status))?;
+        let mut row = 2;
+        for y in 0..size {
+            if y > 0 {
+                let rule = vec!["-"; size].join("+");
+                queue!(self.output, MoveTo(0, row), Print(rule))?;
+                row += 1;
+            }
+            queue!(self.output, MoveTo(0, row))?;
+            for x in 0..size {
+                if x > 0 {
+                    queue!(self.output, Print('|'))?;
+                }
+                let tile = self.board[(x, y)].to_string();
+                if self.cursor.coords() == (x, y) {
+                    queue!(
+                        self.output,
+                        SetAttribute(Attribute::Reverse),
+                        Print(tile),
+                        SetAttribute(Attribute::Reset)
+                    )?;
+                } else {
+                    queue!(self.output, Print(tile))?;
+                }
+            }
+            row += 1;
+        }
+
+        row += 1;
+        for message in self.messages.iter() {
+            queue!(self.output, MoveTo(0, row), Print(message))?;
+            row += 1;
+        }
+        queue!(
+            self.output,
+            MoveTo(0, row),
+            Print(self.language.text(Message::KeyHelp))
+        )?;
+        self.output.flush()
+    }
+}
+
+impl<W: Write> GameIO for TuiGameIO<W> {
+    fn read_command(&mut self, board: &BoardState) -> anyhow::Result<Command> {
+        loop {
+            match event::read()? {
+                Event::Key(key) => {
+                    if let Some(command) = key_command(key, &mut self.cursor, board.size()) {
+                        return Ok(command);
+                    }
+                }
+                Event::Resize(..) => {}
+                _ => continue,
+            }
+            self.draw()?;
+        }
+    }
+
+    fn show_board(&mut self, board: &BoardState) -> anyhow::Result<()> {
+        // A new game, or a board of another size, starts from the middle
+        if board.move_count() == 0 || board.size() != self.board.size() {
+            self.cursor = Cursor::new(board.size());
+        }
+        self.board = board.clone();
+        self.draw()?;
+        Ok(())
+    }
+
+    fn show_message(&mut self, message: &str) -> anyhow::Result<()> {
+        self.messages.push(message.to_owned());
+        let excess = self.messages.len().saturating_sub(MESSAGES);
+        self.messages.drain(..excess);
+        self.draw()?;
+        Ok(())
+    }
+
+    /// Moves the cursor onto the suggested tile
+    fn show_hint(&mut self, hint: Option<(usize, usize)>) -> anyhow::Result<()> {
+        match hint {
+            Some(coords) => {
+                self.cursor.jump(coords, self.board.size());
+                self.show_message(&self.language.text(Message::TryHere))
+            }
+            None => self.show_message(&self.language.text(Message::NoHint)),
+        }
+    }
+
+    /// Does nothing, since the cursor replaces typed coordinates
+    fn show_coordinate_help(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn language(&self) -> Language {
+        self.language
+    }
+}
+
+impl<W: Write> Drop for TuiGameIO<W> {
+    fn drop(&mut self) {
+        // There is nowhere left to report errors restoring the terminal
+        let _ = execute!(self.output, Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+        let _ = writeln!(
+            self.output,
+            "\n{}\n",
+            self.board.render().with_highlight(true)
+        );
+        for message in self.messages.iter() {
+            let _ = writeln!(self.output, "{}", message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn press(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    mod cursor {
+        use super::*;
+
+        #[test]
+        fn new() {
+            assert_eq!(Cursor::new(3).coords(), (1, 1));
+            assert_eq!(Cursor::new(4).coords(), (2, 2));
+            assert_eq!(Cursor::new(1).coords(), (0, 0));
+        }
+
+        #[test]
+        fn step() {
+            let mut cursor = Cursor::new(3);
+            cursor.step(Direction::Up, 3);
+            assert_eq!(cursor.coords(), (1, 0));
+            cursor.step(Direction::Up, 3);
+            assert_eq!(cursor.coords(), (1, 0));
+            cursor.step(Direction::Left, 3);
+            cursor.step(Direction::Left, 3);
+            assert_eq!(cursor.coords(), (0, 0));
+            for _ in 0..5 {
+                cursor.step(Direction::Right, 3);
+                cursor.step(Direction::Down, 3);
+            }
+            assert_eq!(cursor.coords(), (2, 2));
+
+            let mut cursor = Cursor::new(1);
+            for &direction in [
+                Direction::Up,
+                Direction::Down,
+                Direction::Left,
+                Direction::Right,
+            ]
+            .iter()
+            {
+                cursor.step(direction, 1);
+                assert_eq!(cursor.coords(), (0, 0));
+            }
+        }
+
+        #[test]
+        fn jump() {
+            let mut cursor = Cursor::new(5);
+            cursor.jump((4, 0), 5);
+            assert_eq!(cursor.coords(), (4, 0));
+            cursor.jump(cursor.coords(), 3);
+            assert_eq!(cursor.coords(), (2, 0));
+        }
+    }
+
+    #[test]
+    fn key_command() {
+        let mut cursor = Cursor::new(3);
+        assert_eq!(
+            super::key_command(press(KeyCode::Left), &mut cursor, 3),
+            None
+        );
+        assert_eq!(
+            super::key_command(press(KeyCode::Down), &mut cursor, 3),
+            None
+        );
+        assert_eq!(
+            super::key_command(press(KeyCode::Down), &mut cursor, 3),
+            None
+        );
+        assert_eq!(cursor.coords(), (0, 2));
+        assert_eq!(
+            super::key_command(press(KeyCode::Enter), &mut cursor, 3),
+            Some(Command::Move((0, 2)))
+        );
+        assert_eq!(
+            super::key_command(press(KeyCode::Char(' ')), &mut cursor, 3),
+            Some(Command::Move((0, 2)))
+        );
+
+        for (code, command) in [
+            (KeyCode::Char('h'), Command::Hint),
+            (KeyCode::Char('R'), Command::Restart),
+            (KeyCode::Char('q'), Command::Quit),
+            (KeyCode::Esc, Command::Quit),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                super::key_command(press(*code), &mut cursor, 3),
+                Some(command.clone())
+            );
+        }
+        assert_eq!(
+            super::key_command(
+                KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL),
+                &mut cursor,
+                3
+            ),
+            Some(Command::Quit)
+        );
+        assert_eq!(
+            super::key_command(press(KeyCode::Char('x')), &mut cursor, 3),
+            None
+        );
+
+        let mut release = press(KeyCode::Up);
+        release.kind = KeyEventKind::Release;
+        assert_eq!(super::key_command(release, &mut cursor, 3), None);
+        assert_eq!(cursor.coords(), (0, 2));
+    }
+}
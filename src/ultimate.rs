@@ -0,0 +1,267 @@
+//! Ultimate tic-tac-toe, played on a 3 by 3 grid of standard boards
+
+use crate::state::{BoardState, GameError, GameResult, Player, BOARD_SIZE};
+
+/// A game of ultimate tic-tac-toe. Each move is played on one of nine local
+/// boards, and winning a local board claims its tile on the meta board, which
+/// decides the game. The tile a move takes on its local board sends the
+/// opponent to the local board in the same place, unless that board is
+/// finished, in which case they may play on any board still in progress.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UltimateBoard {
+    /// The local boards in row-major order
+    locals: Vec<BoardState>,
+    /// Each local board's winner, in the tile the board occupies
+    meta: BoardState,
+    next: Player,
+    /// The local board the next move must be played on, if any
+    sent_to: Option<(usize, usize)>,
+}
+
+impl UltimateBoard {
+    /// Creates an empty game, in which X may start on any board
+    pub fn new() -> Self {
+        UltimateBoard {
+            locals: vec![BoardState::new(); BOARD_SIZE * BOARD_SIZE],
+            meta: BoardState::new(),
+            next: Player::X,
+            sent_to: None,
+        }
+    }
+
+    /// Plays the next player's piece at `small` on the local board at `big`,
+    /// failing if the game is over, the next move was sent to another board,
+    /// or the move is illegal on its local board
+    pub fn play(
+        &mut self,
+        big: (usize, usize),
+        small: (usize, usize),
+    ) -> Result<&mut Self, GameError> {
+        if self.status() != GameResult::InProgress {
+            return Err(GameError::GameOver);
+        }
+
+        let (x, y) = big;
+        if x >= BOARD_SIZE || y >= BOARD_SIZE {
+            return Err(GameError::OutOfBounds { x, y });
+        }
+        match self.sent_to {
+            Some((x, y)) if (x, y) != big => return Err(GameError::WrongBoard { x, y }),
+            _ if self.local(big).status() != GameResult::InProgress => {
+                return Err(GameError::BoardFinished { x, y })
+            }
+            _ => {}
+        }
+
+        let mark = self.next.into();
+        let local = &mut self.locals[x + y * BOARD_SIZE];
+        local.play_mark(small, mark)?;
+        if local.won() == Some(self.next) {
+            self.meta[big] = mark;
+        }
+
+        self.next = self.next.opponent();
+        self.sent_to = match self.local(small).status() {
+            GameResult::InProgress => Some(small),
+            _ => None,
+        };
+        Ok(self)
+    }
+
+    /// The local board at `big`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `big` is off the 3 by 3 grid of boards.
+    pub fn local(&self, (x, y): (usize, usize)) -> &BoardState {
+        assert!(
+            x < BOARD_SIZE && y < BOARD_SIZE,
+            "no board at ({}, {})",
+            x,
+            y
+        );
+        &self.locals[x + y * BOARD_SIZE]
+    }
+
+    /// The board of local winners, holding each winner's mark in the tile of
+    /// the local board they won. Drawn and unfinished boards are empty.
+    pub fn meta(&self) -> &BoardState {
+        &self.meta
+    }
+
+    pub fn next(&self) -> Player {
+        self.next
+    }
+
+    /// The local board the next move must be played on, or `None` if the
+    /// next player may choose any board still in progress
+    pub fn sent_to(&self) -> Option<(usize, usize)> {
+        self.sent_to
+    }
+
+    /// Iterates over every legal move as a pair of the local board and the
+    /// tile on it, both in row-major order
+    pub fn available_moves(&self) -> impl Iterator<Item = ((usize, usize), (usize, usize))> + '_ {
+        let in_progress = self.status() == GameResult::InProgress;
+        (0..BOARD_SIZE)
+            .flat_map(|y| (0..BOARD_SIZE).map(move |x| (x, y)))
+            .filter(move |&big| {
+                in_progress
+                    && self.sent_to.is_none_or(|sent_to| sent_to == big)
+                    && self.local(big).status() == GameResult::InProgress
+            })
+            .flat_map(move |big| {
+                self.local(big)
+                    .available_moves()
+                    .map(move |small| (big, small))
+            })
+    }
+
+    /// Won by a line of local boards, or drawn once every local board is
+    /// finished without one
+    pub fn status(&self) -> GameResult {
+        if let Some(player) = self.meta.won() {
+            GameResult::Win(player)
+        } else if self
+            .locals
+            .iter()
+            .all(|local| local.status() != GameResult::InProgress)
+        {
+            GameResult::Draw
+        } else {
+            GameResult::InProgress
+        }
+    }
+}
+
+impl Default for UltimateBoard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::state::TileState;
+
+    /// A game X wins along the bottom row of local boards. X wins the middle
+    /// bottom board on move 11, O wins the center on move 16, and move 18
+    /// sends O to a finished board.
+    const MOVES: [((usize, usize), (usize, usize)); 19] = [
+        ((1, 2), (0, 0)),
+        ((0, 0), (2, 2)),
+        ((2, 2), (1, 1)),
+        ((1, 1), (2, 2)),
+        ((2, 2), (1, 2)),
+        ((1, 2), (1, 1)),
+        ((1, 1), (1, 1)),
+        ((1, 1), (1, 2)),
+        ((1, 2), (0, 2)),
+        ((0, 2), (1, 2)),
+        ((1, 2), (0, 1)),
+        ((0, 1), (2, 2)),
+        ((2, 2), (1, 0)),
+        ((1, 0), (0, 2)),
+        ((0, 2), (1, 1)),
+        ((1, 1), (0, 2)),
+        ((0, 2), (2, 1)),
+        ((2, 1), (2, 2)),
+        ((0, 2), (0, 1)),
+    ];
+
+    /// Plays the first `count` of [`MOVES`]
+    fn play_moves(count: usize) -> UltimateBoard {
+        let mut board = UltimateBoard::new();
+        for &(big, small) in MOVES[..count].iter() {
+            board.play(big, small).unwrap();
+        }
+        board
+    }
+
+    #[test]
+    fn new() {
+        let board = UltimateBoard::new();
+        assert_eq!(board.next(), Player::X);
+        assert_eq!(board.sent_to(), None);
+        assert_eq!(board.status(), GameResult::InProgress);
+        assert_eq!(board.available_moves().count(), 81);
+        assert_eq!(board, UltimateBoard::default());
+    }
+
+    #[test]
+    fn send_constraint() {
+        let mut board = UltimateBoard::new();
+        board.play((1, 1), (0, 2)).unwrap();
+        assert_eq!(board.local((1, 1))[(0, 2)], TileState::X);
+        assert_eq!(board.next(), Player::O);
+        assert_eq!(board.sent_to(), Some((0, 2)));
+        assert!(board.available_moves().all(|(big, _)| big == (0, 2)));
+        assert_eq!(board.available_moves().count(), 9);
+
+        let before = board.clone();
+        assert_eq!(
+            board.play((1, 1), (1, 1)).unwrap_err(),
+            GameError::WrongBoard { x: 0, y: 2 }
+        );
+        assert_eq!(
+            board.play((3, 0), (1, 1)).unwrap_err(),
+            GameError::OutOfBounds { x: 3, y: 0 }
+        );
+        assert_eq!(
+            board.play((0, 2), (3, 1)).unwrap_err(),
+            GameError::OutOfBounds { x: 3, y: 1 }
+        );
+        assert_eq!(board, before);
+
+        // Being sent back to the same board works like any other
+        board.play((0, 2), (0, 2)).unwrap();
+        assert_eq!(board.sent_to(), Some((0, 2)));
+        assert_eq!(
+            board.play((0, 2), (0, 2)).unwrap_err(),
+            GameError::AlreadyPlayed { x: 0, y: 2 }
+        );
+        assert_eq!(board.local((0, 2))[(0, 2)], TileState::O);
+    }
+
+    #[test]
+    fn local_win() {
+        let board = play_moves(10);
+        assert_eq!(board.meta(), &BoardState::new());
+
+        let board = play_moves(11);
+        assert_eq!(board.local((1, 2)).won(), Some(Player::X));
+        assert_eq!(board.meta()[(1, 2)], TileState::X);
+        assert_eq!(board.meta().won(), None);
+        assert_eq!(board.status(), GameResult::InProgress);
+
+        let board = play_moves(16);
+        assert_eq!(board.meta()[(1, 1)], TileState::O);
+
+        // O is sent to a finished board, so may play on any other
+        let mut board = play_moves(18);
+        assert_eq!(board.sent_to(), None);
+        assert!(board
+            .available_moves()
+            .all(|(big, _)| board.local(big).status() == GameResult::InProgress));
+        assert!(board.available_moves().any(|(big, _)| big == (2, 0)));
+        assert!(!board.available_moves().any(|(big, _)| big == (1, 2)));
+        assert_eq!(
+            board.play((1, 2), (2, 2)).unwrap_err(),
+            GameError::BoardFinished { x: 1, y: 2 }
+        );
+        board.play((2, 0), (0, 0)).unwrap();
+        assert_eq!(board.sent_to(), Some((0, 0)));
+    }
+
+    #[test]
+    fn meta_win() {
+        let mut board = play_moves(18);
+        assert_eq!(board.status(), GameResult::InProgress);
+        board.play(MOVES[18].0, MOVES[18].1).unwrap();
+        assert_eq!(board.meta().won(), Some(Player::X));
+        assert_eq!(board.status(), GameResult::Win(Player::X));
+        assert_eq!(board.available_moves().count(), 0);
+        assert_eq!(board.play((2, 0), (0, 0)).unwrap_err(), GameError::GameOver);
+    }
+}
@@ -0,0 +1,122 @@
+//! Bindings for playing in the browser through WebAssembly.
+//!
+//! The engine itself needs no IO, so a page can drive a [`WasmGame`] from
+//! JavaScript and draw the board however it likes. Tiles are numbered
+//! row-major from 0, as in [`BoardState::to_compact`].
+//!
+//! [`BoardState::to_compact`]: crate::state::BoardState::to_compact
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{ai, game::Game, state::GameResult};
+
+/// A standard game, played one tile index at a time
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct WasmGame {
+    game: Game,
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Plays the tile numbered `index` for the next player, failing with a
+    /// description of why the move is illegal
+    pub fn play_index(&mut self, index: usize) -> Result<(), String> {
+        let size = self.size();
+        if index >= size * size {
+            return Err(format!("tile {} is off the board", index));
+        }
+
+        self.game
+            .play((index % size, index / size))
+            .map(|_| ())
+            .map_err(|error| error.to_string())
+    }
+
+    /// Plays the AI's move for the next player, returning the tile it
+    /// played, or `None` if the game is over
+    pub fn play_ai(&mut self) -> Option<usize> {
+        let (x, y) = ai::strong_move(self.game.board())?;
+        self.game.play((x, y)).ok()?;
+        Some(x + y * self.size())
+    }
+
+    /// The board written as by [`BoardState::to_compact`]
+    ///
+    /// [`BoardState::to_compact`]: crate::state::BoardState::to_compact
+    pub fn board_string(&self) -> String {
+        self.game.board().to_compact()
+    }
+
+    /// `X` or `O` for a win, `draw`, or `in_progress`
+    pub fn result_string(&self) -> String {
+        match self.game.result() {
+            GameResult::InProgress => "in_progress".to_owned(),
+            GameResult::Draw => "draw".to_owned(),
+            GameResult::Win(player) => player.to_string(),
+        }
+    }
+
+    /// `X` or `O`, whoever is to move
+    pub fn next_player(&self) -> String {
+        self.game.next().to_string()
+    }
+
+    pub fn size(&self) -> usize {
+        self.game.board().size()
+    }
+
+    /// Clears the board for a new game
+    pub fn reset(&mut self) {
+        self.game = Game::new();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn play_index() {
+        let mut game = WasmGame::new();
+        assert_eq!(game.board_string(), ".........");
+        assert_eq!(game.next_player(), "X");
+        assert_eq!(game.result_string(), "in_progress");
+
+        for &index in [4, 0, 5, 3].iter() {
+            game.play_index(index).unwrap();
+        }
+        assert_eq!(game.board_string(), "O..OXX...");
+        assert_eq!(
+            game.play_index(4).unwrap_err(),
+            "(1, 1) has already been played"
+        );
+        assert_eq!(game.play_index(9).unwrap_err(), "tile 9 is off the board");
+        assert_eq!(game.next_player(), "X");
+
+        game.play_index(6).unwrap();
+        game.play_index(1).unwrap();
+        game.play_index(2).unwrap();
+        assert_eq!(game.result_string(), "X");
+        assert_eq!(game.play_index(7).unwrap_err(), "the game is already over");
+
+        game.reset();
+        assert_eq!(game, WasmGame::new());
+    }
+
+    #[test]
+    fn play_ai() {
+        let mut game = WasmGame::new();
+        while let Some(index) = game.play_ai() {
+            assert!(index < game.size() * game.size());
+        }
+        assert_eq!(game.result_string(), "draw");
+        assert!(!game.board_string().contains('.'));
+        assert_eq!(game.play_ai(), None);
+    }
+}